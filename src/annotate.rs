@@ -0,0 +1,250 @@
+use dxf::entities::Entity;
+use dxf::XDataItem;
+
+use crate::escape_xml_text;
+
+/// The xdata application AutoCAD stores hyperlinks under.
+const HYPERLINK_APPLICATION: &str = "PE_URL";
+
+/// The URL schemes hyperlinks may use, others like `javascript:` are dropped.
+const LINK_SCHEMES: [&str; 4] = ["http", "https", "mailto", "file"];
+
+/// A hyperlink attached to an entity.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Hyperlink {
+    pub url: String,
+    pub description: Option<String>,
+}
+
+/// Reads the hyperlink AutoCAD stores in an entity's xdata: the url, then a group holding the
+/// description and optionally a named location in the target. Urls that aren't relative and don't
+/// use one of the `LINK_SCHEMES` are ignored.
+pub(crate) fn hyperlink(entity: &Entity) -> Option<Hyperlink> {
+    let x_data = entity
+        .common
+        .x_data
+        .iter()
+        .find(|x_data| x_data.application_name.eq_ignore_ascii_case(HYPERLINK_APPLICATION))?;
+    let mut url = None;
+    let mut details = vec![];
+    for item in &x_data.items {
+        match item {
+            XDataItem::Str(value) if url.is_none() => url = Some(value.clone()),
+            XDataItem::ControlGroup(items) => {
+                details.extend(items.iter().filter_map(|item| match item {
+                    XDataItem::Str(value) if !value.is_empty() => Some(value.clone()),
+                    _ => None,
+                }));
+            }
+            _ => {}
+        }
+    }
+    let mut url = url.filter(|url| !url.is_empty() && is_safe_url(url))?;
+    let mut details = details.into_iter();
+    let description = details.next();
+    if let Some(sub_address) = details.next() {
+        url = format!("{}#{}", url, sub_address);
+    }
+    Some(Hyperlink { url, description })
+}
+
+/// True if a url is relative or uses one of the `LINK_SCHEMES`. Browsers ignore whitespace and control
+/// characters in the scheme, so they are left out before comparing it.
+fn is_safe_url(url: &str) -> bool {
+    let end = url.find(['/', '?', '#']).unwrap_or(url.len());
+    match url[..end].split_once(':') {
+        Some((scheme, _)) => {
+            let scheme: String = scheme.chars().filter(|c| !c.is_ascii_whitespace() && !c.is_control()).collect();
+            LINK_SCHEMES.iter().any(|allowed| scheme.eq_ignore_ascii_case(allowed))
+        }
+        None => true,
+    }
+}
+
+/// Wraps everything an entity wrote to `svg` since `start` in a link.
+pub(crate) fn add_link(svg: &mut String, start: usize, link: &Hyperlink) {
+    if start == svg.len() {
+        return;
+    }
+    let title = link
+        .description
+        .as_ref()
+        .map(|description| format!(r#" xlink:title="{}""#, escape_xml_text(description)))
+        .unwrap_or_default();
+    svg.insert_str(start, &format!(r#"<a xlink:href="{}"{}>"#, escape_xml_text(&link.url), title));
+    svg.push_str("</a>");
+}
+
+/// What the tooltip of each entity shows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Tooltip {
+    /// The entity's layer.
+    Layer,
+    /// The entity's handle.
+    Handle,
+    /// The first string in the entity's xdata for this application.
+    XData(String),
+}
+
+impl Tooltip {
+    /// The tooltip text for an entity, or None if it has nothing to show.
+    pub(crate) fn text(&self, entity: &Entity) -> Option<String> {
+        match self {
+            Tooltip::Layer => Some(entity.common.layer.clone()),
+            Tooltip::Handle => Some(entity.common.handle.as_string()).filter(|_| !entity.common.handle.is_empty()),
+            Tooltip::XData(application) => entity
+                .common
+                .x_data
+                .iter()
+                .filter(|x_data| x_data.application_name.eq_ignore_ascii_case(application))
+                .flat_map(|x_data| &x_data.items)
+                .find_map(|item| match item {
+                    XDataItem::Str(value) => Some(value.clone()),
+                    _ => None,
+                }),
+        }
+        .filter(|text| !text.is_empty())
+    }
+}
+
+/// Adds attributes and a `<title>` tooltip to everything an entity wrote to `svg` since `start`.
+/// A single element gets them itself, several elements are wrapped in a `<g>` that carries them.
+pub(crate) fn annotate(svg: &mut String, start: usize, attributes: &str, title: Option<&str>) {
+    let elements = top_level_elements(&svg[start..]);
+    let attributes = if attributes.is_empty() { String::new() } else { format!(" {}", attributes) };
+    let title = title
+        .map(|title| format!("<title>{}</title>", escape_xml_text(title)))
+        .unwrap_or_default();
+    match elements.as_slice() {
+        [] => {}
+        [element] => {
+            let tag_start = start + element;
+            let name_end = svg[tag_start + 1..]
+                .find(|c: char| c.is_whitespace() || c == '/' || c == '>')
+                .map_or(svg.len(), |end| tag_start + 1 + end);
+            let name = svg[tag_start + 1..name_end].to_string();
+            svg.insert_str(name_end, &attributes);
+            if title.is_empty() {
+                return;
+            }
+            let tag_end = svg[tag_start..].find('>').map_or(svg.len(), |end| tag_start + end);
+            if svg[..tag_end].ends_with('/') {
+                // open up the empty element so it can hold the title
+                let content_start = svg[..tag_end - 1].trim_end().len();
+                svg.replace_range(content_start..=tag_end, &format!(">{}</{}>", title, name));
+            } else {
+                svg.insert_str(tag_end + 1, &title);
+            }
+        }
+        _ => {
+            svg.insert_str(start, &format!("<g{}>{}", attributes, title));
+            svg.push_str("</g>");
+        }
+    }
+}
+
+/// Where the elements of a fragment that aren't nested inside another one start.
+fn top_level_elements(fragment: &str) -> Vec<usize> {
+    let mut elements = vec![];
+    let mut depth = 0usize;
+    let mut position = 0;
+    while let Some(offset) = fragment[position..].find('<') {
+        let tag_start = position + offset;
+        let tag_end = fragment[tag_start..].find('>').map_or(fragment.len(), |end| tag_start + end + 1);
+        let tag = &fragment[tag_start..tag_end];
+        if tag.starts_with("</") {
+            depth = depth.saturating_sub(1);
+        } else {
+            if depth == 0 {
+                elements.push(tag_start);
+            }
+            if !tag.ends_with("/>") {
+                depth += 1;
+            }
+        }
+        position = tag_end;
+    }
+    elements
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dxf::entities::{EntityType, Line};
+    use dxf::XData;
+
+    #[test]
+    fn test_hyperlink() {
+        let mut line = Entity::new(EntityType::Line(Line::default()));
+        assert_eq!(hyperlink(&line), None);
+
+        line.common.x_data.push(XData {
+            application_name: "PE_URL".to_string(),
+            items: vec![
+                XDataItem::Str("https://example.com/spec.pdf".to_string()),
+                XDataItem::ControlGroup(vec![
+                    XDataItem::Str("Door spec".to_string()),
+                    XDataItem::Str("page=4".to_string()),
+                ]),
+            ],
+        });
+        assert_eq!(
+            hyperlink(&line),
+            Some(Hyperlink {
+                url: "https://example.com/spec.pdf#page=4".to_string(),
+                description: Some("Door spec".to_string()),
+            })
+        );
+
+        let mut svg = String::from("<line />");
+        add_link(&mut svg, 0, &hyperlink(&line).unwrap());
+        assert_eq!(
+            svg,
+            r#"<a xlink:href="https://example.com/spec.pdf#page=4" xlink:title="Door spec"><line /></a>"#
+        );
+
+        line.common.x_data[0].items[0] = XDataItem::Str(" Java\tScript:alert(1)".to_string());
+        assert_eq!(hyperlink(&line), None);
+        line.common.x_data[0].items[0] = XDataItem::Str("docs/spec.pdf".to_string());
+        assert_eq!(hyperlink(&line).unwrap().url, "docs/spec.pdf#page=4");
+        assert!(is_safe_url("mailto:office@example.com") && is_safe_url("FILE:///C:/specs/door.pdf"));
+        assert!(!is_safe_url("data:text/html,<script>") && !is_safe_url("vbscript:msgbox"));
+    }
+
+    #[test]
+    fn test_annotate() {
+        let mut svg = String::from("<g>");
+        annotate(&mut svg, 3, r#"id="e1""#, None);
+        assert_eq!(svg, "<g>");
+
+        svg.push_str(r#"<line x1="0" />"#);
+        annotate(&mut svg, 3, r#"id="e1""#, None);
+        assert_eq!(svg, r#"<g><line id="e1" x1="0" />"#);
+
+        let start = svg.len();
+        svg.push_str("<text>A</text><path d=\"M 0 0\"/>");
+        annotate(&mut svg, start, r#"id="e2""#, None);
+        assert!(svg.ends_with(r#"<g id="e2"><text>A</text><path d="M 0 0"/></g>"#));
+    }
+
+    #[test]
+    fn test_tooltips() {
+        let mut svg = String::from(r#"<line x1="0" />"#);
+        annotate(&mut svg, 0, "", Some("WALLS"));
+        assert_eq!(svg, r#"<line x1="0"><title>WALLS</title></line>"#);
+
+        let mut svg = String::from("<text>A</text>");
+        annotate(&mut svg, 0, r#"id="e1""#, Some("1"));
+        assert_eq!(svg, r#"<text id="e1"><title>1</title>A</text>"#);
+
+        let mut line = Entity::new(EntityType::Line(Line::default()));
+        line.common.layer = "WALLS".to_string();
+        line.common.x_data.push(XData {
+            application_name: "QA".to_string(),
+            items: vec![XDataItem::Integer(3), XDataItem::Str("checked".to_string())],
+        });
+        assert_eq!(Tooltip::Layer.text(&line), Some("WALLS".to_string()));
+        assert_eq!(Tooltip::Handle.text(&line), None);
+        assert_eq!(Tooltip::XData("qa".to_string()).text(&line), Some("checked".to_string()));
+    }
+}
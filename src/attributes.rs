@@ -0,0 +1,97 @@
+use dxf::entities::{Entity, EntityType};
+use dxf::{Drawing, Handle};
+
+/// The attributes of a block reference: its tags and the values filled in for them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockAttributes {
+    /// The handle of the INSERT.
+    pub insert_handle: Handle,
+    pub block_name: String,
+    /// The block definition the INSERT is nested in, None for block references in the drawing itself.
+    pub owner_block: Option<String>,
+    /// Tag and value of each attribute, in the order they're stored.
+    pub attributes: Vec<(String, String)>,
+}
+
+impl BlockAttributes {
+    /// The value of the attribute with the given tag, ignoring case like AutoCAD does.
+    pub fn value(&self, tag: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(attribute_tag, _)| attribute_tag.eq_ignore_ascii_case(tag))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/**
+Reads the attributes of every block reference in the drawing, for building schedules like a parts list
+or a room list from the same drawing the SVG is made from. Block references nested in block definitions
+follow those in the drawing itself, listed once per definition however often it's inserted; check
+`owner_block` to count them per reference. Block references without attributes are left out, invisible
+attributes are included.
+*/
+pub fn extract_attributes(drawing: &Drawing) -> Vec<BlockAttributes> {
+    let nested = drawing
+        .blocks()
+        .flat_map(|block| block.entities.iter().map(move |entity| (Some(&block.name), entity)));
+    drawing
+        .entities()
+        .map(|entity| (None, entity))
+        .chain(nested)
+        .filter_map(|(owner, entity)| block_attributes(entity, owner))
+        .filter(|block| !block.attributes.is_empty())
+        .collect()
+}
+
+fn block_attributes(entity: &Entity, owner_block: Option<&String>) -> Option<BlockAttributes> {
+    let EntityType::Insert(insert) = &entity.specific else {
+        return None;
+    };
+    Some(BlockAttributes {
+        insert_handle: entity.common.handle,
+        block_name: insert.name.clone(),
+        owner_block: owner_block.cloned(),
+        attributes: insert
+            .attributes()
+            .map(|attribute| (attribute.attribute_tag.clone(), attribute.value.clone()))
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dxf::entities::{Attribute, Insert};
+    use dxf::Block;
+
+    #[test]
+    fn test_extract_attributes() {
+        let mut drawing = Drawing::new();
+        let mut insert = Insert::default();
+        insert.name = "ROOM".to_string();
+        let attribute = Attribute { attribute_tag: "NUMBER".to_string(), value: "101".to_string(), ..Default::default() };
+        insert.add_attribute(&mut drawing, attribute);
+        let mut entity = Entity::new(EntityType::Insert(insert));
+        entity.common.handle = Handle(0x2A);
+        drawing.add_entity(entity);
+        drawing.add_entity(Entity::new(EntityType::Insert(Insert::default())));
+
+        // a door with a tag, nested in the room's block
+        let mut door = Insert::default();
+        door.name = "DOOR".to_string();
+        let attribute = Attribute { attribute_tag: "TYPE".to_string(), value: "D1".to_string(), ..Default::default() };
+        door.add_attribute(&mut drawing, attribute);
+        let mut room = Block { name: "ROOM".to_string(), ..Default::default() };
+        room.entities.push(Entity::new(EntityType::Insert(door)));
+        drawing.add_block(room);
+
+        let blocks = extract_attributes(&drawing);
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].block_name, "ROOM");
+        assert_eq!(blocks[0].owner_block, None);
+        assert_eq!(blocks[0].value("number"), Some("101"));
+        assert_eq!(blocks[1].block_name, "DOOR");
+        assert_eq!(blocks[1].owner_block.as_deref(), Some("ROOM"));
+        assert_eq!(blocks[1].value("type"), Some("D1"));
+    }
+}
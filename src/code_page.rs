@@ -0,0 +1,78 @@
+use std::path::Path;
+
+use dxf::enums::AcadVersion;
+use dxf::{Drawing, DxfResult};
+use encoding_rs::{
+    Encoding, BIG5, EUC_KR, GBK, IBM866, SHIFT_JIS, WINDOWS_1250, WINDOWS_1251, WINDOWS_1252, WINDOWS_1253,
+    WINDOWS_1254, WINDOWS_1255, WINDOWS_1256, WINDOWS_1257, WINDOWS_1258, WINDOWS_874,
+};
+
+/**
+Loads a dxf file, decoding its text in the code page its `$DWGCODEPAGE` header names.
+Files older than AutoCAD 2007 store text in the ANSI code page of the system that wrote them, which is
+read as Windows-1252 (Western European) unless told otherwise, so Cyrillic, Central European or Asian
+labels come out garbled. Such files are read again in their own code page. Newer files are UTF-8.
+*/
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(path = %path.as_ref().display())))]
+pub fn load_drawing<P: AsRef<Path>>(path: P) -> DxfResult<Drawing> {
+    let drawing = Drawing::load_file(&path)?;
+    match encoding(&drawing.header.drawing_code_page) {
+        Some(encoding) if drawing.header.version < AcadVersion::R2007 && encoding != WINDOWS_1252 => {
+            Drawing::load_file_with_encoding(path, encoding)
+        }
+        _ => Ok(drawing),
+    }
+}
+
+/// The encoding of the 8-bit text a drawing stores outside its DXF strings, like proxy graphics, from its
+/// `$DWGCODEPAGE`. Windows-1252, AutoCAD's default, without a drawing or for code pages that can't be decoded.
+pub(crate) fn drawing_encoding(drawing: Option<&Drawing>) -> &'static Encoding {
+    drawing.and_then(|drawing| encoding(&drawing.header.drawing_code_page)).unwrap_or(WINDOWS_1252)
+}
+
+/// The encoding of a `$DWGCODEPAGE` value like `ANSI_1251`, if it's one that can be decoded.
+fn encoding(code_page: &str) -> Option<&'static Encoding> {
+    let code_page = code_page.trim().to_ascii_uppercase();
+    let number = code_page
+        .strip_prefix("ANSI_")
+        .or_else(|| code_page.strip_prefix("DOS"))?
+        .parse::<u16>()
+        .ok()?;
+    match number {
+        866 => Some(IBM866),
+        874 => Some(WINDOWS_874),
+        932 => Some(SHIFT_JIS),
+        936 => Some(GBK),
+        949 => Some(EUC_KR),
+        950 => Some(BIG5),
+        1250 => Some(WINDOWS_1250),
+        1251 => Some(WINDOWS_1251),
+        1252 => Some(WINDOWS_1252),
+        1253 => Some(WINDOWS_1253),
+        1254 => Some(WINDOWS_1254),
+        1255 => Some(WINDOWS_1255),
+        1256 => Some(WINDOWS_1256),
+        1257 => Some(WINDOWS_1257),
+        1258 => Some(WINDOWS_1258),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encoding() {
+        assert_eq!(encoding("ANSI_1251"), Some(WINDOWS_1251));
+        assert_eq!(encoding("ansi_932"), Some(SHIFT_JIS));
+        assert_eq!(encoding("DOS866"), Some(IBM866));
+        assert_eq!(encoding("ANSI_42"), None);
+        assert_eq!(encoding(""), None);
+
+        let mut drawing = Drawing::new();
+        drawing.header.drawing_code_page = "ANSI_1250".to_string();
+        assert_eq!(drawing_encoding(Some(&drawing)), WINDOWS_1250);
+        assert_eq!(drawing_encoding(None), WINDOWS_1252);
+    }
+}
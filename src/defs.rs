@@ -0,0 +1,52 @@
+use std::sync::Mutex;
+
+/**
+Collects the definitions rendering needs, like block symbols and masks, while the entities are being
+rendered, so they can be written once in a single `<defs>` at the top of the document. Each definition
+is kept by its id, and adding an id that's already there keeps the first.
+*/
+#[derive(Default)]
+pub(crate) struct DefsCollector {
+    definitions: Mutex<Vec<(String, String)>>,
+}
+
+impl DefsCollector {
+    /// Adds the element defining `id`, unless something was defined with that id already.
+    pub(crate) fn add(&self, id: &str, element: String) {
+        let mut definitions = self.definitions.lock().unwrap();
+        if !definitions.iter().any(|(defined, _)| defined == id) {
+            definitions.push((id.to_string(), element));
+        }
+    }
+
+    /// How many definitions there are, to number new ones.
+    pub(crate) fn len(&self) -> usize {
+        self.definitions.lock().unwrap().len()
+    }
+
+    /// Every definition in a `<defs>`, in the order they were added, or nothing if there are none.
+    pub(crate) fn defs_element(&self) -> String {
+        let definitions = self.definitions.lock().unwrap();
+        if definitions.is_empty() {
+            return String::new();
+        }
+        let elements: String = definitions.iter().map(|(_, element)| element.as_str()).collect();
+        format!("<defs>{}</defs>", elements)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defs_collector() {
+        let defs = DefsCollector::default();
+        assert_eq!(defs.defs_element(), "");
+        defs.add("a", "<symbol id=\"a\" />".to_string());
+        defs.add("b", "<mask id=\"b\" />".to_string());
+        defs.add("a", "<symbol id=\"a\">again</symbol>".to_string());
+        assert_eq!(defs.len(), 2);
+        assert_eq!(defs.defs_element(), r#"<defs><symbol id="a" /><mask id="b" /></defs>"#);
+    }
+}
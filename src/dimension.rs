@@ -0,0 +1,718 @@
+use dxf::entities::{
+    AlignedDimension, AngularThreePointDimension, DiameterDimension, DimensionBase, EntityType, OrdinateDimension,
+    RadialDimension, RotatedDimension,
+};
+use dxf::enums::DimensionType;
+use dxf::tables::DimStyle;
+use dxf::{Drawing, Point};
+
+use std::f64::consts::PI;
+use std::fmt::Write;
+
+use crate::{emit, escape_xml_text, geometry, text};
+
+/// How dimensions are drawn, in drawing units. The defaults are those of AutoCAD's metric ISO-25 style.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct DimensionStyle {
+    pub text_height: f64,
+    pub arrow_size: f64,
+    /// The gap between the measured point and the start of its extension line
+    pub extension_offset: f64,
+    /// How far extension lines continue past the dimension line
+    pub extension_extension: f64,
+    /// The gap between the dimension line and its text
+    pub text_gap: f64,
+    /// Decimals of the measured value
+    pub decimals: usize,
+    /// Decimals of measured angles
+    pub angle_decimals: usize,
+    /// Whether text placed automatically sits above the dimension line rather than centered on it
+    pub text_above: bool,
+}
+
+impl Default for DimensionStyle {
+    fn default() -> Self {
+        Self {
+            text_height: 2.5,
+            arrow_size: 2.5,
+            extension_offset: 0.625,
+            extension_extension: 1.25,
+            text_gap: 0.625,
+            decimals: 2,
+            angle_decimals: 0,
+            text_above: true,
+        }
+    }
+}
+
+impl DimensionStyle {
+    /**
+    The style a dimension is drawn with: its DIMSTYLE, or the drawing's current one (`$DIMSTYLE`) when it
+    doesn't name one. Sizes are multiplied by the style's overall scale (DIMSCALE).
+    */
+    pub(crate) fn for_dimension(specific: &EntityType, drawing: Option<&Drawing>) -> Self {
+        match dimension_base(specific) {
+            Some(base) => Self::named(&base.dimension_style_name, drawing),
+            None => Self::default(),
+        }
+    }
+
+    /// The DIMSTYLE with the given name, or the drawing's current one when the name is empty.
+    pub(crate) fn named(name: &str, drawing: Option<&Drawing>) -> Self {
+        let Some(drawing) = drawing else {
+            return Self::default();
+        };
+        let name = if name.is_empty() { &drawing.header.dimension_style_name } else { name };
+        drawing
+            .dim_styles()
+            .find(|dim_style| dim_style.name.eq_ignore_ascii_case(name))
+            .map_or_else(Self::default, Self::from_dim_style)
+    }
+
+    fn from_dim_style(dim_style: &DimStyle) -> Self {
+        let scale = if dim_style.dimensioning_scale_factor > 0.0 { dim_style.dimensioning_scale_factor } else { 1.0 };
+        let default = Self::default();
+        let size = |value: f64, default: f64| if value > 0.0 { value * scale } else { default * scale };
+        Self {
+            text_height: size(dim_style.dimensioning_text_height, default.text_height),
+            arrow_size: size(dim_style.dimensioning_arrow_size, default.arrow_size),
+            extension_offset: dim_style.dimension_extension_line_offset.max(0.0) * scale,
+            extension_extension: dim_style.dimension_extension_line_extension.max(0.0) * scale,
+            // a negative gap asks for a box around the text, the gap itself is the same
+            text_gap: dim_style.dimension_line_gap.abs() * scale,
+            decimals: usize::try_from(dim_style.dimension_unit_tolerance_decimal_places).unwrap_or(default.decimals),
+            angle_decimals: default.angle_decimals,
+            text_above: dim_style.text_above_dimension_line,
+        }
+    }
+}
+
+/// Writes the lines, arrowheads and text of dimensions.
+pub(crate) struct DimensionWriter<'a> {
+    pub style: &'a DimensionStyle,
+    pub stroke_attr: &'a str,
+    pub color: &'a str,
+    pub precision: usize,
+}
+
+impl DimensionWriter<'_> {
+    /// Draws any kind of dimension, other entities are ignored.
+    pub(crate) fn render(&self, specific: &EntityType, svg: &mut String) {
+        match specific {
+            EntityType::RotatedDimension(dimension) => self.rotated(dimension, svg),
+            EntityType::AlignedDimension(dimension) => self.aligned(dimension, svg),
+            EntityType::RadialDimension(dimension) => self.radial(dimension, svg),
+            EntityType::DiameterDimension(dimension) => self.diameter(dimension, svg),
+            EntityType::AngularThreePointDimension(dimension) => self.angular(dimension, svg),
+            EntityType::OrdinateDimension(dimension) => self.ordinate(dimension, svg),
+            _ => {}
+        }
+    }
+
+    /**
+    A rotated (horizontal, vertical or any fixed angle) linear dimension: the distance between the two
+    extension line origins, measured along the dimension line.
+    */
+    pub(crate) fn rotated(&self, dimension: &RotatedDimension, svg: &mut String) {
+        let angle = dimension.rotation_angle.to_radians();
+        self.linear(
+            &dimension.dimension_base,
+            point(&dimension.definition_point_2),
+            point(&dimension.definition_point_3),
+            (angle.cos(), angle.sin()),
+            svg,
+        );
+    }
+
+    /// An aligned dimension: the true distance between the two points, with the dimension line parallel to them.
+    pub(crate) fn aligned(&self, dimension: &AlignedDimension, svg: &mut String) {
+        let first = point(&dimension.definition_point_2);
+        let second = point(&dimension.definition_point_3);
+        let direction = unit(sub(second, first)).unwrap_or((1.0, 0.0));
+        self.linear(&dimension.dimension_base, first, second, direction, svg);
+    }
+
+    /// A radius: a leader from the text to the arc, with the radius prefixed by "R".
+    pub(crate) fn radial(&self, dimension: &RadialDimension, svg: &mut String) {
+        let center = point(&dimension.dimension_base.definition_point_1);
+        let on_arc = point(&dimension.definition_point_2);
+        self.radius_leader(&dimension.dimension_base, center, on_arc, None, dimension.leader_length, "R", svg);
+    }
+
+    /// A diameter: a line across the circle with arrowheads at both ends, and the diameter prefixed by "Ø".
+    pub(crate) fn diameter(&self, dimension: &DiameterDimension, svg: &mut String) {
+        let opposite = point(&dimension.dimension_base.definition_point_1);
+        let on_circle = point(&dimension.definition_point_2);
+        let center = scale(add(opposite, on_circle), 0.5);
+        self.radius_leader(&dimension.dimension_base, center, on_circle, Some(opposite), dimension.leader_length, "Ø", svg);
+    }
+
+    /**
+    An angular dimension, either between three points (a vertex and a point on each side) or between two lines.
+    The angle is drawn as an arc through the dimension's arc location, with extension lines reaching it.
+    */
+    pub(crate) fn angular(&self, dimension: &AngularThreePointDimension, svg: &mut String) {
+        let base = &dimension.dimension_base;
+        let Some([center, first, second, arc_location]) = angular_points(dimension) else {
+            return;
+        };
+        let (Some(first_direction), Some(second_direction)) = (unit(sub(first, center)), unit(sub(second, center))) else {
+            return;
+        };
+        let arc_offset = sub(arc_location, center);
+        let radius = dot(arc_offset, arc_offset).sqrt();
+        if radius <= f64::EPSILON {
+            return;
+        }
+        // of the two arcs between the sides, the dimension is drawn on the one holding the arc location
+        let mut start = first_direction.1.atan2(first_direction.0);
+        let mut end = second_direction.1.atan2(second_direction.0);
+        if !geometry::is_angle_in_arc(arc_offset.1.atan2(arc_offset.0), start, end) {
+            std::mem::swap(&mut start, &mut end);
+        }
+        let sweep = (end - start).rem_euclid(2.0 * PI);
+
+        self.extension_line_to_arc(center, first, radius, svg);
+        self.extension_line_to_arc(center, second, radius, svg);
+        let arc_start = add(center, scale((start.cos(), start.sin()), radius));
+        let arc_end = add(center, scale((end.cos(), end.sin()), radius));
+        write!(
+            svg,
+            r#"<path d="M {:.p$},{:.p$} A {:.p$},{:.p$} 0 {} 1 {:.p$},{:.p$}" {} fill="none" />"#,
+            arc_start.0,
+            arc_start.1,
+            radius,
+            radius,
+            u8::from(sweep > PI),
+            arc_end.0,
+            arc_end.1,
+            self.stroke_attr,
+            p = self.precision
+        ).unwrap();
+        // the arrowheads point along the arc, away from its middle
+        svg.push_str(&self.arrowhead(arc_start, (start.sin(), -start.cos())));
+        svg.push_str(&self.arrowhead(arc_end, (-end.sin(), end.cos())));
+
+        let degrees = format!("{:.d$}°", sweep.to_degrees(), d = self.style.angle_decimals);
+        let text = measurement_text(base, &degrees);
+        let middle = start + sweep / 2.0;
+        let fallback = add(
+            center,
+            scale((middle.cos(), middle.sin()), radius + self.style.text_gap + self.style.text_height / 2.0),
+        );
+        svg.push_str(&self.text(base, fallback, middle.to_degrees() + 90.0, &text));
+    }
+
+    /// The extension line of an angular dimension, along the side through `point` out to the arc.
+    /// Sides already reaching the arc don't need one.
+    fn extension_line_to_arc(&self, center: (f64, f64), point: (f64, f64), radius: f64, svg: &mut String) {
+        let offset = sub(point, center);
+        let distance = dot(offset, offset).sqrt();
+        if distance <= f64::EPSILON || radius <= distance + self.style.extension_offset {
+            return;
+        }
+        let direction = scale(offset, 1.0 / distance);
+        let start = add(point, scale(direction, self.style.extension_offset));
+        let end = add(center, scale(direction, radius + self.style.extension_extension));
+        svg.push_str(&self.line(start, end));
+    }
+
+    /**
+    An ordinate dimension: the X or Y distance of a feature from the datum, written at the end of a leader.
+    The leader leaves the feature straight along the measured axis and jogs sideways halfway when its end
+    isn't in line with the feature.
+    */
+    pub(crate) fn ordinate(&self, dimension: &OrdinateDimension, svg: &mut String) {
+        let base = &dimension.dimension_base;
+        let datum = point(&base.definition_point_1);
+        let feature = point(&dimension.definition_point_2);
+        let leader_end = point(&dimension.definition_point_3);
+        // X datums are measured along the X axis, so their leader runs vertically
+        let (axis, measurement) = if base.is_ordinate_x_type {
+            ((0.0, 1.0), feature.0 - datum.0)
+        } else {
+            ((1.0, 0.0), feature.1 - datum.1)
+        };
+        let along = dot(sub(leader_end, feature), axis);
+        let direction = if along < 0.0 { scale(axis, -1.0) } else { axis };
+        if along.abs() <= self.style.extension_offset {
+            return;
+        }
+
+        let start = add(feature, scale(direction, self.style.extension_offset));
+        let mut points = vec![start];
+        let sideways = sub(sub(leader_end, feature), scale(axis, along));
+        if dot(sideways, sideways).sqrt() > f64::EPSILON {
+            let first_jog = add(feature, scale(axis, along / 3.0));
+            points.push(first_jog);
+            points.push(add(first_jog, add(sideways, scale(axis, along / 3.0))));
+        }
+        points.push(leader_end);
+        write!(
+            svg,
+            r#"<polyline points="{}" {} fill="none" />"#,
+            emit::Points(&points, self.precision),
+            self.stroke_attr
+        ).unwrap();
+
+        let text = measurement_text(base, &format!("{:.d$}", measurement.abs(), d = self.style.decimals));
+        // roughly half the text's length, so it starts just past the leader
+        let half_length = text.chars().count() as f64 * self.style.text_height * 0.3;
+        let fallback = add(leader_end, scale(direction, self.style.text_gap + half_length));
+        let angle = direction.1.atan2(direction.0).to_degrees();
+        svg.push_str(&self.text(base, fallback, angle, &text));
+    }
+
+    /**
+    The line of a radial or diameter dimension, ending with an arrowhead on the curve at `on_curve`.
+    The text sits at its stored position, inside or outside the curve, or `leader_length` outside it when
+    it has none. A diameter also has its `opposite` end drawn with an arrowhead.
+    */
+    #[allow(clippy::too_many_arguments)]
+    fn radius_leader(
+        &self,
+        base: &DimensionBase,
+        center: (f64, f64),
+        on_curve: (f64, f64),
+        opposite: Option<(f64, f64)>,
+        leader_length: f64,
+        prefix: &str,
+        svg: &mut String,
+    ) {
+        let radius_vector = sub(on_curve, center);
+        let radius = dot(radius_vector, radius_vector).sqrt();
+        let outwards = unit(radius_vector).unwrap_or((1.0, 0.0));
+        let leader_length = if leader_length > 0.0 { leader_length } else { 2.0 * self.style.arrow_size };
+        let fallback = add(on_curve, scale(outwards, leader_length + self.style.text_height));
+        let text_position = match point(&base.text_mid_point) {
+            (0.0, 0.0) => fallback,
+            position => position,
+        };
+        let text_offset = sub(text_position, center);
+        let text_outside = dot(text_offset, text_offset).sqrt() > radius;
+
+        // the line starts at the text when it's outside, otherwise at the center or across the circle
+        let start = match (text_outside, opposite) {
+            (true, _) => text_position,
+            (false, Some(opposite)) => opposite,
+            (false, None) => center,
+        };
+        if let (true, Some(opposite)) = (text_outside, opposite) {
+            svg.push_str(&self.line(opposite, on_curve));
+        }
+        svg.push_str(&self.line(start, on_curve));
+        if let Some(direction) = unit(sub(on_curve, start)) {
+            svg.push_str(&self.arrowhead(on_curve, direction));
+        }
+        if let Some(opposite) = opposite {
+            svg.push_str(&self.arrowhead(opposite, scale(outwards, -1.0)));
+        }
+
+        let measurement = if opposite.is_some() { 2.0 * radius } else { radius };
+        let text = measurement_text(base, &format!("{}{:.d$}", prefix, measurement, d = self.style.decimals));
+        let angle = outwards.1.atan2(outwards.0).to_degrees();
+        svg.push_str(&self.text(base, fallback, angle, &text));
+    }
+
+    /// A dimension line through the base's definition point in `direction`, with extension lines
+    /// from the two measured points.
+    fn linear(&self, base: &DimensionBase, first: (f64, f64), second: (f64, f64), direction: (f64, f64), svg: &mut String) {
+        let line_point = point(&base.definition_point_1);
+        let first_foot = project_onto_line(first, line_point, direction);
+        let second_foot = project_onto_line(second, line_point, direction);
+
+        self.extension_line(first, first_foot, svg);
+        self.extension_line(second, second_foot, svg);
+        svg.push_str(&self.line(first_foot, second_foot));
+        if let Some(outwards) = unit(sub(first_foot, second_foot)) {
+            svg.push_str(&self.arrowhead(first_foot, outwards));
+            svg.push_str(&self.arrowhead(second_foot, scale(outwards, -1.0)));
+        }
+
+        let measurement = dot(sub(second, first), direction).abs();
+        let text = measurement_text(base, &format!("{:.d$}", measurement, d = self.style.decimals));
+        let middle = scale(add(first_foot, second_foot), 0.5);
+        // without a stored text position, the text sits above or on the middle of the dimension line
+        let normal = (-direction.1, direction.0);
+        let lift = if self.style.text_above { self.style.text_gap + self.style.text_height / 2.0 } else { 0.0 };
+        let fallback = add(middle, scale(normal, lift));
+        let angle = direction.1.atan2(direction.0).to_degrees();
+        svg.push_str(&self.text(base, fallback, angle, &text));
+    }
+
+    /// An extension line from near the measured point to a little past the dimension line.
+    fn extension_line(&self, origin: (f64, f64), foot: (f64, f64), svg: &mut String) {
+        let offset = sub(foot, origin);
+        let length = dot(offset, offset).sqrt();
+        if length <= self.style.extension_offset {
+            return;
+        }
+        let direction = scale(offset, 1.0 / length);
+        let start = add(origin, scale(direction, self.style.extension_offset));
+        let end = add(foot, scale(direction, self.style.extension_extension));
+        svg.push_str(&self.line(start, end));
+    }
+
+    fn line(&self, start: (f64, f64), end: (f64, f64)) -> String {
+        format!(
+            r#"<line x1="{:.p$}" y1="{:.p$}" x2="{:.p$}" y2="{:.p$}" {} fill="none" />"#,
+            start.0, start.1, end.0, end.1, self.stroke_attr, p = self.precision
+        )
+    }
+
+    /// A closed filled arrowhead with its tip at `tip`, pointing along the unit vector `direction`.
+    pub(crate) fn arrowhead(&self, tip: (f64, f64), direction: (f64, f64)) -> String {
+        let size = self.style.arrow_size;
+        let back = sub(tip, scale(direction, size));
+        let side = scale((-direction.1, direction.0), size / 6.0);
+        let (left, right) = (add(back, side), sub(back, side));
+        format!(
+            r#"<polygon points="{:.p$},{:.p$} {:.p$},{:.p$} {:.p$},{:.p$}" fill="{}" stroke="none" />"#,
+            tip.0, tip.1, left.0, left.1, right.0, right.1, self.color, p = self.precision
+        )
+    }
+
+    /// The dimension's text, centered on its stored position or on `fallback` when it has none.
+    /// `angle` is the direction of the dimension line in degrees, used unless the text has its own rotation.
+    fn text(&self, base: &DimensionBase, fallback: (f64, f64), angle: f64, text: &str) -> String {
+        if text.is_empty() {
+            return String::new();
+        }
+        let position = match point(&base.text_mid_point) {
+            (0.0, 0.0) => fallback,
+            position => position,
+        };
+        let angle = if base.text_rotation_angle != 0.0 { base.text_rotation_angle } else { readable_angle(angle) };
+        // the output's Y axis is flipped, so the text is flipped back to stay upright
+        format!(
+            r#"<text transform="translate({:.p$} {:.p$}) rotate({:.p$}) scale(1 -1)" font-size="{:.p$}" text-anchor="middle" dominant-baseline="central" fill="{}" stroke="none">{}</text>"#,
+            position.0,
+            position.1,
+            angle,
+            self.style.text_height,
+            self.color,
+            escape_xml_text(text),
+            p = self.precision
+        )
+    }
+}
+
+/// The anonymous block holding the dimension as AutoCAD drew it, if it has one.
+pub(crate) fn block_name(specific: &EntityType) -> Option<&str> {
+    dimension_base(specific).map(|base| base.block_name.as_str()).filter(|name| !name.is_empty())
+}
+
+/// The properties shared by every kind of dimension.
+fn dimension_base(specific: &EntityType) -> Option<&DimensionBase> {
+    match specific {
+        EntityType::RotatedDimension(dimension) => Some(&dimension.dimension_base),
+        EntityType::AlignedDimension(dimension) => Some(&dimension.dimension_base),
+        EntityType::RadialDimension(dimension) => Some(&dimension.dimension_base),
+        EntityType::DiameterDimension(dimension) => Some(&dimension.dimension_base),
+        EntityType::AngularThreePointDimension(dimension) => Some(&dimension.dimension_base),
+        EntityType::OrdinateDimension(dimension) => Some(&dimension.dimension_base),
+        _ => None,
+    }
+}
+
+/// The points a dimension is defined by, which its bounding box should include.
+pub(crate) fn definition_points(specific: &EntityType) -> Vec<(f64, f64)> {
+    let (base, points) = match specific {
+        EntityType::RotatedDimension(dimension) => (
+            &dimension.dimension_base,
+            vec![&dimension.definition_point_2, &dimension.definition_point_3],
+        ),
+        EntityType::AlignedDimension(dimension) => (
+            &dimension.dimension_base,
+            vec![&dimension.definition_point_2, &dimension.definition_point_3],
+        ),
+        EntityType::RadialDimension(dimension) => (&dimension.dimension_base, vec![&dimension.definition_point_2]),
+        EntityType::DiameterDimension(dimension) => (&dimension.dimension_base, vec![&dimension.definition_point_2]),
+        EntityType::AngularThreePointDimension(dimension) => (
+            &dimension.dimension_base,
+            vec![
+                &dimension.definition_point_2,
+                &dimension.definition_point_3,
+                &dimension.definition_point_4,
+                &dimension.definition_point_5,
+            ],
+        ),
+        EntityType::OrdinateDimension(dimension) => (
+            &dimension.dimension_base,
+            vec![&dimension.definition_point_2, &dimension.definition_point_3],
+        ),
+        _ => return vec![],
+    };
+    std::iter::once(&base.definition_point_1).chain(points).map(point).collect()
+}
+
+/**
+The vertex, a point on each side and the arc location of an angular dimension.
+Three point dimensions store the vertex, two line dimensions store both lines and the vertex is where they meet.
+*/
+fn angular_points(dimension: &AngularThreePointDimension) -> Option<[(f64, f64); 4]> {
+    let base = &dimension.dimension_base;
+    if base.dimension_type != DimensionType::Angular {
+        return Some([
+            point(&dimension.definition_point_4),
+            point(&dimension.definition_point_2),
+            point(&dimension.definition_point_3),
+            point(&base.definition_point_1),
+        ]);
+    }
+    // the first line runs through the 13 and 14 points, the second from the 15 to the 10 point
+    let (first_start, first_end) = (point(&dimension.definition_point_2), point(&dimension.definition_point_3));
+    let (second_start, second_end) = (point(&dimension.definition_point_4), point(&base.definition_point_1));
+    let first_direction = sub(first_end, first_start);
+    let second_direction = sub(second_end, second_start);
+    let denominator = first_direction.0 * second_direction.1 - first_direction.1 * second_direction.0;
+    if denominator.abs() <= f64::EPSILON {
+        return None;
+    }
+    let between = sub(second_start, first_start);
+    let t = (between.0 * second_direction.1 - between.1 * second_direction.0) / denominator;
+    let vertex = add(first_start, scale(first_direction, t));
+    // each side points from the vertex to the far end of its line
+    let far_end = |start: (f64, f64), end: (f64, f64)| {
+        let (start_offset, end_offset) = (sub(start, vertex), sub(end, vertex));
+        if dot(start_offset, start_offset) > dot(end_offset, end_offset) { start } else { end }
+    };
+    Some([
+        vertex,
+        far_end(first_start, first_end),
+        far_end(second_start, second_end),
+        point(&dimension.definition_point_5),
+    ])
+}
+
+/**
+The text of a dimension. An empty override shows the measurement, `<>` in an override is replaced
+by it, and a single space hides the text. `%%` codes like `%%c` are decoded.
+*/
+fn measurement_text(base: &DimensionBase, value: &str) -> String {
+    match base.text.as_str() {
+        "" => value.to_string(),
+        " " => String::new(),
+        custom => text::plain_text(&custom.replace("<>", value)),
+    }
+}
+
+/// Turns an angle in degrees so text along it doesn't read upside down.
+fn readable_angle(angle: f64) -> f64 {
+    let angle = angle.rem_euclid(360.0);
+    if angle > 90.0 && angle <= 270.0 {
+        angle - 180.0
+    } else if angle > 270.0 {
+        angle - 360.0
+    } else {
+        angle
+    }
+}
+
+fn point(point: &Point) -> (f64, f64) {
+    (point.x, point.y)
+}
+
+fn add(a: (f64, f64), b: (f64, f64)) -> (f64, f64) {
+    (a.0 + b.0, a.1 + b.1)
+}
+
+fn sub(a: (f64, f64), b: (f64, f64)) -> (f64, f64) {
+    (a.0 - b.0, a.1 - b.1)
+}
+
+fn scale(a: (f64, f64), factor: f64) -> (f64, f64) {
+    (a.0 * factor, a.1 * factor)
+}
+
+fn dot(a: (f64, f64), b: (f64, f64)) -> f64 {
+    a.0 * b.0 + a.1 * b.1
+}
+
+fn unit(a: (f64, f64)) -> Option<(f64, f64)> {
+    let length = dot(a, a).sqrt();
+    (length > f64::EPSILON).then(|| scale(a, 1.0 / length))
+}
+
+/// The foot of the perpendicular from `point` to the line through `origin` along the unit `direction`.
+fn project_onto_line(point: (f64, f64), origin: (f64, f64), direction: (f64, f64)) -> (f64, f64) {
+    add(origin, scale(direction, dot(sub(point, origin), direction)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn writer(style: &DimensionStyle) -> DimensionWriter<'_> {
+        DimensionWriter { style, stroke_attr: r#"stroke="black""#, color: "black", precision: 3 }
+    }
+
+    #[test]
+    fn test_rotated_dimension() {
+        let mut dimension = RotatedDimension {
+            definition_point_2: Point::new(0.0, 0.0, 0.0),
+            definition_point_3: Point::new(30.0, 5.0, 0.0),
+            ..Default::default()
+        };
+        dimension.dimension_base.definition_point_1 = Point::new(30.0, 10.0, 0.0);
+        let style = DimensionStyle::default();
+        let mut svg = String::new();
+        writer(&style).rotated(&dimension, &mut svg);
+
+        // extension lines start offset from the points and run past the dimension line at y = 10
+        assert!(svg.contains(r#"<line x1="0.000" y1="0.625" x2="0.000" y2="11.250""#));
+        assert!(svg.contains(r#"<line x1="30.000" y1="5.625" x2="30.000" y2="11.250""#));
+        assert!(svg.contains(r#"<line x1="0.000" y1="10.000" x2="30.000" y2="10.000""#));
+        assert_eq!(svg.matches("<polygon").count(), 2);
+        assert!(svg.contains(r#"<polygon points="0.000,10.000 2.500,9.583 2.500,10.417""#));
+        assert!(svg.contains(r#"translate(15.000 11.875) rotate(0.000) scale(1 -1)"#));
+        assert!(svg.contains(">30.00</text>"));
+
+        dimension.rotation_angle = 90.0;
+        dimension.dimension_base.text = "<> mm".to_string();
+        let mut svg = String::new();
+        writer(&style).rotated(&dimension, &mut svg);
+        assert!(svg.contains(">5.00 mm</text>"));
+    }
+
+    #[test]
+    fn test_aligned_dimension() {
+        let mut dimension = AlignedDimension {
+            definition_point_2: Point::new(0.0, 0.0, 0.0),
+            definition_point_3: Point::new(30.0, 40.0, 0.0),
+            ..Default::default()
+        };
+        // 10 units from the measured points, to their upper left
+        dimension.dimension_base.definition_point_1 = Point::new(22.0, 46.0, 0.0);
+        let style = DimensionStyle::default();
+        let mut svg = String::new();
+        writer(&style).aligned(&dimension, &mut svg);
+
+        assert!(svg.contains(r#"<line x1="-8.000" y1="6.000" x2="22.000" y2="46.000""#));
+        assert!(svg.contains("rotate(53.130)"));
+        assert!(svg.contains(">50.00</text>"));
+    }
+
+    #[test]
+    fn test_radial_and_diameter_dimensions() {
+        let mut radial = RadialDimension {
+            definition_point_2: Point::new(3.0, 4.0, 0.0),
+            ..Default::default()
+        };
+        radial.dimension_base.text_mid_point = Point::new(6.0, 8.0, 0.0);
+        let style = DimensionStyle::default();
+        let mut svg = String::new();
+        writer(&style).radial(&radial, &mut svg);
+        // the text is outside, so the leader runs from it to the arc
+        assert!(svg.contains(r#"<line x1="6.000" y1="8.000" x2="3.000" y2="4.000""#));
+        assert!(svg.contains(r#"<polygon points="3.000,4.000 "#));
+        assert!(svg.contains(">R5.00</text>"));
+
+        let mut diameter = DiameterDimension {
+            definition_point_2: Point::new(5.0, 0.0, 0.0),
+            ..Default::default()
+        };
+        diameter.dimension_base.definition_point_1 = Point::new(-5.0, 0.0, 0.0);
+        diameter.dimension_base.text_mid_point = Point::new(1.0, 1.0, 0.0);
+        let mut svg = String::new();
+        writer(&style).diameter(&diameter, &mut svg);
+        assert!(svg.contains(r#"<line x1="-5.000" y1="0.000" x2="5.000" y2="0.000""#));
+        assert_eq!(svg.matches("<polygon").count(), 2);
+        assert!(svg.contains(">Ø10.00</text>"));
+    }
+
+    #[test]
+    fn test_angular_dimension() {
+        let mut dimension = AngularThreePointDimension {
+            definition_point_2: Point::new(10.0, 0.0, 0.0),
+            definition_point_3: Point::new(0.0, 10.0, 0.0),
+            ..Default::default()
+        };
+        dimension.dimension_base.definition_point_1 = Point::new(3.0, 4.0, 0.0);
+        let style = DimensionStyle::default();
+        let mut svg = String::new();
+        writer(&style).angular(&dimension, &mut svg);
+        assert!(svg.contains(r#"<path d="M 5.000,0.000 A 5.000,5.000 0 0 1 0.000,5.000""#));
+        assert_eq!(svg.matches("<polygon").count(), 2);
+        assert!(svg.contains(">90°</text>"));
+
+        // two lines meeting at (0, 0) with the arc on the reflex side
+        dimension.dimension_base.dimension_type = DimensionType::Angular;
+        dimension.definition_point_2 = Point::new(1.0, 0.0, 0.0);
+        dimension.definition_point_3 = Point::new(10.0, 0.0, 0.0);
+        dimension.definition_point_4 = Point::new(2.0, 2.0, 0.0);
+        dimension.dimension_base.definition_point_1 = Point::new(10.0, 10.0, 0.0);
+        dimension.definition_point_5 = Point::new(-4.0, -3.0, 0.0);
+        let mut svg = String::new();
+        writer(&style).angular(&dimension, &mut svg);
+        assert!(svg.contains(r#"A 5.000,5.000 0 1 1 5.000,0.000""#));
+        assert!(svg.contains(">315°</text>"));
+    }
+
+    #[test]
+    fn test_ordinate_dimension() {
+        let mut dimension = OrdinateDimension {
+            definition_point_2: Point::new(12.0, 5.0, 0.0),
+            definition_point_3: Point::new(15.0, 20.0, 0.0),
+            ..Default::default()
+        };
+        dimension.dimension_base.is_ordinate_x_type = true;
+        let style = DimensionStyle::default();
+        let mut svg = String::new();
+        writer(&style).ordinate(&dimension, &mut svg);
+        assert!(svg.contains(r#"<polyline points="12.000,5.625 12.000,10.000 15.000,15.000 15.000,20.000""#));
+        assert!(svg.contains("rotate(90.000)"));
+        assert!(svg.contains(">12.00</text>"));
+
+        dimension.dimension_base.is_ordinate_x_type = false;
+        dimension.definition_point_3 = Point::new(30.0, 5.0, 0.0);
+        let mut svg = String::new();
+        writer(&style).ordinate(&dimension, &mut svg);
+        assert!(svg.contains(r#"<polyline points="12.625,5.000 30.000,5.000""#));
+        assert!(svg.contains(">5.00</text>"));
+    }
+
+    #[test]
+    fn test_dimension_style() {
+        let mut drawing = Drawing::new();
+        drawing.add_dim_style(DimStyle {
+            name: "ARCH".to_string(),
+            dimensioning_scale_factor: 10.0,
+            dimensioning_text_height: 0.18,
+            dimensioning_arrow_size: 0.1,
+            dimension_extension_line_offset: 0.0625,
+            dimension_extension_line_extension: 0.18,
+            dimension_line_gap: -0.09,
+            dimension_unit_tolerance_decimal_places: 4,
+            ..Default::default()
+        });
+        drawing.header.dimension_style_name = "arch".to_string();
+
+        let mut dimension = RotatedDimension::default();
+        let style = DimensionStyle::for_dimension(&EntityType::RotatedDimension(dimension.clone()), Some(&drawing));
+        assert!((style.text_height - 1.8).abs() < 1e-9);
+        assert!((style.arrow_size - 1.0).abs() < 1e-9);
+        assert!((style.text_gap - 0.9).abs() < 1e-9);
+        assert_eq!(style.decimals, 4);
+        assert!(!style.text_above);
+
+        dimension.dimension_base.dimension_style_name = "Standard".to_string();
+        let specific = EntityType::RotatedDimension(dimension);
+        assert_eq!(DimensionStyle::for_dimension(&specific, Some(&drawing)), DimensionStyle::default());
+        assert_eq!(DimensionStyle::for_dimension(&EntityType::Line(Default::default()), Some(&drawing)), DimensionStyle::default());
+    }
+
+    #[test]
+    fn test_measurement_text() {
+        let mut base = DimensionBase::default();
+        assert_eq!(measurement_text(&base, "R12.35"), "R12.35");
+        base.text = "approx. <>".to_string();
+        assert_eq!(measurement_text(&base, "12.3"), "approx. 12.3");
+        base.text = "%%c<>".to_string();
+        assert_eq!(measurement_text(&base, "12.3"), "Ø12.3");
+        base.text = " ".to_string();
+        assert_eq!(measurement_text(&base, "12.3"), "");
+        assert_eq!(readable_angle(180.0), 0.0);
+        assert_eq!(readable_angle(-45.0), -45.0);
+    }
+}
@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+
+use dxf::entities::Entity;
+use dxf::objects::ObjectType;
+use dxf::Drawing;
+
+/**
+Sorts entities into the order AutoCAD draws them in, so later entities are painted on top.
+Draw order changes ("bring to front", "send to back") are stored in SORTENTSTABLE objects, which
+give an entity a sort handle to be drawn at instead of its own handle. Without any table the
+file order is kept. Entities without a handle, like loose ones built in code, stay where they are.
+*/
+pub(crate) fn sort_by_draw_order(entities: &mut [&Entity], drawing: &Drawing) {
+    let sort_handles: HashMap<u64, u64> = drawing
+        .objects()
+        .filter_map(|object| match &object.specific {
+            ObjectType::SortentsTable(table) => Some(table),
+            _ => None,
+        })
+        .flat_map(|table| table.entity_handles.iter().zip(&table.sort_items))
+        .map(|(entity, sort_item)| (entity.0, sort_item.0))
+        .collect();
+    if sort_handles.is_empty() {
+        return;
+    }
+    // only the entities with handles are sorted, into the places they took up between the others
+    let places: Vec<usize> = (0..entities.len()).filter(|&i| !entities[i].common.handle.is_empty()).collect();
+    let mut sorted: Vec<&Entity> = places.iter().map(|&i| entities[i]).collect();
+    sorted.sort_by_key(|entity| {
+        let handle = entity.common.handle.0;
+        sort_handles.get(&handle).copied().unwrap_or(handle)
+    });
+    for (place, entity) in places.into_iter().zip(sorted) {
+        entities[place] = entity;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dxf::entities::{EntityType, Line};
+    use dxf::objects::{Object, SortentsTable};
+    use dxf::Handle;
+
+    #[test]
+    fn test_sort_by_draw_order() {
+        let mut drawing = Drawing::new();
+        let entities: Vec<Entity> = (1..=3)
+            .map(|handle| {
+                let mut line = Entity::new(EntityType::Line(Line::default()));
+                line.common.handle = Handle(handle);
+                line
+            })
+            .collect();
+        let mut ordered: Vec<&Entity> = entities.iter().collect();
+        sort_by_draw_order(&mut ordered, &drawing);
+        assert_eq!(ordered.iter().map(|entity| entity.common.handle.0).collect::<Vec<_>>(), [1, 2, 3]);
+
+        // the first entity was brought to the front
+        drawing.add_object(Object {
+            common: Default::default(),
+            specific: ObjectType::SortentsTable(SortentsTable {
+                entity_handles: vec![Handle(1)],
+                sort_items: vec![Handle(0x10)],
+            }),
+        });
+        sort_by_draw_order(&mut ordered, &drawing);
+        assert_eq!(ordered.iter().map(|entity| entity.common.handle.0).collect::<Vec<_>>(), [2, 3, 1]);
+
+        // entities without a handle aren't moved to the front
+        let loose = Entity::new(EntityType::Line(Line::default()));
+        let mut ordered: Vec<&Entity> = vec![&entities[0], &entities[1], &loose, &entities[2]];
+        sort_by_draw_order(&mut ordered, &drawing);
+        assert_eq!(ordered.iter().map(|entity| entity.common.handle.0).collect::<Vec<_>>(), [2, 3, 0, 1]);
+    }
+}
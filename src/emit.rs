@@ -0,0 +1,48 @@
+use std::fmt;
+
+/**
+The points of a polyline or polygon as the value of a `points` attribute, `x,y` pairs separated by spaces
+with `precision` decimals. Written straight into the output with `write!`, without an intermediate string
+for each point.
+*/
+pub(crate) struct Points<'a>(pub &'a [(f64, f64)], pub usize);
+
+impl fmt::Display for Points<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Points(points, precision) = *self;
+        for (i, (x, y)) in points.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{:.p$},{:.p$}", x, y, p = precision)?;
+        }
+        Ok(())
+    }
+}
+
+/// The points of an open polyline as the `d` attribute of a `<path>`, `M` to the first and `L` to the rest.
+pub(crate) struct PathData<'a>(pub &'a [(f64, f64)], pub usize);
+
+impl fmt::Display for PathData<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let PathData(points, precision) = *self;
+        for (i, (x, y)) in points.iter().enumerate() {
+            let command = if i == 0 { "M" } else { " L" };
+            write!(f, "{} {:.p$},{:.p$}", command, x, y, p = precision)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_points() {
+        let points = [(0.0, 1.0), (2.5, -3.25)];
+        assert_eq!(Points(&points, 1).to_string(), "0.0,1.0 2.5,-3.2");
+        assert_eq!(PathData(&points, 0).to_string(), "M 0,1 L 2,-3");
+        assert_eq!(Points(&[], 2).to_string(), "");
+    }
+}
@@ -0,0 +1,67 @@
+/**
+Selects which layers get converted.
+Layer names are compared case-insensitively, like AutoCAD does, and patterns may use
+`*` (any run of characters) and `?` (any single character) wildcards.
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LayerFilter {
+    /// Only convert entities on layers matching one of the patterns.
+    Include(Vec<String>),
+    /// Convert every entity except those on layers matching one of the patterns.
+    Exclude(Vec<String>),
+}
+
+impl LayerFilter {
+    /// Returns true if entities on the given layer should be converted.
+    pub fn allows(&self, layer: &str) -> bool {
+        match self {
+            LayerFilter::Include(patterns) => patterns.iter().any(|pattern| glob_match(pattern, layer)),
+            LayerFilter::Exclude(patterns) => !patterns.iter().any(|pattern| glob_match(pattern, layer)),
+        }
+    }
+}
+
+/// Case-insensitive wildcard match supporting `*` and `?`.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().flat_map(char::to_lowercase).collect();
+    let text: Vec<char> = text.chars().flat_map(char::to_lowercase).collect();
+
+    let (mut p, mut t) = (0, 0);
+    // where to resume if the text doesn't match after the last `*`
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star, star_t)) = backtrack {
+            // let the `*` swallow one more character and try again
+            p = star + 1;
+            t = star_t + 1;
+            backtrack = Some((star, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layer_filter() {
+        let include = LayerFilter::Include(vec!["WALLS".to_string(), "a-door*".to_string()]);
+        assert!(include.allows("walls"));
+        assert!(include.allows("A-DOOR-FRAME"));
+        assert!(!include.allows("A-WINDOW"));
+
+        let exclude = LayerFilter::Exclude(vec!["DIM?".to_string(), "*TEXT*".to_string()]);
+        assert!(!exclude.allows("DIMS"));
+        assert!(!exclude.allows("ROOM-TEXT-LABELS"));
+        assert!(exclude.allows("DIMENSIONS"));
+    }
+}
@@ -0,0 +1,118 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::ShxFont;
+
+/// Stroke fonts shipped with AutoCAD and the web fonts closest to them.
+const SHX_FONTS: &[(&str, &str)] = &[
+    ("txt.shx", "Arial, Helvetica, sans-serif"),
+    ("simplex.shx", "Arial, Helvetica, sans-serif"),
+    ("romans.shx", "Arial, Helvetica, sans-serif"),
+    ("romand.shx", "Arial, Helvetica, sans-serif"),
+    ("isocp.shx", "Arial, Helvetica, sans-serif"),
+    ("isocpeur.shx", "Arial, Helvetica, sans-serif"),
+    ("iso.shx", "Arial, Helvetica, sans-serif"),
+    ("monotxt.shx", "'Courier New', monospace"),
+    ("romanc.shx", "'Times New Roman', serif"),
+    ("romant.shx", "'Times New Roman', serif"),
+    ("complex.shx", "'Times New Roman', serif"),
+    ("italic.shx", "'Times New Roman', serif"),
+    ("gothice.shx", "'Old English Text MT', serif"),
+    ("scripts.shx", "'Brush Script MT', cursive"),
+];
+
+/// The family for stroke fonts that aren't mapped.
+const DEFAULT_FAMILY: &str = "sans-serif";
+
+/**
+Maps the font files of text styles to CSS font families.
+Outline fonts (`.ttf`, `.otf`) are named after their file. AutoCAD's `.shx` stroke fonts aren't
+available in browsers, so they're replaced by similar web fonts: the defaults cover the fonts AutoCAD
+ships with and `insert` adds or overrides others. Text in an `.shx` font loaded with `insert_shx` is
+drawn as the font's strokes instead.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct FontMap {
+    /// families by lower case font file name
+    families: HashMap<String, String>,
+    /// loaded stroke fonts by lower case font file name
+    shx_fonts: BTreeMap<String, ShxFont>,
+}
+
+impl Default for FontMap {
+    fn default() -> Self {
+        FontMap {
+            families: SHX_FONTS
+                .iter()
+                .map(|(file, family)| (file.to_string(), family.to_string()))
+                .collect(),
+            shx_fonts: BTreeMap::new(),
+        }
+    }
+}
+
+impl FontMap {
+    /// Uses `family` (a CSS font-family list) for text in the given font file, like `"simplex.shx"`.
+    pub fn insert(&mut self, font_file: &str, family: &str) {
+        self.families.insert(normalize(font_file), family.to_string());
+    }
+
+    /// Draws text in the given font file, like `"simplex.shx"`, with the strokes of a loaded SHX font.
+    pub fn insert_shx(&mut self, font_file: &str, font: ShxFont) {
+        self.shx_fonts.insert(normalize(font_file), font);
+    }
+
+    /// The loaded SHX font for a font file, if there is one.
+    pub(crate) fn shx(&self, font_file: &str) -> Option<&ShxFont> {
+        self.shx_fonts.get(&normalize(font_file))
+    }
+
+    /// The loaded SHX font defining a shape by name, and the shape's number in it.
+    pub(crate) fn find_shape(&self, name: &str) -> Option<(&ShxFont, u16)> {
+        self.shx_fonts
+            .values()
+            .find_map(|font| font.shape_number(name).map(|number| (font, number)))
+    }
+
+    /// The CSS font family for a font file, or None if the style doesn't name one.
+    pub fn family(&self, font_file: &str) -> Option<String> {
+        if font_file.trim().is_empty() {
+            return None;
+        }
+        let file = normalize(font_file);
+        if let Some(family) = self.families.get(&file) {
+            return Some(family.clone());
+        }
+        match file.rsplit_once('.') {
+            Some((name, "ttf" | "ttc" | "otf")) => Some(format!("'{}'", name)),
+            _ => Some(DEFAULT_FAMILY.to_string()),
+        }
+    }
+}
+
+/// The lower case file name without its directory, with `.shx` added when it has no extension
+/// like AutoCAD does.
+fn normalize(font_file: &str) -> String {
+    let name = font_file.trim().rsplit(['/', '\\']).next().unwrap_or_default().to_lowercase();
+    if name.contains('.') {
+        name
+    } else {
+        format!("{}.shx", name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_font_map() {
+        let mut fonts = FontMap::default();
+        assert_eq!(fonts.family("SIMPLEX").as_deref(), Some("Arial, Helvetica, sans-serif"));
+        assert_eq!(fonts.family(r"C:\Fonts\Verdana.ttf").as_deref(), Some("'verdana'"));
+        assert_eq!(fonts.family("custom.shx").as_deref(), Some("sans-serif"));
+        assert_eq!(fonts.family(""), None);
+
+        fonts.insert("Custom.shx", "'Roboto Mono', monospace");
+        assert_eq!(fonts.family("custom").as_deref(), Some("'Roboto Mono', monospace"));
+    }
+}
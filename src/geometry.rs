@@ -0,0 +1,288 @@
+use std::f64::consts::PI;
+
+/// Returns true if the angle lies on the counterclockwise arc from `start` to `end` (all in radians).
+pub(crate) fn is_angle_in_arc(angle: f64, start: f64, end: f64) -> bool {
+    let mut angle = angle.rem_euclid(2.0 * PI);
+    let start = start.rem_euclid(2.0 * PI);
+    let mut end = end.rem_euclid(2.0 * PI);
+
+    if start > end {
+        end += 2.0 * PI;
+        if angle < start {
+            angle += 2.0 * PI;
+        }
+    }
+
+    angle >= start && angle <= end
+}
+
+/// The most segments a single curve is flattened into, however small the tolerance.
+const MAX_SEGMENTS: usize = 1024;
+
+/// How many straight segments an arc needs so that none strays more than `tolerance` from it.
+pub(crate) fn arc_segments(radius: f64, sweep: f64, tolerance: f64) -> usize {
+    let radius = radius.abs();
+    if !(tolerance > 0.0 && tolerance.is_finite()) {
+        return MAX_SEGMENTS;
+    }
+    if radius <= tolerance {
+        return 1;
+    }
+    // the sagitta of a chord spanning `step` radians is r * (1 - cos(step / 2))
+    let step = 2.0 * (1.0 - tolerance / radius).acos();
+    ((sweep.abs() / step).ceil() as usize).clamp(1, MAX_SEGMENTS)
+}
+
+/**
+How many straight segments a polynomial curve piece needs so that none strays more than `tolerance` from it.
+`second_difference` is the largest `|P[i-1] - 2 P[i] + P[i+1]|` of the piece's control points, which bounds
+how far the curve bends.
+*/
+pub(crate) fn curve_segments(second_difference: f64, degree: usize, tolerance: f64) -> usize {
+    if !(tolerance > 0.0 && tolerance.is_finite()) {
+        return MAX_SEGMENTS;
+    }
+    let degree = degree as f64;
+    let segments = (degree * (degree - 1.0) * second_difference / (8.0 * tolerance)).sqrt().ceil();
+    (segments as usize).clamp(1, MAX_SEGMENTS)
+}
+
+/// The points that determine the bounding box of a counterclockwise arc:
+/// its end points and any of the four axis extremes it passes through.
+pub(crate) fn arc_extremes(center_x: f64, center_y: f64, radius: f64, start: f64, end: f64) -> Vec<(f64, f64)> {
+    let mut points = vec![
+        (center_x + radius * start.cos(), center_y + radius * start.sin()),
+        (center_x + radius * end.cos(), center_y + radius * end.sin()),
+    ];
+    for angle in [0.0, PI / 2.0, PI, 3.0 * PI / 2.0] {
+        if is_angle_in_arc(angle, start, end) {
+            points.push((center_x + radius * angle.cos(), center_y + radius * angle.sin()));
+        }
+    }
+    points
+}
+
+/**
+An ellipse drawn in the XY plane as `center + major_axis cos t + minor_axis sin t`.
+The axes don't have to be perpendicular: an ellipse or circle on a tilted plane projects to one
+whose projected axes are merely conjugate diameters.
+*/
+pub(crate) struct EllipseShape {
+    pub(crate) center: (f64, f64),
+    pub(crate) major_axis: (f64, f64),
+    pub(crate) minor_axis: (f64, f64),
+}
+
+/// The true axes of an [`EllipseShape`], as needed by SVG.
+pub(crate) struct EllipseAxes {
+    pub(crate) radius_x: f64,
+    pub(crate) radius_y: f64,
+    /// The direction of the `radius_x` axis in degrees
+    pub(crate) rotation: f64,
+    /// False if increasing parameters run clockwise
+    pub(crate) counterclockwise: bool,
+    /// The parameter of the shape at which the `radius_x` axis starts
+    pub(crate) parameter_offset: f64,
+}
+
+impl EllipseShape {
+    /// The point at the parametric angle `t`, 0 being the end of the major axis.
+    pub(crate) fn point(&self, t: f64) -> (f64, f64) {
+        (
+            self.center.0 + self.major_axis.0 * t.cos() + self.minor_axis.0 * t.sin(),
+            self.center.1 + self.major_axis.1 * t.cos() + self.minor_axis.1 * t.sin(),
+        )
+    }
+
+    /// Finds the perpendicular axes of the ellipse from its (possibly conjugate) ones.
+    pub(crate) fn axes(&self) -> EllipseAxes {
+        let (a, b) = (self.major_axis, self.minor_axis);
+        // the parameter where the distance from the center is extreme
+        let t = 0.5 * (2.0 * (a.0 * b.0 + a.1 * b.1)).atan2(a.0 * a.0 + a.1 * a.1 - b.0 * b.0 - b.1 * b.1);
+        let axis_x = (a.0 * t.cos() + b.0 * t.sin(), a.1 * t.cos() + b.1 * t.sin());
+        let axis_y = (b.0 * t.cos() - a.0 * t.sin(), b.1 * t.cos() - a.1 * t.sin());
+        EllipseAxes {
+            radius_x: axis_x.0.hypot(axis_x.1),
+            radius_y: axis_y.0.hypot(axis_y.1),
+            rotation: axis_x.1.atan2(axis_x.0).to_degrees(),
+            counterclockwise: a.0 * b.1 - a.1 * b.0 >= 0.0,
+            parameter_offset: t,
+        }
+    }
+
+    /// The points that determine the bounding box of the elliptical arc between the parameters `start` and `end`.
+    pub(crate) fn arc_extremes(&self, start: f64, end: f64) -> Vec<(f64, f64)> {
+        let full = is_full_turn(start, end);
+        let mut points = vec![self.point(start), self.point(end)];
+        // x and y are each extreme where their derivative, -major sin t + minor cos t, is zero
+        let x_extreme = self.minor_axis.0.atan2(self.major_axis.0);
+        let y_extreme = self.minor_axis.1.atan2(self.major_axis.1);
+        for t in [x_extreme, x_extreme + PI, y_extreme, y_extreme + PI] {
+            if full || is_angle_in_arc(t, start, end) {
+                points.push(self.point(t));
+            }
+        }
+        points
+    }
+}
+
+/// True if a counterclockwise sweep from `start` to `end` (in radians) covers the whole turn.
+pub(crate) fn is_full_turn(start: f64, end: f64) -> bool {
+    let sweep = (end - start).rem_euclid(2.0 * PI);
+    sweep < 1e-9 || 2.0 * PI - sweep < 1e-9
+}
+
+/// The circular arc described by a polyline segment with a bulge.
+pub(crate) struct BulgeArc {
+    pub(crate) center_x: f64,
+    pub(crate) center_y: f64,
+    pub(crate) radius: f64,
+    /// Start and end angles in radians, the arc runs counterclockwise from start to end
+    pub(crate) start_angle: f64,
+    pub(crate) end_angle: f64,
+}
+
+/// The bulge of a polyline vertex is the tangent of a quarter of the angle the arc to the next vertex
+/// spans, negative when the arc runs clockwise. Returns None for straight segments.
+pub(crate) fn bulge_arc(x1: f64, y1: f64, x2: f64, y2: f64, bulge: f64) -> Option<BulgeArc> {
+    let chord = (x2 - x1).hypot(y2 - y1);
+    if bulge == 0.0 || chord == 0.0 {
+        return None;
+    }
+    let sagitta = bulge.abs() * chord / 2.0;
+    let radius = chord * (1.0 + bulge * bulge) / (4.0 * bulge.abs());
+
+    // the center lies on the chord's perpendicular bisector, left of the chord for small
+    // counterclockwise arcs and on the other side for arcs over 180 degrees
+    let (normal_x, normal_y) = (-(y2 - y1) / chord, (x2 - x1) / chord);
+    let offset = bulge.signum() * (radius - sagitta);
+    let center_x = (x1 + x2) / 2.0 + normal_x * offset;
+    let center_y = (y1 + y2) / 2.0 + normal_y * offset;
+
+    let from = (y1 - center_y).atan2(x1 - center_x);
+    let to = (y2 - center_y).atan2(x2 - center_x);
+    let (start_angle, end_angle) = if bulge > 0.0 { (from, to) } else { (to, from) };
+    Some(BulgeArc {
+        center_x,
+        center_y,
+        radius,
+        start_angle,
+        end_angle,
+    })
+}
+
+/**
+The part of the line `origin + t * direction`, for `t` from `start` on, that lies inside the box from `min`
+to `max`, found by clipping the range of `t` to each side of the box in turn. An unbounded `start` clips an
+infinite line, a `start` of 0 a ray. None if the line misses the box.
+*/
+pub(crate) fn clip_line(
+    origin: (f64, f64),
+    direction: (f64, f64),
+    start: f64,
+    min: (f64, f64),
+    max: (f64, f64),
+) -> Option<((f64, f64), (f64, f64))> {
+    let (mut t0, mut t1) = (start, f64::INFINITY);
+    for (position, delta, low, high) in [(origin.0, direction.0, min.0, max.0), (origin.1, direction.1, min.1, max.1)] {
+        if delta == 0.0 {
+            if position < low || position > high {
+                return None;
+            }
+            continue;
+        }
+        let (a, b) = ((low - position) / delta, (high - position) / delta);
+        t0 = t0.max(a.min(b));
+        t1 = t1.min(a.max(b));
+    }
+    if t0 > t1 || !t0.is_finite() || !t1.is_finite() {
+        return None;
+    }
+    let at = |t: f64| (origin.0 + t * direction.0, origin.1 + t * direction.1);
+    Some((at(t0), at(t1)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ellipse_arc_extremes() {
+        let ellipse = EllipseShape { center: (1.0, 1.0), major_axis: (0.0, 2.0), minor_axis: (-1.0, 0.0) };
+        let (x, y) = ellipse.point(PI / 2.0);
+        assert_close(x, 0.0);
+        assert_close(y, 1.0);
+
+        // the upper half runs from the top, over the left side, to the bottom
+        let extremes = ellipse.arc_extremes(0.0, PI);
+        let min_x = extremes.iter().map(|p| p.0).fold(f64::INFINITY, f64::min);
+        let max_x = extremes.iter().map(|p| p.0).fold(f64::NEG_INFINITY, f64::max);
+        assert_close(min_x, 0.0);
+        assert_close(max_x, 1.0);
+        assert_eq!(ellipse.arc_extremes(0.0, 2.0 * PI).len(), 6);
+    }
+
+    #[test]
+    fn test_ellipse_axes() {
+        let conjugate = EllipseShape { center: (0.0, 0.0), major_axis: (1.0, 0.0), minor_axis: (1.0, 1.0) }.axes();
+        assert!(conjugate.counterclockwise);
+        // the area, pi * a * b, equals pi times the cross product of conjugate diameters
+        assert_close(conjugate.radius_x * conjugate.radius_y, 1.0);
+
+        let mirrored = EllipseShape { center: (0.0, 0.0), major_axis: (2.0, 0.0), minor_axis: (0.0, -1.0) }.axes();
+        assert!(!mirrored.counterclockwise);
+        assert_close(mirrored.radius_x, 2.0);
+        assert_close(mirrored.radius_y, 1.0);
+        assert_close(mirrored.rotation, 0.0);
+    }
+
+    #[test]
+    fn test_segment_counts() {
+        // a quarter circle of radius 1 flattened to within 0.01 needs 6 chords of about 16 degrees
+        assert_eq!(arc_segments(1.0, PI / 2.0, 0.01), 6);
+        assert_eq!(arc_segments(0.001, 2.0 * PI, 0.01), 1);
+        assert_eq!(arc_segments(1.0, PI, 0.0), MAX_SEGMENTS);
+        assert_eq!(curve_segments(0.0, 3, 0.01), 1);
+        assert_eq!(curve_segments(8.0, 2, 0.01), 15);
+    }
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-9, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn test_clip_line() {
+        let (min, max) = ((0.0, 0.0), (10.0, 10.0));
+        assert_eq!(
+            clip_line((5.0, 5.0), (1.0, 0.0), f64::NEG_INFINITY, min, max),
+            Some(((0.0, 5.0), (10.0, 5.0)))
+        );
+        assert_eq!(clip_line((5.0, 5.0), (1.0, 0.0), 0.0, min, max), Some(((5.0, 5.0), (10.0, 5.0))));
+        assert_eq!(clip_line((12.0, 5.0), (1.0, 0.0), 0.0, min, max), None);
+        assert_eq!(clip_line((5.0, 20.0), (1.0, 0.0), f64::NEG_INFINITY, min, max), None);
+    }
+
+    #[test]
+    fn test_bulge_arc() {
+        // a counterclockwise half circle from (0, 0) to (2, 0) bulges below the chord
+        let arc = bulge_arc(0.0, 0.0, 2.0, 0.0, 1.0).unwrap();
+        assert_close(arc.center_x, 1.0);
+        assert_close(arc.center_y, 0.0);
+        assert_close(arc.radius, 1.0);
+        assert_close(arc.start_angle, PI);
+        assert_close(arc.end_angle, 0.0);
+        let lowest = arc_extremes(arc.center_x, arc.center_y, arc.radius, arc.start_angle, arc.end_angle)
+            .into_iter()
+            .map(|(_, y)| y)
+            .fold(f64::INFINITY, f64::min);
+        assert_close(lowest, -1.0);
+
+        // a clockwise quarter circle
+        let arc = bulge_arc(1.0, 0.0, 0.0, 1.0, -(PI / 8.0).tan()).unwrap();
+        assert_close(arc.center_x, 1.0);
+        assert_close(arc.center_y, 1.0);
+        assert_close(arc.radius, 1.0);
+
+        assert!(bulge_arc(0.0, 0.0, 1.0, 0.0, 0.0).is_none());
+    }
+}
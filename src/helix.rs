@@ -0,0 +1,109 @@
+use std::f64::consts::PI;
+
+use dxf::entities::Helix;
+
+use crate::geometry;
+
+/// The most segments a whole helix is flattened into, however many turns it has.
+const MAX_SEGMENTS: usize = 16 * 1024;
+
+/**
+Flattens a helix into points no further than `tolerance` from it, seen from above. The helix winds
+around its axis from its start point, whose distance from the axis is the base radius, to the top
+radius after all its turns, rising a turn height each turn. Right-handed helixes turn
+counterclockwise looking down the axis towards its base, left-handed ones clockwise.
+*/
+pub(crate) fn helix_points(helix: &Helix, tolerance: f64) -> Vec<(f64, f64)> {
+    let start = &helix.start_point;
+    let base = &helix.axis_base_point;
+    let axis = normalize((helix.axis_vector.x, helix.axis_vector.y, helix.axis_vector.z)).unwrap_or((0.0, 0.0, 1.0));
+    let offset = (start.x - base.x, start.y - base.y, start.z - base.z);
+    let along = dot(offset, axis);
+    let radial = (offset.0 - along * axis.0, offset.1 - along * axis.1, offset.2 - along * axis.2);
+    let base_radius = dot(radial, radial).sqrt();
+    if helix.number_of_turns <= 0.0 {
+        return vec![(start.x, start.y)];
+    }
+    let first = normalize(radial).unwrap_or_else(|| perpendicular(axis));
+    let second = cross(axis, first);
+    let handedness = if helix.is_right_handed { 1.0 } else { -1.0 };
+
+    let turns = helix.number_of_turns;
+    let per_turn = geometry::arc_segments(base_radius.max(helix.radius), 2.0 * PI, tolerance) as f64;
+    let segments = (turns * per_turn).ceil().clamp(1.0, MAX_SEGMENTS as f64) as usize;
+    (0..=segments)
+        .map(|i| {
+            let t = turns * i as f64 / segments as f64;
+            let angle = handedness * 2.0 * PI * t;
+            let radius = base_radius + (helix.radius - base_radius) * t / turns;
+            let height = along + helix.turn_height * t;
+            let (cos, sin) = (radius * angle.cos(), radius * angle.sin());
+            (
+                base.x + height * axis.0 + cos * first.0 + sin * second.0,
+                base.y + height * axis.1 + cos * first.1 + sin * second.1,
+            )
+        })
+        .collect()
+}
+
+fn dot(a: (f64, f64, f64), b: (f64, f64, f64)) -> f64 {
+    a.0 * b.0 + a.1 * b.1 + a.2 * b.2
+}
+
+fn cross(a: (f64, f64, f64), b: (f64, f64, f64)) -> (f64, f64, f64) {
+    (a.1 * b.2 - a.2 * b.1, a.2 * b.0 - a.0 * b.2, a.0 * b.1 - a.1 * b.0)
+}
+
+fn normalize(vector: (f64, f64, f64)) -> Option<(f64, f64, f64)> {
+    let length = dot(vector, vector).sqrt();
+    (length > 1e-12).then(|| (vector.0 / length, vector.1 / length, vector.2 / length))
+}
+
+/// Any direction at right angles to `axis`, for helixes starting on their axis.
+fn perpendicular(axis: (f64, f64, f64)) -> (f64, f64, f64) {
+    let other = if axis.0.abs() < 0.9 { (1.0, 0.0, 0.0) } else { (0.0, 1.0, 0.0) };
+    normalize(cross(other, axis)).unwrap_or((1.0, 0.0, 0.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dxf::{Point, Vector};
+
+    fn assert_close(actual: (f64, f64), expected: (f64, f64)) {
+        assert!(
+            (actual.0 - expected.0).abs() < 1e-9 && (actual.1 - expected.1).abs() < 1e-9,
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_helix_points() {
+        let mut helix = Helix {
+            axis_base_point: Point::new(10.0, 10.0, 0.0),
+            start_point: Point::new(10.0, 11.0, 0.0),
+            axis_vector: Vector::new(0.0, 0.0, 1.0),
+            radius: 3.0,
+            number_of_turns: 1.5,
+            turn_height: 2.0,
+            is_right_handed: true,
+            ..Default::default()
+        };
+        let points = helix_points(&helix, 0.01);
+        assert_close(points[0], (10.0, 11.0));
+        // half a turn more than a full one ends opposite the start, at the top radius
+        assert_close(*points.last().unwrap(), (10.0, 7.0));
+        // a quarter turn in, right-handed helixes have turned counterclockwise
+        let quarter = points.iter().position(|(x, _)| *x < 9.0).unwrap();
+        assert!(points[quarter].1 < 11.0);
+
+        helix.is_right_handed = false;
+        let quarter = helix_points(&helix, 0.01).into_iter().find(|(x, _)| (x - 10.0).abs() > 1.0).unwrap();
+        assert!(quarter.0 > 11.0);
+
+        helix.number_of_turns = 1e9;
+        assert_eq!(helix_points(&helix, 0.01).len(), MAX_SEGMENTS + 1);
+    }
+}
@@ -0,0 +1,232 @@
+use std::fmt::Write;
+use std::path::{Component, Path, PathBuf};
+
+use dxf::entities::Image;
+use dxf::objects::ObjectType;
+use dxf::Drawing;
+
+use crate::escape_xml_text;
+
+/// Where the image is stored and how to refer to it from the SVG.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct ImagePaths<'a> {
+    /// The folder of the DXF file, which relative IMAGEDEF paths start from
+    pub source_dir: Option<&'a Path>,
+    /// The folder the SVG is written to, which the links are rewritten relative to
+    pub output_dir: Option<&'a Path>,
+    /// Whether to read the file and embed it as a `data:` URI instead of linking to it
+    pub embed: bool,
+}
+
+/**
+Renders an IMAGE as an `<image>` linking to the file of its IMAGEDEF. The raster is placed by a matrix
+taking its pixels to the drawing: one pixel along the U vector and one pixel up the V vector, with the
+insertion point at its bottom left corner.
+*/
+pub(crate) fn render_image(image: &Image, drawing: Option<&Drawing>, paths: ImagePaths, precision: usize, svg: &mut String) {
+    let Some(file_path) = drawing
+        .and_then(|drawing| image.get_image_definition(drawing))
+        .and_then(|object| match &object.specific {
+            ObjectType::ImageDefinition(definition) => Some(definition.file_path.as_str()),
+            _ => None,
+        })
+        .filter(|file_path| !file_path.is_empty())
+    else {
+        return;
+    };
+    let (width, height) = (image.image_size.x, image.image_size.y);
+    if width <= 0.0 || height <= 0.0 {
+        return;
+    }
+    // images that can't be read are linked to instead, so they show up once the file is found
+    let href = paths
+        .embed
+        .then(|| data_uri(file_path, paths.source_dir))
+        .flatten()
+        .unwrap_or_else(|| href(file_path, paths));
+    write!(
+        svg,
+        r#"<image href="{}" width="{}" height="{}" preserveAspectRatio="none" transform="{}" />"#,
+        escape_xml_text(&href),
+        width,
+        height,
+        image_transform(image, precision)
+    ).unwrap();
+}
+
+/// The corners of an image in the drawing, starting at its insertion point.
+pub(crate) fn image_corners(image: &Image) -> [(f64, f64); 4] {
+    let (u, v) = (&image.u_vector, &image.v_vector);
+    let (width, height) = (image.image_size.x, image.image_size.y);
+    let (x, y) = (image.location.x, image.location.y);
+    [
+        (x, y),
+        (x + u.x * width, y + u.y * width),
+        (x + u.x * width + v.x * height, y + u.y * width + v.y * height),
+        (x + v.x * height, y + v.y * height),
+    ]
+}
+
+/// The matrix from the pixels of an image, with Y down from its top left corner, to the drawing.
+fn image_transform(image: &Image, precision: usize) -> String {
+    let (u, v) = (&image.u_vector, &image.v_vector);
+    let height = image.image_size.y;
+    format!(
+        "matrix({:.p$} {:.p$} {:.p$} {:.p$} {:.p$} {:.p$})",
+        u.x,
+        u.y,
+        -v.x,
+        -v.y,
+        image.location.x + v.x * height,
+        image.location.y + v.y * height,
+        p = precision
+    )
+}
+
+/**
+The link to an image file. DXF files written on Windows use backslashes, which are turned into slashes.
+With an output folder the path is made relative to it, resolving relative paths from the DXF's folder,
+so the link still works next to the SVG. Absolute paths become `file:` URLs.
+*/
+fn href(file_path: &str, paths: ImagePaths) -> String {
+    let file_path = file_path.replace('\\', "/");
+    let mut path = PathBuf::from(&file_path);
+    // a Windows path read on another system can't be resolved, so it's kept as is
+    let foreign = is_absolute(&file_path) && !path.is_absolute();
+    if let (Some(output_dir), false) = (paths.output_dir, foreign) {
+        if !is_absolute(&file_path) {
+            if let Some(source_dir) = paths.source_dir {
+                path = source_dir.join(path);
+            }
+        }
+        if let (Ok(path_absolute), Ok(output_absolute)) = (std::path::absolute(&path), std::path::absolute(output_dir)) {
+            if let Some(relative) = relative_path(&output_absolute, &path_absolute) {
+                path = relative;
+            }
+        }
+    }
+    let link = path.to_string_lossy().replace('\\', "/").replace(' ', "%20");
+    if is_absolute(&link) {
+        format!("file://{}{}", if link.starts_with('/') { "" } else { "/" }, link)
+    } else {
+        link
+    }
+}
+
+/// The contents of an image file as a base64 `data:` URI, reading relative paths from the DXF's folder.
+fn data_uri(file_path: &str, source_dir: Option<&Path>) -> Option<String> {
+    let file_path = file_path.replace('\\', "/");
+    let path = Path::new(&file_path);
+    let data = match source_dir {
+        Some(source_dir) if path.is_relative() => std::fs::read(source_dir.join(path)),
+        _ => std::fs::read(path),
+    }
+    .ok()?;
+    let extension = path.extension().and_then(|extension| extension.to_str()).unwrap_or_default();
+    Some(format!("data:{};base64,{}", mime_type(extension), base64(&data)))
+}
+
+/// The media type of an image file, by its extension.
+fn mime_type(extension: &str) -> &'static str {
+    match extension.to_ascii_lowercase().as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" | "jpe" => "image/jpeg",
+        "gif" => "image/gif",
+        "bmp" | "dib" | "rle" => "image/bmp",
+        "tif" | "tiff" => "image/tiff",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Encodes bytes as standard, padded base64.
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [chunk[0], chunk.get(1).copied().unwrap_or(0), chunk.get(2).copied().unwrap_or(0)];
+        let triple = u32::from(bytes[0]) << 16 | u32::from(bytes[1]) << 8 | u32::from(bytes[2]);
+        for index in 0..4 {
+            if index <= chunk.len() {
+                encoded.push(ALPHABET[(triple >> (18 - 6 * index) & 0x3F) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// Whether a path is absolute on Unix or Windows, wherever this runs.
+fn is_absolute(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    path.starts_with('/') || (bytes.len() > 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' && bytes[2] == b'/')
+}
+
+/// The path to `target` from the folder `base`, both absolute. None if they don't share a root.
+fn relative_path(base: &Path, target: &Path) -> Option<PathBuf> {
+    let base: Vec<Component> = base.components().filter(|component| *component != Component::CurDir).collect();
+    let target: Vec<Component> = target.components().filter(|component| *component != Component::CurDir).collect();
+    if base.first() != target.first() {
+        return None;
+    }
+    let common = base.iter().zip(&target).take_while(|(a, b)| a == b).count();
+    let mut relative = PathBuf::new();
+    for _ in common..base.len() {
+        relative.push("..");
+    }
+    for component in &target[common..] {
+        relative.push(component);
+    }
+    Some(relative)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dxf::{Point, Vector};
+
+    #[test]
+    fn test_image_transform() {
+        let mut image = Image::default();
+        image.location = Point::new(10.0, 20.0, 0.0);
+        image.u_vector = Vector::new(0.5, 0.0, 0.0);
+        image.v_vector = Vector::new(0.0, 0.5, 0.0);
+        image.image_size = Vector::new(200.0, 100.0, 0.0);
+        assert_eq!(image_transform(&image, 1), "matrix(0.5 0.0 -0.0 -0.5 10.0 70.0)");
+        assert_eq!(image_corners(&image)[2], (110.0, 70.0));
+    }
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+    }
+
+    #[test]
+    fn test_data_uri() {
+        let dir = std::env::temp_dir().join("dxf_to_svg_test_data_uri");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("logo.PNG"), b"foo").unwrap();
+        assert_eq!(data_uri("logo.PNG", Some(&dir)).as_deref(), Some("data:image/png;base64,Zm9v"));
+        assert_eq!(data_uri("missing.png", Some(&dir)), None);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_href() {
+        let paths = ImagePaths {
+            source_dir: Some(Path::new("/plans/dxf")),
+            output_dir: Some(Path::new("/plans/svg")),
+            embed: false,
+        };
+        assert_eq!(href(r"images\site plan.png", paths), "../dxf/images/site%20plan.png");
+        assert_eq!(href("/plans/photos/a.jpg", paths), "../photos/a.jpg");
+        assert_eq!(href(r"C:\images\a.png", ImagePaths::default()), "file:///C:/images/a.png");
+        assert_eq!(href("a.png", ImagePaths::default()), "a.png");
+    }
+}
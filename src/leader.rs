@@ -0,0 +1,80 @@
+use std::fmt::Write;
+
+use dxf::entities::Leader;
+use dxf::enums::LeaderPathType;
+use dxf::Drawing;
+
+use crate::dimension::{DimensionStyle, DimensionWriter};
+use crate::emit::Points;
+use crate::spline;
+
+/**
+Renders a LEADER's path through its vertices, straight or as a spline, with an arrowhead at its first
+vertex sized by the leader's dimension style (DIMASZ times DIMSCALE).
+*/
+pub(crate) fn render_leader(
+    leader: &Leader,
+    drawing: Option<&Drawing>,
+    stroke_attr: &str,
+    color: &str,
+    precision: usize,
+    tolerance: f64,
+    svg: &mut String,
+) {
+    let vertices: Vec<(f64, f64)> = leader.vertices.iter().map(|vertex| (vertex.x, vertex.y)).collect();
+    if vertices.len() < 2 {
+        return;
+    }
+    let points = match leader.path_type {
+        LeaderPathType::Spline => spline::fit_curve(vertices.clone(), false, tolerance),
+        LeaderPathType::StraightLineSegments => vertices.clone(),
+    };
+    write!(
+        svg,
+        r#"<polyline points="{}" {} fill="none" />"#,
+        Points(&points, precision),
+        stroke_attr
+    ).unwrap();
+
+    if !leader.use_arrowheads {
+        return;
+    }
+    // the arrow points back along the first segment of the drawn path
+    let (tip, next) = (points[0], points[1]);
+    let (dx, dy) = (tip.0 - next.0, tip.1 - next.1);
+    let length = dx.hypot(dy);
+    if length <= f64::EPSILON {
+        return;
+    }
+    let style = DimensionStyle::named(&leader.dimension_style_name, drawing);
+    let writer = DimensionWriter { style: &style, stroke_attr, color, precision };
+    svg.push_str(&writer.arrowhead(tip, (dx / length, dy / length)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dxf::Point;
+
+    #[test]
+    fn test_render_leader() {
+        let mut leader = Leader::default();
+        leader.vertices = vec![Point::new(0.0, 0.0, 0.0), Point::new(10.0, 0.0, 0.0), Point::new(12.0, 2.0, 0.0)];
+        leader.use_arrowheads = true;
+        let mut svg = String::new();
+        render_leader(&leader, None, r#"stroke="red""#, "red", 3, 0.01, &mut svg);
+        assert_eq!(
+            svg,
+            concat!(
+                r#"<polyline points="0.000,0.000 10.000,0.000 12.000,2.000" stroke="red" fill="none" />"#,
+                r#"<polygon points="0.000,0.000 2.500,-0.417 2.500,0.417" fill="red" stroke="none" />"#
+            )
+        );
+
+        leader.path_type = LeaderPathType::Spline;
+        let mut svg = String::new();
+        render_leader(&leader, None, r#"stroke="red""#, "red", 3, 0.01, &mut svg);
+        let points = svg.split('"').nth(1).unwrap().split(' ').count();
+        assert!(points > 3);
+    }
+}
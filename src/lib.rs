@@ -1,16 +1,90 @@
-use dxf::entities::{Entity, EntityType};
+mod annotate;
+mod attributes;
+mod code_page;
+mod color;
+mod defs;
+mod dimension;
+mod draw_order;
+mod emit;
+mod filter;
+mod font;
+mod leader;
+mod geometry;
+mod helix;
+mod image;
+mod linetype;
+mod metadata;
+mod mline;
+mod mtext;
+mod ocs;
+#[cfg(feature = "text-paths")]
+mod outline;
+mod page;
+mod plot_style;
+mod point;
+mod polyline;
+mod pretty;
+mod progress;
+mod projection;
+mod renderer;
+mod report;
+mod proxy;
+mod sanitize;
+mod shx;
+mod spline;
+mod style;
+mod text;
+mod tolerance;
+mod units;
+mod wipeout;
+
+pub use annotate::Tooltip;
+pub use attributes::{extract_attributes, BlockAttributes};
+pub use code_page::load_drawing;
+pub use filter::LayerFilter;
+pub use font::FontMap;
+pub use linetype::LineTypeLibrary;
+#[cfg(feature = "text-paths")]
+pub use outline::OutlineFont;
+pub use page::PageSize;
+pub use plot_style::{PlotStyle, PlotStyleTable};
+pub use progress::ProgressCallback;
+pub use projection::Projection;
+pub use renderer::{EntityContext, EntityRenderer, RendererRegistry, SvgFragment};
+pub use report::{ConversionError, ConversionReport, Warning, WarningCallback};
+pub use shx::ShxFont;
+pub use style::Style;
+
+use dxf::entities::{Entity, EntityType, Insert};
+use dxf::tables::Layer;
+use dxf::{Block, Drawing, Point};
+use polyline::{
+    lwpolyline_vertices, mesh_edges, polyline_segments, polyline_vertices, render_mesh_edges, render_polyline,
+    PolylineVertex,
+};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
 use std::f64::consts::PI;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
 
-#[derive(Debug)]
-struct Bounds {
-    min_x: f64,
-    min_y: f64,
-    max_x: f64,
-    max_y: f64,
+/// The fewest decimals written for coordinates unless `precision` asks for less
+const DEFAULT_PRECISION: usize = 3;
+const MAX_PRECISION: usize = 12;
+
+/// An axis-aligned box in drawing units, like the extents of a drawing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bounds {
+    pub min_x: f64,
+    pub min_y: f64,
+    pub max_x: f64,
+    pub max_y: f64,
 }
 
 impl Bounds {
-    fn new() -> Self {
+    /// Bounds containing nothing, which grow to fit whatever is added to them.
+    pub fn empty() -> Self {
         Bounds {
             min_x: f64::INFINITY,
             min_y: f64::INFINITY,
@@ -19,6 +93,41 @@ impl Bounds {
         }
     }
 
+    /// True if the bounds contain nothing, like those of a drawing without entities.
+    pub fn is_empty(&self) -> bool {
+        !(self.min_x <= self.max_x && self.min_y <= self.max_y)
+    }
+
+    /// The width, 0 when empty.
+    pub fn width(&self) -> f64 {
+        (self.max_x - self.min_x).max(0.0)
+    }
+
+    /// The height, 0 when empty.
+    pub fn height(&self) -> f64 {
+        (self.max_y - self.min_y).max(0.0)
+    }
+
+    /// The point in the middle.
+    pub fn center(&self) -> (f64, f64) {
+        ((self.min_x + self.max_x) / 2.0, (self.min_y + self.max_y) / 2.0)
+    }
+
+    /// The smallest bounds containing both.
+    pub fn union(&self, other: &Bounds) -> Bounds {
+        Bounds {
+            min_x: self.min_x.min(other.min_x),
+            min_y: self.min_y.min(other.min_y),
+            max_x: self.max_x.max(other.max_x),
+            max_y: self.max_y.max(other.max_y),
+        }
+    }
+
+    /// True if the point lies inside the bounds or on their edge.
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        (self.min_x..=self.max_x).contains(&x) && (self.min_y..=self.max_y).contains(&y)
+    }
+
     fn update(&mut self, x: f64, y: f64) {
         self.min_x = self.min_x.min(x);
         self.min_y = self.min_y.min(y);
@@ -26,6 +135,56 @@ impl Bounds {
         self.max_y = self.max_y.max(y);
     }
 
+    /// Adds the vertices of a polyline, including the extremes of bulged segments.
+    fn update_polyline(&mut self, vertices: &[PolylineVertex], closed: bool) {
+        for vertex in vertices {
+            self.update(vertex.x, vertex.y);
+        }
+        for (start, end) in polyline_segments(vertices, closed) {
+            if let Some(arc) = geometry::bulge_arc(start.x, start.y, end.x, end.y, start.bulge) {
+                for (x, y) in geometry::arc_extremes(arc.center_x, arc.center_y, arc.radius, arc.start_angle, arc.end_angle) {
+                    self.update(x, y);
+                }
+            }
+        }
+    }
+
+    /**
+    Enough decimals to keep detail a ten-thousandth the size of the drawing: three for anything
+    bigger than a few units, more for small drawings like ones in geographic degrees.
+    */
+    fn precision(&self) -> usize {
+        let extent = (self.max_x - self.min_x).max(self.max_y - self.min_y);
+        if !extent.is_finite() || extent <= 0.0 {
+            return DEFAULT_PRECISION;
+        }
+        let decimals = 3.0 - extent.log10().floor();
+        (decimals as usize).clamp(DEFAULT_PRECISION, MAX_PRECISION)
+    }
+
+    /// What's shown of a drawing with nothing to show.
+    const DEFAULT_VIEW: Bounds = Bounds { min_x: 0.0, min_y: 0.0, max_x: 100.0, max_y: 100.0 };
+
+    /**
+    Bounds that can be scaled to fit a view: a single point gets a box a unit across around it, and a
+    horizontal or vertical line is made as tall or wide as it is long.
+    */
+    fn with_minimum_size(&self) -> Self {
+        let (x, y) = self.center();
+        let half = match (self.width(), self.height()) {
+            (0.0, 0.0) => 0.5,
+            (width, 0.0) => width / 2.0,
+            (0.0, height) => height / 2.0,
+            _ => return *self,
+        };
+        Bounds {
+            min_x: self.min_x.min(x - half),
+            min_y: self.min_y.min(y - half),
+            max_x: self.max_x.max(x + half),
+            max_y: self.max_y.max(y + half),
+        }
+    }
+
     // Add padding to the bounds
     fn with_padding(&self, padding_percent: f64) -> Self {
         let width = self.max_x - self.min_x;
@@ -42,134 +201,288 @@ impl Bounds {
     }
 }
 
-fn is_angle_in_arc(angle: f64, start: f64, end: f64) -> bool {
-    let mut angle = angle % (2.0 * PI);
-    let start = start % (2.0 * PI);
-    let mut end = end % (2.0 * PI);
-    
-    if start > end {
-        end += 2.0 * PI;
-        if angle < start {
-            angle += 2.0 * PI;
+/**
+The extents of the entities as the SVG would show them, without padding: only those `options` converts,
+seen through its projection and point transform. Use it for thumbnails or zoom-to-fit without converting.
+Empty if no entity has any extent.
+*/
+pub fn calculate_bounds(entities: &[&Entity], options: Option<&SvgOptions>) -> Bounds {
+    let default_options;
+    let options = match options {
+        Some(options) => options,
+        None => {
+            default_options = SvgOptions::default();
+            &default_options
         }
-    }
-    
-    angle >= start && angle <= end
+    };
+    let entities: Vec<&Entity> = entities
+        .iter()
+        .copied()
+        .filter(|entity| is_converted(options, entity) && is_drawable(entity, options, None))
+        .collect();
+    entity_bounds(&entities, None, options)
 }
 
-fn calculate_bounds(entities: &[&Entity]) -> Bounds {
-    let mut bounds = Bounds::new();
+/// Like calculate_bounds for the entities of a drawing, including the contents of the blocks they insert.
+pub fn drawing_bounds(drawing: &Drawing, options: Option<&SvgOptions>) -> Bounds {
+    let default_options;
+    let options = match options {
+        Some(options) => options,
+        None => {
+            default_options = SvgOptions::default();
+            &default_options
+        }
+    };
+    let entities: Vec<&Entity> = drawing
+        .entities()
+        .filter(|entity| is_converted(options, entity) && is_drawable(entity, options, None))
+        .collect();
+    entity_bounds(&entities, Some(drawing), options)
+}
 
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(entities = entities.len()), ret))]
+fn entity_bounds(entities: &[&Entity], drawing: Option<&Drawing>, options: &SvgOptions) -> Bounds {
+    let mut bounds = Bounds::empty();
+    let mut blocks = HashMap::new();
     for entity in entities {
-        match &entity.specific {
-            EntityType::Line(line) => {
-                bounds.update(line.p1.x, line.p1.y);
-                bounds.update(line.p2.x, line.p2.y);
-            }
-            EntityType::Circle(circle) => {
-                bounds.update(circle.center.x - circle.radius, circle.center.y - circle.radius);
-                bounds.update(circle.center.x + circle.radius, circle.center.y + circle.radius);
-            }
-            EntityType::Arc(arc) => {
-                // For arcs, we need to check start, end, and potential extreme points
-                let start_angle = arc.start_angle.to_radians();
-                let end_angle = arc.end_angle.to_radians();
-                
-                // Check start and end points
-                bounds.update(
-                    arc.center.x + arc.radius * start_angle.cos(),
-                    arc.center.y + arc.radius * start_angle.sin()
-                );
-                bounds.update(
-                    arc.center.x + arc.radius * end_angle.cos(),
-                    arc.center.y + arc.radius * end_angle.sin()
-                );
-                
-                // Check extreme points if they fall within the arc
-                let angles = [0.0, PI/2.0, PI, 3.0*PI/2.0];
-                for &angle in &angles {
-                    if is_angle_in_arc(angle, start_angle, end_angle) {
-                        bounds.update(
-                            arc.center.x + arc.radius * angle.cos(),
-                            arc.center.y + arc.radius * angle.sin()
-                        );
-                    }
-                }
+        let wcs = ocs::to_wcs(entity);
+        if let Some(projected) = projection::project(&wcs, &options.projection, options.point_transform.as_deref()) {
+            update_bounds(&mut bounds, &projected, drawing, options, &mut blocks);
+        }
+    }
+    bounds
+}
+
+/**
+Grows the bounds to fit an entity in world coordinates. `blocks` caches the extents of each block by name,
+None for blocks without any, or still being measured when they insert themselves.
+*/
+fn update_bounds(
+    bounds: &mut Bounds,
+    entity: &Entity,
+    drawing: Option<&Drawing>,
+    options: &SvgOptions,
+    blocks: &mut HashMap<String, Option<Bounds>>,
+) {
+    match &entity.specific {
+        EntityType::Line(line) => {
+            bounds.update(line.p1.x, line.p1.y);
+            bounds.update(line.p2.x, line.p2.y);
+        }
+        EntityType::Circle(circle) => {
+            bounds.update(circle.center.x - circle.radius, circle.center.y - circle.radius);
+            bounds.update(circle.center.x + circle.radius, circle.center.y + circle.radius);
+        }
+        EntityType::Arc(arc) => {
+            // For arcs, we need to check start, end, and potential extreme points
+            let start_angle = arc.start_angle.to_radians();
+            let end_angle = arc.end_angle.to_radians();
+            for (x, y) in geometry::arc_extremes(arc.center.x, arc.center.y, arc.radius, start_angle, end_angle) {
+                bounds.update(x, y);
             }
-            EntityType::LwPolyline(lwpolyline) => {
-                for vertex in &lwpolyline.vertices {
-                    bounds.update(vertex.x, vertex.y);
-                }
+        }
+        EntityType::LwPolyline(lwpolyline) => {
+            bounds.update_polyline(&lwpolyline_vertices(lwpolyline), lwpolyline.is_closed());
+        }
+        EntityType::Polyline(polyline) => {
+            bounds.update_polyline(&polyline_vertices(polyline), polyline.is_closed());
+        }
+        EntityType::Ellipse(ellipse) => {
+            for (x, y) in ellipse_shape(ellipse).arc_extremes(ellipse.start_parameter, ellipse.end_parameter) {
+                bounds.update(x, y);
+            }
+        }
+        EntityType::Spline(spline) => {
+            for (x, y) in spline::spline_points(spline, options.curve_tolerance) {
+                bounds.update(x, y);
+            }
+        }
+        EntityType::Text(text) => {
+            let style = text::TextStyle::resolve(&text.text_style_name, drawing, &options.font_map);
+            for (x, y) in text::text_corners(text, &style, text_metrics(text, &style, options)) {
+                bounds.update(x, y);
+            }
+        }
+        EntityType::MText(mtext) => {
+            bounds.update(mtext.insertion_point.x, mtext.insertion_point.y);
+        }
+        EntityType::ModelPoint(point) => {
+            bounds.update(point.location.x, point.location.y);
+        }
+        EntityType::Face3D(face) => {
+            bounds.update(face.first_corner.x, face.first_corner.y);
+            bounds.update(face.second_corner.x, face.second_corner.y);
+            bounds.update(face.third_corner.x, face.third_corner.y);
+            bounds.update(face.fourth_corner.x, face.fourth_corner.y);
+        }
+        EntityType::Solid(solid) => {
+            bounds.update(solid.first_corner.x, solid.first_corner.y);
+            bounds.update(solid.second_corner.x, solid.second_corner.y);
+            bounds.update(solid.third_corner.x, solid.third_corner.y);
+            bounds.update(solid.fourth_corner.x, solid.fourth_corner.y);
+        }
+        EntityType::Leader(leader) => {
+            for vertex in &leader.vertices {
+                bounds.update(vertex.x, vertex.y);
+            }
+        }
+        EntityType::Helix(helix) => {
+            for (x, y) in helix::helix_points(helix, options.curve_tolerance) {
+                bounds.update(x, y);
+            }
+        }
+        EntityType::Trace(trace) => {
+            bounds.update(trace.first_corner.x, trace.first_corner.y);
+            bounds.update(trace.second_corner.x, trace.second_corner.y);
+            bounds.update(trace.third_corner.x, trace.third_corner.y);
+            bounds.update(trace.fourth_corner.x, trace.fourth_corner.y);
+        }
+        specific @ (EntityType::RotatedDimension(_)
+        | EntityType::AlignedDimension(_)
+        | EntityType::RadialDimension(_)
+        | EntityType::DiameterDimension(_)
+        | EntityType::AngularThreePointDimension(_)
+        | EntityType::OrdinateDimension(_)) => {
+            for (x, y) in dimension::definition_points(specific) {
+                bounds.update(x, y);
+            }
+        }
+        // rays and construction lines are infinite, so only the point they pass through counts
+        EntityType::Ray(ray) => bounds.update(ray.start_point.x, ray.start_point.y),
+        EntityType::XLine(xline) => bounds.update(xline.first_point.x, xline.first_point.y),
+        EntityType::Image(image) => {
+            for (x, y) in image::image_corners(image) {
+                bounds.update(x, y);
+            }
+        }
+        EntityType::Wipeout(wipeout) => {
+            for (x, y) in wipeout::outline(wipeout) {
+                bounds.update(x, y);
             }
-            EntityType::Polyline(polyline) => {
-                for vertex in polyline.vertices() {
-                    bounds.update(vertex.location.x, vertex.location.y);
+        }
+        EntityType::ProxyEntity(proxy) => {
+            for graphic in proxy::parse_graphics(&proxy.graphics_data_string, code_page::drawing_encoding(drawing)) {
+                for (x, y) in proxy::graphic_points(&graphic) {
+                    bounds.update(x, y);
                 }
             }
-            EntityType::Ellipse(ellipse) => {
-                // Calculate the bounding box of the ellipse
-                let major_axis_length = (
-                    ellipse.major_axis.x.powi(2) + 
-                    ellipse.major_axis.y.powi(2)
-                ).sqrt();
-                let minor_axis_length = major_axis_length * ellipse.minor_axis_ratio;
-                
-                bounds.update(ellipse.center.x - major_axis_length, ellipse.center.y - minor_axis_length);
-                bounds.update(ellipse.center.x + major_axis_length, ellipse.center.y + minor_axis_length);
-            }
-            EntityType::Text(text) => {
-                // For text, just use the insertion point
-                // Note: This is a simplification as it doesn't account for text size
-                bounds.update(text.location.x, text.location.y);
-            }
-            EntityType::ModelPoint(point) => {
-                bounds.update(point.location.x, point.location.y);
-            }
-            EntityType::Face3D(face) => {
-                bounds.update(face.first_corner.x, face.first_corner.y);
-                bounds.update(face.second_corner.x, face.second_corner.y);
-                bounds.update(face.third_corner.x, face.third_corner.y);
-                bounds.update(face.fourth_corner.x, face.fourth_corner.y);
-            }
-            EntityType::Solid(solid) => {
-                bounds.update(solid.first_corner.x, solid.first_corner.y);
-                bounds.update(solid.second_corner.x, solid.second_corner.y);
-                bounds.update(solid.third_corner.x, solid.third_corner.y);
-                bounds.update(solid.fourth_corner.x, solid.fourth_corner.y);
-            }
-            EntityType::Leader(leader) => {
-                for vertex in &leader.vertices {
-                    bounds.update(vertex.x, vertex.y);
+        }
+        EntityType::MLine(mline) => {
+            for vertex in &mline.vertices {
+                bounds.update(vertex.x, vertex.y);
+            }
+        }
+        EntityType::Tolerance(tolerance) => {
+            bounds.update(tolerance.insertion_point.x, tolerance.insertion_point.y);
+        }
+        EntityType::Shape(shape) => {
+            bounds.update(shape.location.x, shape.location.y);
+            // Add some padding based on shape size
+            bounds.update(shape.location.x + shape.size, shape.location.y + shape.size);
+            bounds.update(shape.location.x - shape.size, shape.location.y - shape.size);
+        }
+        EntityType::Insert(insert) => {
+            let block = drawing.and_then(|drawing| find_block(drawing, &insert.name));
+            if let (Some(drawing), Some(block)) = (drawing, block) {
+                if let Some(block_bounds) = block_bounds(block, drawing, options, blocks) {
+                    insert_bounds(bounds, insert, &block.base_point, &block_bounds);
                 }
             }
-            EntityType::Helix(helix) => {
-                bounds.update(helix.axis_base_point.x, helix.axis_base_point.y);
-                bounds.update(helix.start_point.x, helix.start_point.y);
-                // Add some padding for the helix radius
-                bounds.update(helix.axis_base_point.x + helix.radius, helix.axis_base_point.y + helix.radius);
-                bounds.update(helix.axis_base_point.x - helix.radius, helix.axis_base_point.y - helix.radius);
-            }
-            EntityType::Trace(trace) => {
-                bounds.update(trace.first_corner.x, trace.first_corner.y);
-                bounds.update(trace.second_corner.x, trace.second_corner.y);
-                bounds.update(trace.third_corner.x, trace.third_corner.y);
-                bounds.update(trace.fourth_corner.x, trace.fourth_corner.y);
-            }
-            EntityType::Shape(shape) => {
-                bounds.update(shape.location.x, shape.location.y);
-                // Add some padding based on shape size
-                bounds.update(shape.location.x + shape.size, shape.location.y + shape.size);
-                bounds.update(shape.location.x - shape.size, shape.location.y - shape.size);
-            }
-            _ => {
-                continue;
+            for attribute in insert.attributes() {
+                if attribute.is_invisible() && !options.invisible_attributes {
+                    continue;
+                }
+                let text = Entity::new(EntityType::Text(text::attribute_text(attribute)));
+                update_bounds(bounds, &text, drawing, options, blocks);
             }
         }
+        _ => {}
     }
+}
 
+/// The extents of a block's contents in its own coordinates, measured once and then taken from `blocks`.
+fn block_bounds(
+    block: &Block,
+    drawing: &Drawing,
+    options: &SvgOptions,
+    blocks: &mut HashMap<String, Option<Bounds>>,
+) -> Option<Bounds> {
+    let key = block.name.to_ascii_lowercase();
+    if let Some(cached) = blocks.get(&key) {
+        return *cached;
+    }
+    // a block inserting itself adds nothing to itself
+    blocks.insert(key.clone(), None);
+    let mut bounds = Bounds::empty();
+    for entity in &block.entities {
+        if is_layer_included(options, &entity.common.layer) && is_drawable(entity, options, None) {
+            update_bounds(&mut bounds, &ocs::to_wcs(entity), Some(drawing), options, blocks);
+        }
+    }
+    let bounds = Some(bounds).filter(|bounds| !bounds.is_empty());
+    blocks.insert(key, bounds);
     bounds
 }
 
+/// Grows the bounds to fit the corners of a block's extents placed by an insert, in each cell of a MINSERT.
+fn insert_bounds(bounds: &mut Bounds, insert: &Insert, base: &Point, block: &Bounds) {
+    let (sin, cos) = insert.rotation.to_radians().sin_cos();
+    let corners = [
+        (block.min_x, block.min_y),
+        (block.max_x, block.min_y),
+        (block.max_x, block.max_y),
+        (block.min_x, block.max_y),
+    ];
+    for row in 0..insert.row_count.max(1) {
+        for column in 0..insert.column_count.max(1) {
+            for (x, y) in corners {
+                // as insert_transform places the block
+                let x = (x - base.x) * insert.x_scale_factor + f64::from(column) * insert.column_spacing;
+                let y = (y - base.y) * insert.y_scale_factor + f64::from(row) * insert.row_spacing;
+                bounds.update(insert.location.x + x * cos - y * sin, insert.location.y + x * sin + y * cos);
+            }
+        }
+    }
+}
+
+/// The length and descent of a TEXT measured in the `text_font` it's written in, if it's written as paths.
+#[cfg(feature = "text-paths")]
+fn text_metrics(text: &dxf::entities::Text, style: &text::TextStyle, options: &SvgOptions) -> Option<(f64, f64)> {
+    let font = options.text_font.as_ref()?;
+    Some(outline::text_metrics(text, style, font))
+}
+
+#[cfg(not(feature = "text-paths"))]
+fn text_metrics(_: &dxf::entities::Text, _: &text::TextStyle, _: &SvgOptions) -> Option<(f64, f64)> {
+    None
+}
+
+/**
+The box a drawing's header stores between two corners, as seen through the projection and point transform.
+None if the header doesn't hold a usable box, like the huge inverted extents of an empty drawing.
+*/
+fn header_bounds(
+    min: &Point,
+    max: &Point,
+    projection: &Projection,
+    transform: Option<&projection::PointTransformFn>,
+) -> Option<Bounds> {
+    let valid = [min.x, min.y, max.x, max.y].iter().all(|value| value.is_finite() && value.abs() < 1e19);
+    if !valid || min.x >= max.x || min.y >= max.y {
+        return None;
+    }
+    let mut bounds = Bounds::empty();
+    for x in [min.x, max.x] {
+        for y in [min.y, max.y] {
+            for z in [min.z, max.z] {
+                let corner = projection.point(&Point::new(x, y, z), transform);
+                bounds.update(corner.x, corner.y);
+            }
+        }
+    }
+    Some(bounds)
+}
+
 /**
 a struct containing a bunch of options around the svg.
 Fill each of these or use None for default when using dxf_to_svg.
@@ -188,6 +501,189 @@ pub struct SvgOptions {
     pub stroke_width: f64,
     /// The default color for entities without a specific color
     pub default_color: String,
+    /// If true, closed polylines are filled with their color
+    pub fill_closed_polylines: bool,
+    /// How the rendered entities are grouped into `<g>` elements
+    pub group_by: GroupBy,
+    /// Only convert the layers this filter allows. None converts every layer.
+    pub layer_filter: Option<LayerFilter>,
+    /// Linetypes used when the drawing doesn't define the one an entity refers to, e.g. loaded from `acad.lin`
+    pub line_type_library: Option<LineTypeLibrary>,
+    /// Drawing units per millimetre of lineweight. When set, entities with a lineweight are drawn
+    /// that wide instead of using `stroke_width`.
+    pub lineweight_scale: Option<f64>,
+    /// Remaps colors, lineweights and screening the way a CTB/STB file does when plotting
+    pub plot_style_table: Option<PlotStyleTable>,
+    /// The furthest, in drawing units, that flattened splines, bulges and helixes may stray from the real curve.
+    /// Smaller values give smoother curves and bigger files.
+    pub curve_tolerance: f64,
+    /// The direction 3D drawings are viewed from
+    pub projection: Projection,
+    /// Only convert entities whose Z coordinates all lie between these two heights, e.g. to cut a single floor
+    /// out of a 3D model. None converts every entity.
+    pub z_range: Option<(f64, f64)>,
+    /// Only convert the entities this returns true for, e.g. to leave out a type, color or handle. It's
+    /// checked along with `layer_filter` and `z_range`, before the bounds are computed. None converts every entity.
+    pub entity_filter: Option<EntityFilter>,
+    /// Where the viewBox comes from. Only used by drawing_to_svg, dxf_to_svg always computes the bounds.
+    pub bounds_source: BoundsSource,
+    /// If true, the root `<svg>` gets a width and height in millimetres from the drawing's `$INSUNITS`,
+    /// so it prints at true scale. Unitless drawings keep the relative size.
+    pub physical_size: bool,
+    /// Drawing units per millimetre of output, e.g. 100.0 to export a millimetre drawing at 1:100 or 0.1 for a
+    /// metre drawing. Sizes the root `<svg>` in millimetres and takes precedence over `physical_size`.
+    pub scale: Option<f64>,
+    /// Fit the drawing onto a sheet of paper, turned to match the drawing. The output is sized and laid out
+    /// in millimetres; with `scale` set the drawing is centered at that scale instead of being fitted.
+    pub page: Option<PageSize>,
+    /// The blank border kept around the drawing on the page, in millimetres
+    pub page_margin: f64,
+    /// The width and height of the root `<svg>`. Ignored when fitting to a `page`.
+    pub size: SvgSize,
+    /// Decimals written for coordinates, angles, scales and font sizes. None picks at least 3, more for
+    /// drawings only a few units across. Fewer decimals make smaller files, e.g. 1 for screen previews.
+    pub precision: Option<u8>,
+    /// If true, every element goes on its own, indented line so the output can be read and diffed
+    pub pretty_print: bool,
+    /// If true, elements get classes like `class="layer-WALLS entity-line s0"` and their strokes are written
+    /// once in an embedded `<style>`, instead of as attributes on every element. Makes the output much
+    /// smaller and lets stylesheets restyle it by layer or entity type.
+    pub css_classes: bool,
+    /// Extra attributes added to the root `<svg>` element, in order, like `("id", "plan")` or
+    /// `("xmlns:inkscape", "http://www.inkscape.org/namespaces/inkscape")`. Values are escaped, names are written as is.
+    pub root_attributes: Vec<(String, String)>,
+    /// A `<title>` for the document, shown by browsers and read by screen readers.
+    pub title: Option<String>,
+    /// A `<desc>` for the document, a longer text alternative to the title.
+    pub description: Option<String>,
+    /// If true, a `<metadata>` element records the source file name, the DXF version and when the
+    /// file was converted. Off by default, since the timestamp makes every conversion different.
+    pub metadata: bool,
+    /// If true, the elements of each entity get an `id="e{handle}"` from its DXF handle, so a viewer
+    /// can map them back to the entity. Entities drawn with several elements are wrapped in a `<g>` with the id.
+    pub entity_ids: bool,
+    /// If true, the elements of each entity get `data-layer="..."` and `data-entity="LINE"` attributes,
+    /// so scripts can filter and style them by layer or entity type.
+    pub data_attributes: bool,
+    /// Adds a `<title>` to each entity's elements, which browsers show as a tooltip on hover.
+    pub tooltip: Option<Tooltip>,
+    /// If true, entities with a hyperlink in their `PE_URL` xdata are wrapped in an `<a>` linking to it.
+    /// Off by default, since the links come from the drawing; only relative, http(s), mailto and file links are kept.
+    pub hyperlinks: bool,
+    /// If true, dimensions are drawn from the anonymous block (`*D...`) AutoCAD renders them into, which
+    /// matches the source exactly. Dimensions without a block are still reconstructed from their definition points.
+    pub dimension_blocks: bool,
+    /// If true, block attributes flagged invisible are drawn too. Off by default, as in AutoCAD.
+    pub invisible_attributes: bool,
+    /// The fonts text is written in, by the font file of its text style. `.shx` stroke fonts are replaced by
+    /// similar web fonts, extend the map to choose others or load the `.shx` files to draw their strokes.
+    pub font_map: FontMap,
+    /// The folder the SVG will be saved in. When set, the links of IMAGE entities are rewritten relative to it,
+    /// resolving relative paths from the DXF file's folder, so the images are found next to the SVG.
+    pub image_output_dir: Option<PathBuf>,
+    /// If true, the files of IMAGE entities are read and embedded as base64 `data:` URIs, so the SVG is
+    /// self-contained. Images that can't be read are still linked.
+    pub embed_images: bool,
+    /// If true, WIPEOUT entities hide what's drawn before them with an SVG `<mask>`, leaving the area
+    /// transparent, instead of covering it with a polygon in the background color.
+    pub wipeout_masks: bool,
+    /// How POINT entities are drawn, like the drawing's `$PDMODE`, which is used when this is None:
+    /// 0 a dot, 1 nothing, 2 a cross, 3 an X, 4 a tick, plus 32 for a circle and 64 for a square around it.
+    pub point_mode: Option<i32>,
+    /// The size of POINT symbols, like the drawing's `$PDSIZE`, which is used when this is None: in drawing
+    /// units when positive, a percentage of the view's height when negative and 5% of it when 0.
+    pub point_size: Option<f64>,
+    /// If true, polylines made of equal arcs, like AutoCAD's revision clouds, get a `revcloud` class so
+    /// stylesheets and reviewers can highlight the areas that changed.
+    pub revision_cloud_class: bool,
+    /// Custom renderers asked to draw each entity before the built-in rendering, to override it or to
+    /// draw entity types the crate doesn't cover.
+    pub renderers: RendererRegistry,
+    /// Called with each entity the converter can't draw, e.g. to log or count them. Entities custom
+    /// `renderers` draw aren't reported. Unsupported entities are skipped silently when this is None.
+    pub on_unsupported: Option<UnsupportedCallback>,
+    /// Called with each `Warning` as it comes up, e.g. to log them. They're also collected in the
+    /// `ConversionReport` the `*_with_report` functions return.
+    pub on_warning: Option<WarningCallback>,
+    /// Called with how many entities are rendered so far and how many there are, every 100 entities and
+    /// after the last, e.g. to show a progress bar.
+    pub on_progress: Option<ProgressCallback>,
+    /// Stops the conversion once it's set, e.g. when the client waiting for it disconnects. The entities
+    /// not rendered by then are left out, `try_dxf_to_svg` and `try_drawing_to_svg` fail with `Cancelled`
+    /// and `svg_fragments` ends early.
+    pub cancel: Option<Arc<AtomicBool>>,
+    /// Makes `try_dxf_to_svg` and `try_drawing_to_svg` fail with the offending handles when any entity is
+    /// unsupported or degenerate or a block is missing, or with `EmptyDrawing` when there's nothing to show,
+    /// instead of returning an incomplete drawing.
+    /// The other functions can't fail and ignore it.
+    pub strict: bool,
+    /// Skips zero-length lines, circles and arcs without a radius and polylines with a single vertex, which
+    /// bloat the output and break some renderers. They're reported as degenerate.
+    pub skip_degenerate: bool,
+    /// Changes the style of each entity once its color, width and linetype are resolved, e.g. to
+    /// highlight a layer in red or thicken a kind of entity, without post-processing the SVG.
+    pub style_hook: Option<StyleHook>,
+    /// Moves every point after the `projection`, given its x, y and z, to where it should be drawn, e.g. to
+    /// convert units, georeference a plan or apply a custom projection. Sizes and directions follow the
+    /// transform around each entity, which is exact for affine transforms. Proxy entities can't be moved, so
+    /// they're skipped with a warning.
+    pub point_transform: Option<PointTransform>,
+    /// Writes TEXT as `<path>` outlines of this font instead of `<text>`, so it looks the same without
+    /// the fonts installed. MTEXT is still written as `<text>`.
+    #[cfg(feature = "text-paths")]
+    pub text_font: Option<OutlineFont>,
+}
+
+// entities may be rendered on several threads at once, so the callbacks must be shareable between them
+
+/// Called with each entity the converter can't draw, from whichever thread rendered it.
+pub type UnsupportedCallback = Box<dyn FnMut(&Entity) + Send + Sync>;
+
+/// Changes the resolved style of an entity.
+pub type StyleHook = Box<dyn Fn(&Entity, Style) -> Style + Send + Sync>;
+
+/// Chooses the entities to convert.
+pub type EntityFilter = Box<dyn Fn(&Entity) -> bool + Send + Sync>;
+
+/// Moves a point `(x, y, z)` to where it's drawn.
+pub type PointTransform = Box<dyn Fn(f64, f64, f64) -> (f64, f64) + Send + Sync>;
+
+/// How the width and height of the root `<svg>` are set.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum SvgSize {
+    /// Millimetres when `scale` or `physical_size` ask for them, otherwise 100% so the SVG fills its container.
+    #[default]
+    Auto,
+    /// A width in pixels, the height follows from the drawing's aspect ratio.
+    Width(f64),
+    /// Any width and height, with units, e.g. `"800px"` and `"20cm"`.
+    Fixed { width: String, height: String },
+    /// No width or height at all, leaving the size to whatever embeds the SVG.
+    Omit,
+}
+
+/// What the viewBox of the output is fitted to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BoundsSource {
+    /// The extents of the converted entities, computed from their geometry.
+    #[default]
+    Computed,
+    /// The drawing's `$EXTMIN`/`$EXTMAX` header. Gives the same framing on every export, even with stray entities.
+    Extents,
+    /// The drawing's `$LIMMIN`/`$LIMMAX` header, usually the sheet the drawing was made for.
+    Limits,
+}
+
+/// How rendered entities are grouped in the output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GroupBy {
+    /// Entities are emitted directly, in drawing order.
+    #[default]
+    None,
+    /// One `<g id="layer-NAME">` per DXF layer, in order of first appearance, so viewers can toggle layers.
+    Layer,
+    /// Each INSERT's elements are wrapped in a `<g data-block="NAME">`, so whole symbols can be selected at once.
+    Block,
 }
 
 impl Default for SvgOptions {
@@ -198,377 +694,1333 @@ impl Default for SvgOptions {
             background_color: "white".to_string(),
             stroke_width: 1.0,
             default_color: "black".to_string(),
+            fill_closed_polylines: false,
+            group_by: GroupBy::None,
+            layer_filter: None,
+            line_type_library: None,
+            lineweight_scale: None,
+            plot_style_table: None,
+            curve_tolerance: 0.01,
+            projection: Projection::Top,
+            z_range: None,
+            entity_filter: None,
+            bounds_source: BoundsSource::Computed,
+            physical_size: false,
+            scale: None,
+            page: None,
+            page_margin: 10.0,
+            size: SvgSize::Auto,
+            precision: None,
+            pretty_print: false,
+            css_classes: false,
+            root_attributes: vec![],
+            title: None,
+            description: None,
+            metadata: false,
+            entity_ids: false,
+            data_attributes: false,
+            tooltip: None,
+            hyperlinks: false,
+            dimension_blocks: false,
+            invisible_attributes: false,
+            font_map: FontMap::default(),
+            image_output_dir: None,
+            embed_images: false,
+            wipeout_masks: false,
+            point_mode: None,
+            point_size: None,
+            revision_cloud_class: false,
+            renderers: RendererRegistry::default(),
+            on_unsupported: None,
+            on_warning: None,
+            on_progress: None,
+            cancel: None,
+            strict: false,
+            skip_degenerate: false,
+            style_hook: None,
+            point_transform: None,
+            #[cfg(feature = "text-paths")]
+            text_font: None,
         }
     }
 }
 
 /**
 Takes in a vector of entities and displays them as an SVG string.
-If an entity is not supported, it will be printed to the console and skipped.
+If an entity is not supported, it is skipped and reported to `on_unsupported` and `on_warning`.
 
 * `entities` - the list of entities you wish to turn into a string.
 * Returns a string SVG representation of the entities.
 */
 pub fn dxf_to_svg(entities: Vec<&Entity>, options: Option<SvgOptions>) -> String {
-    let options = options.unwrap_or_default();
-    let bounds = calculate_bounds(&entities).with_padding(options.padding);
-    
-    // Calculate scale and translation to normalize coordinates
-    let width = bounds.max_x - bounds.min_x;
-    let height = bounds.max_y - bounds.min_y;
-    
-    // Calculate the aspect ratio to maintain proportions
-    let aspect_ratio = width / height;
-    
-    let mut svg = String::new();
-    
-    if options.use_bounds {
-        // Add a viewBox that ensures the content is visible and properly scaled
-        svg.push_str(&format!(
-            r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink" 
-            viewBox="{} {} {} {}" width="100%" height="100%" 
-            preserveAspectRatio="xMidYMid meet">"#,
-            0, // Start at 0 for normalized coordinates
-            0,
-            1000.0, // Use fixed width for consistent scaling
-            1000.0 / aspect_ratio // Height adjusted by aspect ratio
-        ));
-        
-        // Add a transform group to flip the Y axis and scale to normalized coordinates
-        svg.push_str(&format!(
-            r#"<g transform="scale({}, {}) translate({}, {})">"#,
-            1000.0 / width, // Scale X to normalize to 1000 units width
-            -1000.0 / width, // Scale Y (negative for flip) using same scale as X
-            -bounds.min_x, // Translate X to start at 0
-            -bounds.max_y  // Translate Y (after flip) to start at 0
-        ));
-    } else {
-        svg.push_str(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 100 100" xmlns:xlink="http://www.w3.org/1999/xlink">"#);
+    render_svg(entities, None, None, options).0
+}
+
+/// Like dxf_to_svg, but also returns a report of the warnings and skipped entities.
+pub fn dxf_to_svg_with_report(entities: Vec<&Entity>, options: Option<SvgOptions>) -> (String, ConversionReport) {
+    render_svg(entities, None, None, options)
+}
+
+/**
+Converts a whole drawing into an SVG string.
+Unlike dxf_to_svg, this has access to the drawing's blocks, so inserts are rendered
+with the geometry of the block they reference.
+
+* `drawing` - the drawing to convert.
+* Returns a string SVG representation of the drawing's entities.
+*/
+pub fn drawing_to_svg(drawing: &Drawing, options: Option<SvgOptions>) -> String {
+    render_svg(drawing.entities().collect(), Some(drawing), None, options).0
+}
+
+/// Like drawing_to_svg, but also returns a report of the warnings and skipped entities.
+pub fn drawing_to_svg_with_report(drawing: &Drawing, options: Option<SvgOptions>) -> (String, ConversionReport) {
+    render_svg(drawing.entities().collect(), Some(drawing), None, options)
+}
+
+/// Like dxf_to_svg, but fails if it's cancelled or, with `strict` set, if any part of the entities couldn't be drawn.
+pub fn try_dxf_to_svg(entities: Vec<&Entity>, options: Option<SvgOptions>) -> Result<String, ConversionError> {
+    let strict = options.as_ref().is_some_and(|options| options.strict);
+    let (svg, report) = render_svg(entities, None, None, options);
+    if report.cancelled {
+        return Err(ConversionError::Cancelled);
+    }
+    if strict {
+        ConversionError::strict(&report)?;
     }
+    Ok(svg)
+}
 
-    // Add a white background rectangle (in normalized coordinates)
-    if options.background_color != "none" {
-        svg.push_str(&format!(
-            r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
-            bounds.min_x,
-            -bounds.max_y,
-            width,
-            height,
-            options.background_color
-        ));
+/// Like drawing_to_svg, but fails if it's cancelled or, with `strict` set, if any part of the drawing couldn't be drawn.
+pub fn try_drawing_to_svg(drawing: &Drawing, options: Option<SvgOptions>) -> Result<String, ConversionError> {
+    let strict = options.as_ref().is_some_and(|options| options.strict);
+    let (svg, report) = render_svg(drawing.entities().collect(), Some(drawing), None, options);
+    if report.cancelled {
+        return Err(ConversionError::Cancelled);
     }
+    if strict {
+        ConversionError::strict(&report)?;
+    }
+    Ok(svg)
+}
 
-    for entity in entities {
-        let color = if entity.common.color_name.trim().is_empty() {
-            &options.default_color
-        } else {
-            entity.common.color_name.as_str()
-        };
+/// Everything an entity needs to know about the conversion while it's being rendered.
+#[derive(Clone, Copy)]
+struct RenderContext<'a> {
+    options: &'a SvgOptions,
+    drawing: Option<&'a Drawing>,
+    /// Set while rendering the contents of a block, where ByBlock colors come from the insert.
+    in_block: bool,
+    /// Decimals written for coordinates
+    precision: usize,
+    /// Collects the styles when they're written as CSS classes
+    style_sheet: Option<&'a style::StyleSheet>,
+    /// Collects the symbols, masks and other definitions written in the `<defs>` at the top
+    defs: &'a defs::DefsCollector,
+    /// The `on_unsupported` callback, taken out of the options to be called mutably
+    on_unsupported: Option<&'a Mutex<UnsupportedCallback>>,
+    /// Collects the warnings of the conversion
+    reporter: &'a report::Reporter,
+    /// Counts the entities rendered
+    progress: &'a progress::Progress,
+    /// The handles of the entities being converted
+    entity_handles: &'a HashSet<u64>,
+    /// The area the SVG shows, including its padding, once it's known
+    view: Option<Bounds>,
+    /// The folder of the DXF file being converted, if it was read from one
+    source_dir: Option<&'a Path>,
+}
 
-        let stroke_attr = format!("stroke=\"{}\" stroke-width=\"{}\"", color, options.stroke_width);
+impl<'a> RenderContext<'a> {
+    fn layer(&self, name: &str) -> Option<&'a Layer> {
+        self.drawing?.layers().find(|layer| layer.name.eq_ignore_ascii_case(name))
+    }
 
-        match &entity.specific {
-            EntityType::Line(line) => {
-                svg.push_str(&format!(
-                    r#"<line x1="{:.3}" y1="{:.3}" x2="{:.3}" y2="{:.3}" {} fill="none" />"#,
-                    line.p1.x, line.p1.y, line.p2.x, line.p2.y, stroke_attr
-                ));
+    /// The stroke of an entity, as attributes or as classes when `css_classes` is set.
+    fn stroke_attributes(&self, style: &style::Style, entity: &Entity) -> String {
+        match self.style_sheet {
+            Some(style_sheet) => {
+                let classes = format!(
+                    "{} entity-{}",
+                    svg_id("layer", &entity.common.layer),
+                    entity_type_name(&entity.specific).to_lowercase()
+                );
+                style_sheet.class_attribute(style, &classes)
             }
+            None => style.attributes(),
+        }
+    }
+}
 
-            EntityType::Insert(insert) => {
-                svg.push_str(&format!(
-                    r#"<use href="{}{}" x="{:.3}" y="{:.3}" width="{:.3}" height="{:.3}" />"#,
-                    "#",
-                    insert.name,
-                    insert.location.x, insert.location.y,
-                    insert.x_scale_factor, insert.y_scale_factor
-                ));
-            }
+/// The DXF name of an entity's type, like `LINE` or `LWPOLYLINE`.
+fn entity_type_name(specific: &EntityType) -> &'static str {
+    match specific {
+        EntityType::Face3D(_) => "3DFACE",
+        EntityType::Arc(_) => "ARC",
+        EntityType::Attribute(_) => "ATTRIB",
+        EntityType::Circle(_) => "CIRCLE",
+        EntityType::RotatedDimension(_)
+        | EntityType::AlignedDimension(_)
+        | EntityType::RadialDimension(_)
+        | EntityType::DiameterDimension(_)
+        | EntityType::AngularThreePointDimension(_)
+        | EntityType::OrdinateDimension(_) => "DIMENSION",
+        EntityType::Ellipse(_) => "ELLIPSE",
+        EntityType::Helix(_) => "HELIX",
+        EntityType::Image(_) => "IMAGE",
+        EntityType::Insert(_) => "INSERT",
+        EntityType::Leader(_) => "LEADER",
+        EntityType::Line(_) => "LINE",
+        EntityType::LwPolyline(_) => "LWPOLYLINE",
+        EntityType::MLine(_) => "MLINE",
+        EntityType::MText(_) => "MTEXT",
+        EntityType::ModelPoint(_) => "POINT",
+        EntityType::Polyline(_) => "POLYLINE",
+        EntityType::ProxyEntity(_) => "ACAD_PROXY_ENTITY",
+        EntityType::Ray(_) => "RAY",
+        EntityType::Shape(_) => "SHAPE",
+        EntityType::Solid(_) => "SOLID",
+        EntityType::Spline(_) => "SPLINE",
+        EntityType::Text(_) => "TEXT",
+        EntityType::Tolerance(_) => "TOLERANCE",
+        EntityType::Trace(_) => "TRACE",
+        EntityType::Wipeout(_) => "WIPEOUT",
+        EntityType::XLine(_) => "XLINE",
+        _ => "ENTITY",
+    }
+}
 
-            EntityType::LwPolyline(lwpolyline) => {
-                if lwpolyline.vertices.is_empty() {
-                    continue;
-                }
-                svg.push_str(&format!(
-                    r#"<polyline points="{}" {} />"#,
-                    lwpolyline.vertices.iter()
-                        .map(|p| format!("{:.3},{:.3}", p.x, p.y))
-                        .collect::<Vec<_>>()
-                        .join(" "),
-                    stroke_attr
-                ));
-            }
-
-            EntityType::Polyline(polyline) => {
-                let vertices: Vec<_> = polyline.vertices().collect();
-                if vertices.is_empty() {
-                    continue;
+/// Roughly how long the SVG of an entity is, to size the output up front instead of growing it many times
+const BYTES_PER_ENTITY: usize = 128;
+
+/// A conversion under way: the entities to draw, the area they're shown in and what's collected while drawing them.
+struct Conversion<'a> {
+    options: SvgOptions,
+    drawing: Option<&'a Drawing>,
+    source: Option<&'a Path>,
+    /// The `on_unsupported` callback, taken out of the options to be called mutably
+    on_unsupported: Option<Mutex<UnsupportedCallback>>,
+    reporter: report::Reporter,
+    progress: progress::Progress,
+    /// The entities that are drawn, in draw order
+    entities: Vec<&'a Entity>,
+    entity_handles: HashSet<u64>,
+    defs: defs::DefsCollector,
+    style_sheet: style::StyleSheet,
+    /// The area the SVG shows, including its padding
+    bounds: Bounds,
+    precision: usize,
+}
+
+impl<'a> Conversion<'a> {
+    /// Chooses the entities to draw and the area to show them in, `source` is the path of the file they came from, if any.
+    fn new(entities: Vec<&'a Entity>, drawing: Option<&'a Drawing>, source: Option<&'a Path>, options: Option<SvgOptions>) -> Self {
+        let mut options = options.unwrap_or_default();
+        let on_unsupported = options.on_unsupported.take().map(Mutex::new);
+        let reporter = report::Reporter::new(options.on_warning.take());
+        let mut entities: Vec<&Entity> = entities
+            .into_iter()
+            .filter(|entity| is_converted(&options, entity) && is_drawable(entity, &options, Some(&reporter)))
+            .collect();
+        if let Some(drawing) = drawing {
+            draw_order::sort_by_draw_order(&mut entities, drawing);
+        }
+        let entity_handles: HashSet<u64> = entities.iter().map(|entity| entity.common.handle.0).collect();
+        let progress = progress::Progress::new(entities.len(), options.on_progress.take(), options.cancel.take());
+        let bounds = match (options.bounds_source, drawing) {
+            (BoundsSource::Extents, Some(drawing)) => header_bounds(
+                &drawing.header.minimum_drawing_extents,
+                &drawing.header.maximum_drawing_extents,
+                &options.projection,
+                options.point_transform.as_deref(),
+            ),
+            (BoundsSource::Limits, Some(drawing)) => header_bounds(
+                &drawing.header.minimum_drawing_limits,
+                &drawing.header.maximum_drawing_limits,
+                &Projection::Top,
+                options.point_transform.as_deref(),
+            ),
+            _ => None,
+        }
+        .unwrap_or_else(|| entity_bounds(&entities, drawing, &options));
+        let bounds = if bounds.is_empty() || !bounds.width().is_finite() || !bounds.height().is_finite() {
+            reporter.warn(report::Warning::EmptyDrawing);
+            Bounds::DEFAULT_VIEW
+        } else {
+            bounds.with_minimum_size()
+        }
+        .with_padding(options.padding);
+        let precision = options.precision.map_or_else(|| bounds.precision(), usize::from);
+        Conversion {
+            options,
+            drawing,
+            source,
+            on_unsupported,
+            reporter,
+            progress,
+            entities,
+            entity_handles,
+            defs: defs::DefsCollector::default(),
+            style_sheet: style::StyleSheet::default(),
+            bounds,
+            precision,
+        }
+    }
+
+    /// The context the entities are rendered in.
+    fn context(&self) -> RenderContext<'_> {
+        RenderContext {
+            options: &self.options,
+            drawing: self.drawing,
+            in_block: false,
+            precision: self.precision,
+            style_sheet: self.options.css_classes.then_some(&self.style_sheet),
+            defs: &self.defs,
+            on_unsupported: self.on_unsupported.as_ref(),
+            reporter: &self.reporter,
+            progress: &self.progress,
+            entity_handles: &self.entity_handles,
+            view: Some(self.bounds),
+            source_dir: self.source.and_then(Path::parent),
+        }
+    }
+
+    /**
+    The start of the document, split where the `<defs>` go: the root element with the title and metadata,
+    then the group placing the drawing and its background.
+    */
+    fn header(&self) -> (String, String) {
+        let options = &self.options;
+        let bounds = self.bounds;
+
+        // Calculate scale and translation to normalize coordinates
+        let width = bounds.max_x - bounds.min_x;
+        let height = bounds.max_y - bounds.min_y;
+
+        // Calculate the aspect ratio to maintain proportions
+        let aspect_ratio = width / height;
+
+        let root_attributes: String = options
+            .root_attributes
+            .iter()
+            .map(|(name, value)| format!(r#" {}="{}""#, name, escape_xml_text(value)))
+            .collect();
+        let mut document_info = String::new();
+        if let Some(title) = &options.title {
+            write!(document_info, "<title>{}</title>", escape_xml_text(title)).unwrap();
+        }
+        if let Some(description) = &options.description {
+            write!(document_info, "<desc>{}</desc>", escape_xml_text(description)).unwrap();
+        }
+        if options.metadata {
+            document_info.push_str(&metadata::metadata_element(
+                self.source.and_then(Path::file_name).and_then(|name| name.to_str()),
+                self.drawing,
+                std::time::SystemTime::now(),
+            ));
+        }
+        let mut root = String::new();
+        let mut opening = String::new();
+
+        if let (true, Some(page)) = (options.use_bounds, options.page) {
+            let layout = page::page_layout(page, options.page_margin, width, height, options.scale.map(|scale| 1.0 / scale));
+            write!(
+                root,
+                r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink"{} viewBox="0 0 {} {}" width="{}mm" height="{}mm">"#,
+                root_attributes, layout.width, layout.height, layout.width, layout.height
+            ).unwrap();
+            root.push_str(&document_info);
+            // The viewBox is in millimetres, place the drawing on the page and flip the Y axis
+            write!(
+                opening,
+                r#"<g transform="translate({}, {}) scale({}, {}) translate({}, {})">"#,
+                layout.offset_x,
+                layout.offset_y,
+                layout.scale,
+                -layout.scale,
+                -bounds.min_x,
+                -bounds.max_y
+            ).unwrap();
+        } else if options.use_bounds {
+            // In physical units the file prints at true scale, otherwise it fills whatever it's placed in
+            let drawing_units = self.drawing.and_then(|drawing| units::millimeters_per_unit(drawing.header.default_drawing_units));
+            let millimeters_per_unit = match (options.scale, drawing_units) {
+                (Some(scale), _) => Some(1.0 / scale),
+                (None, Some(millimeters)) if options.physical_size => Some(millimeters),
+                _ => None,
+            };
+            let size = match (&options.size, millimeters_per_unit) {
+                (SvgSize::Auto, Some(millimeters)) => {
+                    format!(r#"width="{:.3}mm" height="{:.3}mm" "#, width * millimeters, height * millimeters)
                 }
-                svg.push_str(&format!(
-                    r#"<polyline points="{}" {} />"#,
-                    vertices.iter()
-                        .map(|p| format!("{:.3},{:.3}", p.location.x, p.location.y))
-                        .collect::<Vec<_>>()
-                        .join(" "),
-                    stroke_attr
-                ));
-            }
-
-            EntityType::Circle(circle) => {
-                svg.push_str(&format!(
-                    r#"<circle cx="{:.3}" cy="{:.3}" r="{:.3}" {} />"#,
-                    circle.center.x, circle.center.y, circle.radius, stroke_attr
-                ));
-            }
-
-            EntityType::Arc(arc) => {
-                let start_angle = arc.start_angle.to_radians();
-                let end_angle = arc.end_angle.to_radians();
-                let start_x = arc.center.x + arc.radius * start_angle.cos();
-                let start_y = arc.center.y + arc.radius * start_angle.sin();
-                let end_x = arc.center.x + arc.radius * end_angle.cos();
-                let end_y = arc.center.y + arc.radius * end_angle.sin();
-                
-                let sweep = if end_angle > start_angle { 1 } else { 0 };
-                let large_arc = if (end_angle - start_angle).abs() % (2.0 * PI) > PI { 1 } else { 0 };
-                
-                svg.push_str(&format!(
-                    r#"<path d="M {:.3},{:.3} A {:.3},{:.3} 0 {} {} {:.3},{:.3}" {} />"#,
-                    start_x, start_y,
-                    arc.radius, arc.radius,
-                    large_arc, sweep,
-                    end_x, end_y,
-                    stroke_attr
-                ));
-            }
-
-            EntityType::Ellipse(ellipse) => {
-                let major_axis_length = (
-                    ellipse.major_axis.x.powi(2) + 
-                    ellipse.major_axis.y.powi(2)
-                ).sqrt();
-                
-                let rotation = ellipse.major_axis.y.atan2(ellipse.major_axis.x).to_degrees();
-                
-                svg.push_str(&format!(
-                    r#"<ellipse cx="{:.3}" cy="{:.3}" rx="{:.3}" ry="{:.3}" transform="rotate({:.3} {} {})" {} />"#,
-                    ellipse.center.x, ellipse.center.y,
-                    major_axis_length,
-                    major_axis_length * ellipse.minor_axis_ratio,
-                    rotation,
-                    ellipse.center.x, ellipse.center.y,  // Rotate around the center point
-                    stroke_attr
-                ));
+                (SvgSize::Auto, None) => r#"width="100%" height="100%" "#.to_string(),
+                (SvgSize::Width(pixels), _) => format!(r#"width="{}" height="{}" "#, pixels, pixels / aspect_ratio),
+                (SvgSize::Fixed { width, height }, _) => format!(r#"width="{}" height="{}" "#, width, height),
+                (SvgSize::Omit, _) => String::new(),
+            };
+
+            // Add a viewBox that ensures the content is visible and properly scaled
+            write!(
+                root,
+                r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink"{} 
+            viewBox="{} {} {} {}" {}
+            preserveAspectRatio="xMidYMid meet">"#,
+                root_attributes,
+                0, // Start at 0 for normalized coordinates
+                0,
+                1000.0, // Use fixed width for consistent scaling
+                1000.0 / aspect_ratio, // Height adjusted by aspect ratio
+                size
+            ).unwrap();
+            root.push_str(&document_info);
+
+            // Add a transform group to flip the Y axis and scale to normalized coordinates
+            write!(
+                opening,
+                r#"<g transform="scale({}, {}) translate({}, {})">"#,
+                1000.0 / width, // Scale X to normalize to 1000 units width
+                -1000.0 / width, // Scale Y (negative for flip) using same scale as X
+                -bounds.min_x, // Translate X to start at 0
+                -bounds.max_y  // Translate Y (after flip) to start at 0
+            ).unwrap();
+        } else {
+            write!(
+                root,
+                r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 100 100" xmlns:xlink="http://www.w3.org/1999/xlink"{}>"#,
+                root_attributes
+            ).unwrap();
+            root.push_str(&document_info);
+        }
+
+        // Add a white background rectangle (in normalized coordinates)
+        if options.background_color != "none" {
+            write!(
+                opening,
+                r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+                bounds.min_x,
+                -bounds.max_y,
+                width,
+                height,
+                options.background_color
+            ).unwrap();
+        }
+        (root, opening)
+    }
+
+    /// The entities by the `<g>` they're grouped in with `group_by: GroupBy::Layer`, or all of them ungrouped.
+    /// There are no empty groups, so without entities there are none.
+    fn groups(&self) -> Vec<(Option<String>, Vec<&'a Entity>)> {
+        if self.entities.is_empty() {
+            return vec![];
+        }
+        if self.options.group_by != GroupBy::Layer {
+            return vec![(None, self.entities.clone())];
+        }
+        let mut layers: Vec<(&str, Vec<&Entity>)> = vec![];
+        for entity in &self.entities {
+            let layer = entity.common.layer.as_str();
+            match layers.iter_mut().find(|(name, _)| name.eq_ignore_ascii_case(layer)) {
+                Some((_, layer_entities)) => layer_entities.push(entity),
+                None => layers.push((layer, vec![entity])),
             }
+        }
+        layers.into_iter().map(|(layer, entities)| (Some(svg_id("layer", layer)), entities)).collect()
+    }
 
-            EntityType::Spline(spline) => {
-                if spline.control_points.len() < 2 {
-                    continue;
-                }
+    /// The end of the document, after the last entity.
+    fn footer(&self) -> String {
+        let mut footer = String::new();
+        if self.options.use_bounds {
+            footer.push_str("</g>");
+        }
+        if self.options.css_classes {
+            footer.push_str(&self.style_sheet.style_element());
+        }
+        footer.push_str("</svg>");
+        footer
+    }
 
-                let mut path = String::new();
-                let points = &spline.control_points;
-                
-                // Start path at first point
-                path.push_str(&format!("M {:.3},{:.3} ", points[0].x, points[0].y));
-                
-                // Use cubic Bézier curves between points
-                let mut i = 1;
-                while i < points.len() - 2 {
-                    path.push_str(&format!("C {:.3},{:.3} {:.3},{:.3} {:.3},{:.3} ",
-                        points[i].x, points[i].y,
-                        points[i + 1].x, points[i + 1].y,
-                        points[i + 2].x, points[i + 2].y
-                    ));
-                    i += 3;
-                }
-                
-                svg.push_str(&format!(
-                    r#"<path d="{}" {} />"#,
-                    path.trim(),
-                    stroke_attr
-                ));
-            }
-
-            EntityType::Text(text) => {
-                // Escape special characters in text
-                let escaped_text = escape_xml_text(&text.value);
-                svg.push_str(&format!(
-                    r#"<text x="{:.3}" y="{:.3}" {}>{}</text>"#,
-                    text.location.x,
-                    text.location.y,
-                    stroke_attr,
-                    escaped_text
-                ));
-            }
-
-            EntityType::ModelPoint(point) => {
-                svg.push_str(&format!(
-                    r#"<circle cx="{:.3}" cy="{:.3}" r="1" {} />"#,
-                    point.location.x, point.location.y, stroke_attr
-                ));
-            }
-            EntityType::Face3D(face) => {
-                svg.push_str(&format!(
-                    r#"<polygon points="{:.3},{:.3} {:.3},{:.3} {:.3},{:.3} {:.3},{:.3}" {} />"#,
-                    face.first_corner.x, face.first_corner.y,
-                    face.second_corner.x, face.second_corner.y,
-                    face.third_corner.x, face.third_corner.y,
-                    face.fourth_corner.x, face.fourth_corner.y,
-                    stroke_attr
-                ));
-            }
-            EntityType::Solid(solid) => {
-                svg.push_str(&format!(
-                    r#"<polygon points="{:.3},{:.3} {:.3},{:.3} {:.3},{:.3} {:.3},{:.3}" {} />"#,
-                    solid.first_corner.x, solid.first_corner.y,
-                    solid.second_corner.x, solid.second_corner.y,
-                    solid.third_corner.x, solid.third_corner.y,
-                    solid.fourth_corner.x, solid.fourth_corner.y,
-                    stroke_attr
-                ));
-            }
-            EntityType::Leader(leader) => {
-                if leader.vertices.is_empty() {
-                    continue;
-                }
-                // Draw the leader line
-                svg.push_str(&format!(
-                    r#"<polyline points="{}" {} marker-end="url(#arrowhead)" />"#,
-                    leader.vertices.iter()
-                        .map(|p| format!("{:.3},{:.3}", p.x, p.y))
-                        .collect::<Vec<_>>()
-                        .join(" "),
-                    stroke_attr
-                ));
-                // Add arrowhead marker if not already added
-                if !svg.contains("def id=\"arrowhead\"") {
-                    svg.push_str(
-                        r#"<defs>
-                            <marker id="arrowhead" markerWidth="10" markerHeight="7" 
-                            refX="9" refY="3.5" orient="auto">
-                                <polygon points="0 0, 10 3.5, 0 7" fill="black"/>
-                            </marker>
-                        </defs>"#
-                    );
-                }
+    fn into_report(self, output_bytes: usize) -> ConversionReport {
+        let cancelled = self.progress.is_cancelled();
+        ConversionReport { cancelled, ..self.reporter.into_report(self.bounds, output_bytes) }
+    }
+}
+
+/// Converts the entities, `source` is the path of the file they came from, if any.
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(entities = entities.len())))]
+fn render_svg(
+    entities: Vec<&Entity>,
+    drawing: Option<&Drawing>,
+    source: Option<&Path>,
+    options: Option<SvgOptions>,
+) -> (String, ConversionReport) {
+    let conversion = Conversion::new(entities, drawing, source, options);
+    let ctx = conversion.context();
+    let (root, opening) = conversion.header();
+    let mut svg = String::with_capacity(conversion.entities.len() * BYTES_PER_ENTITY);
+    svg.push_str(&root);
+    // the definitions are only known once everything is rendered, they're inserted here afterwards
+    let defs_position = svg.len();
+    svg.push_str(&opening);
+
+    if let Some(drawing) = drawing {
+        render_block_defs(&conversion.entities, drawing, &ctx);
+    }
+
+    let group_blocks = conversion.options.group_by == GroupBy::Block;
+    for (layer, entities) in conversion.groups() {
+        match layer {
+            Some(id) => {
+                write!(svg, r#"<g id="{}">"#, id).unwrap();
+                render_group(&entities, group_blocks, &ctx, &mut svg);
+                svg.push_str("</g>");
+            }
+            None => render_group(&entities, group_blocks, &ctx, &mut svg),
+        }
+    }
+
+    svg.push_str(&conversion.footer());
+    svg.insert_str(defs_position, &conversion.defs.defs_element());
+    if conversion.options.pretty_print {
+        svg = pretty::pretty_print(&svg);
+    }
+    let output_bytes = svg.len();
+    (svg, conversion.into_report(output_bytes))
+}
+
+/**
+Converts entities like dxf_to_svg, but one piece at a time: the start of the document, then the SVG of each
+entity in turn, then the end of the document. Each entity is only rendered when its piece is asked for, so a
+server can stream a large conversion with back-pressure instead of holding all of it in memory.
+
+The document is the same as dxf_to_svg's, except that a WIPEOUT is always covered in the background color
+as `wipeout_masks` would have to change what was already sent, the definitions are written at the end, and
+`pretty_print` is ignored. Warnings are still passed to `on_warning`.
+
+Once `cancel` is set the remaining entities are skipped and the next piece is the end of the document, so
+what was sent stays well formed. Check the flag after the last piece to tell a cancelled stream from a full one.
+*/
+pub fn svg_fragments<'a>(entities: Vec<&'a Entity>, options: Option<SvgOptions>) -> impl Iterator<Item = String> + 'a {
+    let mut options = options.unwrap_or_default();
+    options.wipeout_masks = false;
+    let conversion = Conversion::new(entities, None, None, Some(options));
+    let groups = conversion.groups();
+    SvgFragments { conversion, groups, group: 0, index: 0, started: false, finished: false }
+}
+
+/// The pieces of a conversion, see svg_fragments.
+struct SvgFragments<'a> {
+    conversion: Conversion<'a>,
+    groups: Vec<(Option<String>, Vec<&'a Entity>)>,
+    /// The group and the index in it of the next entity
+    group: usize,
+    index: usize,
+    started: bool,
+    finished: bool,
+}
+
+impl Iterator for SvgFragments<'_> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        if !self.started {
+            self.started = true;
+            let (root, opening) = self.conversion.header();
+            return Some(root + &opening);
+        }
+        if self.finished {
+            return None;
+        }
+        if self.conversion.progress.is_cancelled() {
+            self.finished = true;
+            // close the layer group the last entity was left in
+            let open_group = self.groups.get(self.group).is_some_and(|(layer, _)| layer.is_some() && self.index > 0);
+            let closing = if open_group { "</g>" } else { "" };
+            return Some(closing.to_string() + &self.conversion.defs.defs_element() + &self.conversion.footer());
+        }
+        if let Some((layer, entities)) = self.groups.get(self.group) {
+            let ctx = self.conversion.context();
+            let group_blocks = self.conversion.options.group_by == GroupBy::Block;
+            let mut fragment = String::with_capacity(BYTES_PER_ENTITY);
+            if let (0, Some(id)) = (self.index, layer) {
+                write!(fragment, r#"<g id="{}">"#, id).unwrap();
             }
-            EntityType::Helix(helix) => {
-                // Approximate helix as a spiral path in 2D
-                let mut path = format!("M {:.3},{:.3} ", helix.start_point.x, helix.start_point.y);
-                let turns = helix.number_of_turns as i32;
-                let points_per_turn = 16;
-                let total_points = turns * points_per_turn;
-                
-                for i in 1..=total_points {
-                    let angle = (i as f64) * 2.0 * PI / (points_per_turn as f64);
-                    let radius = helix.radius * (i as f64) / (total_points as f64);
-                    let x = helix.axis_base_point.x + radius * angle.cos();
-                    let y = helix.axis_base_point.y + radius * angle.sin();
-                    path.push_str(&format!("L {:.3},{:.3} ", x, y));
+            render_group_entity(entities[self.index], group_blocks, 0, &ctx, &mut fragment);
+            self.index += 1;
+            if self.index == entities.len() {
+                if layer.is_some() {
+                    fragment.push_str("</g>");
                 }
-                
-                svg.push_str(&format!(
-                    r#"<path d="{}" {} />"#,
-                    path.trim(),
-                    stroke_attr
-                ));
-            }
-            EntityType::Trace(trace) => {
-                svg.push_str(&format!(
-                    r#"<polygon points="{:.3},{:.3} {:.3},{:.3} {:.3},{:.3} {:.3},{:.3}" {} />"#,
-                    trace.first_corner.x, trace.first_corner.y,
-                    trace.second_corner.x, trace.second_corner.y,
-                    trace.third_corner.x, trace.third_corner.y,
-                    trace.fourth_corner.x, trace.fourth_corner.y,
-                    stroke_attr
-                ));
-            }
-            EntityType::Shape(shape) => {
-                // Render shape as a rectangle with the given size
-                let half_size = shape.size / 2.0;
-                svg.push_str(&format!(
-                    r#"<rect x="{:.3}" y="{:.3}" width="{:.3}" height="{:.3}" 
-                    transform="rotate({:.3} {} {})" {} />"#,
-                    shape.location.x - half_size,
-                    shape.location.y - half_size,
-                    shape.size,
-                    shape.size,
-                    shape.rotation_angle,
-                    shape.location.x,
-                    shape.location.y,
-                    stroke_attr
-                ));
-            }
-            EntityType::RotatedDimension(dimension) => {
-                let start_point = &dimension.definition_point_2; // Start of dimension line
-                let end_point = &dimension.definition_point_3;   // End of dimension line
-                let text_position = &dimension.insertion_point;    // Midpoint for text
-                let measurement = &dimension.dimension_base.text; // Measurement text
-                // Add the dimension line
-                svg.push_str(&format!(
-                    r#"<line x1="{}" y1="{}" x2="{}" y2="{}" {stroke_attr} />"#,
-                    start_point.x,
-                    start_point.y,
-                    end_point.x,
-                    end_point.y,
-                    stroke_attr = stroke_attr
-                ));
-            
-                // Add the dimension text
-                svg.push_str(&format!(
-                    r#"<text x="{}" y="{}" {stroke_attr} font-size="12" text-anchor="middle">{text}</text>"#,
-                    text_position.x,
-                    text_position.y,
-                    stroke_attr = stroke_attr,
-                    text = measurement
-                ));
-            }
-            _ => {
-                println!("Unsupported entity type: {:?}", entity.common.layer);
-                continue;
+                self.group += 1;
+                self.index = 0;
             }
+            return Some(fragment);
         }
+        self.finished = true;
+        Some(self.conversion.defs.defs_element() + &self.conversion.footer())
     }
+}
 
-    if options.use_bounds {
-        svg.push_str("</g>");
+/**
+Renders the entities of a group in order, wrapping each block reference in a `<g data-block="NAME">` if
+`group_blocks` is set. With the `rayon` feature each entity is rendered into its own buffer in parallel
+and the buffers are joined in order; only the wipeouts masking what was drawn before them wait for that.
+*/
+fn render_group(entities: &[&Entity], group_blocks: bool, ctx: &RenderContext, svg: &mut String) {
+    let start = svg.len();
+    #[cfg(feature = "rayon")]
+    {
+        use rayon::prelude::*;
+        let fragments: Vec<Option<String>> = entities
+            .par_iter()
+            .map(|entity| {
+                let masks = matches!(entity.specific, EntityType::Wipeout(_)) && ctx.options.wipeout_masks;
+                (!masks).then(|| {
+                    let mut fragment = String::with_capacity(BYTES_PER_ENTITY);
+                    render_group_entity(entity, group_blocks, start, ctx, &mut fragment);
+                    fragment
+                })
+            })
+            .collect();
+        for (entity, fragment) in entities.iter().zip(fragments) {
+            match fragment {
+                Some(fragment) => svg.push_str(&fragment),
+                None => render_group_entity(entity, group_blocks, start, ctx, svg),
+            }
+        }
+    }
+    #[cfg(not(feature = "rayon"))]
+    for entity in entities {
+        render_group_entity(entity, group_blocks, start, ctx, svg);
     }
-    svg.push_str("</svg>");
-    svg
 }
 
-pub fn dxf_file_to_svg(file_path: &str, options: Option<SvgOptions>) -> String {
-    let entities = dxf::Drawing::load_file(file_path).unwrap();
-    dxf_to_svg(entities.entities().collect(), options)
+fn render_group_entity(entity: &Entity, group_blocks: bool, start: usize, ctx: &RenderContext, svg: &mut String) {
+    if ctx.progress.is_cancelled() {
+        return;
+    }
+    match &entity.specific {
+        EntityType::Insert(insert) if group_blocks => {
+            write!(svg, r#"<g data-block="{}">"#, escape_xml_text(&insert.name)).unwrap();
+            render_entity(entity, ctx, svg);
+            svg.push_str("</g>");
+        }
+        _ => render_top_level_entity(entity, start, ctx, svg),
+    }
+    ctx.progress.step();
 }
 
-/// Escape special characters in XML text content
-fn escape_xml_text(text: &str) -> String {
-    text.replace('&', "&amp;")
-        .replace('<', "&lt;")
-        .replace('>', "&gt;")
-        .replace('"', "&quot;")
-        .replace('\'', "&apos;")
+/**
+Renders an entity that isn't part of a block, where `start` is where the entities drawn before it in
+the same group begin. With `wipeout_masks` a WIPEOUT masks those entities instead of being drawn.
+*/
+fn render_top_level_entity(entity: &Entity, start: usize, ctx: &RenderContext, svg: &mut String) {
+    match (&entity.specific, ctx.view) {
+        (EntityType::Wipeout(wipeout), Some(view)) if ctx.options.wipeout_masks => {
+            ctx.reporter.entity(entity_type_name(&entity.specific));
+            // masks are numbered by how many definitions there are before them, as wipeouts don't need a handle
+            let id = format!("wipeout-{}", ctx.defs.len() + 1);
+            if let Some(mask) = wipeout::mask_behind(wipeout, start, &id, view, ctx.precision, svg) {
+                ctx.defs.add(&id, mask);
+            }
+        }
+        _ => render_entity(entity, ctx, svg),
+    }
 }
 
-#[cfg(test)]
-mod tests {
-    use std::fs;
+/// Render a single entity into the svg buffer.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        level = "trace",
+        skip_all,
+        fields(handle = entity.common.handle.0, entity = entity_type_name(&entity.specific))
+    )
+)]
+fn render_entity(entity: &Entity, ctx: &RenderContext, svg: &mut String) {
+    ctx.reporter.entity(entity_type_name(&entity.specific));
+    let start = svg.len();
+    render_entity_elements(entity, ctx, svg);
 
-    use super::*;
-    use dxf::entities::Line;
-    use dxf::Point;
+    let mut attributes = vec![];
+    if ctx.options.entity_ids && !entity.common.handle.is_empty() {
+        attributes.push(format!(r#"id="e{}""#, entity.common.handle.as_string()));
+    }
+    if ctx.options.data_attributes {
+        attributes.push(format!(r#"data-layer="{}""#, escape_xml_text(&entity.common.layer)));
+        attributes.push(format!(r#"data-entity="{}""#, entity_type_name(&entity.specific)));
+    }
+    let title = ctx.options.tooltip.as_ref().and_then(|tooltip| tooltip.text(entity));
+    if !attributes.is_empty() || title.is_some() {
+        annotate::annotate(svg, start, &attributes.join(" "), title.as_deref());
+    }
+    if ctx.options.hyperlinks {
+        if let Some(link) = annotate::hyperlink(entity) {
+            annotate::add_link(svg, start, &link);
+        }
+    }
+}
 
-    #[test]
-    fn test_basic_entities() {
-        // Test empty vector
-        let empty_svg = dxf_to_svg(vec![], Some(SvgOptions {
+/// Writes the elements drawing an entity.
+fn render_entity_elements(entity: &Entity, ctx: &RenderContext, svg: &mut String) {
+    let precision = ctx.precision;
+    let wcs = ocs::to_wcs(entity);
+    // block contents are drawn in the block's own top view, the insert places them
+    let projected = if ctx.in_block {
+        Some(wcs)
+    } else {
+        projection::project(&wcs, &ctx.options.projection, ctx.options.point_transform.as_deref())
+    };
+    let Some(projected) = projected else {
+        let entity_type = entity_type_name(&entity.specific);
+        ctx.reporter.skipped(entity_type);
+        ctx.reporter.warn(report::Warning::Untransformable { handle: entity.common.handle.0, entity_type });
+        return;
+    };
+    let entity = &*projected;
+    let style = style::entity_style(entity, ctx);
+    let stroke_attr = ctx.stroke_attributes(&style, entity);
+    let color = &style.color;
+    let closed_fill = if ctx.options.fill_closed_polylines { color.as_str() } else { "none" };
+    if !ctx.options.renderers.is_empty() {
+        let entity_ctx = EntityContext {
+            drawing: ctx.drawing,
+            precision,
+            color,
+            stroke_attributes: &stroke_attr,
+            in_block: ctx.in_block,
+        };
+        if let Some(fragment) = ctx.options.renderers.render(entity, &entity_ctx) {
+            svg.push_str(&fragment.0);
+            return;
+        }
+    }
+
+    match &entity.specific {
+        EntityType::Line(line) => {
+            write!(
+                svg,
+                r#"<line x1="{:.p$}" y1="{:.p$}" x2="{:.p$}" y2="{:.p$}" {} fill="none" />"#,
+                line.p1.x, line.p1.y, line.p2.x, line.p2.y, stroke_attr, p = precision
+            ).unwrap();
+        }
+
+        EntityType::Insert(insert) => {
+            let block = ctx.drawing.map(|drawing| find_block(drawing, &insert.name));
+            if let Some(None) = block {
+                ctx.reporter.warn(report::Warning::MissingBlock {
+                    handle: entity.common.handle.0,
+                    block: insert.name.clone(),
+                });
+            }
+            let base_point = block.flatten().map(|block| &block.base_point);
+            // MINSERTs repeat the block in a grid of rows and columns
+            for row in 0..insert.row_count.max(1) {
+                for column in 0..insert.column_count.max(1) {
+                    write!(
+                        svg,
+                        r##"<use href="#{}" transform="{}" color="{}" />"##,
+                        block_id(&insert.name),
+                        insert_transform(insert, base_point, f64::from(column), f64::from(row), precision),
+                        color
+                    ).unwrap();
+                }
+            }
+            // attributes are stored where they're shown, not in block coordinates
+            for attribute in insert.attributes() {
+                if attribute.is_invisible() && !ctx.options.invisible_attributes {
+                    continue;
+                }
+                let mut text = Entity::new(EntityType::Text(text::attribute_text(attribute)));
+                text.common = entity.common.clone();
+                render_entity_elements(&text, ctx, svg);
+            }
+        }
+
+        EntityType::LwPolyline(lwpolyline) => {
+            render_polyline(&lwpolyline_vertices(lwpolyline), lwpolyline.is_closed(), closed_fill, &style, entity, ctx, svg);
+        }
+
+        EntityType::Polyline(polyline) => {
+            if let Some(edges) = mesh_edges(polyline) {
+                render_mesh_edges(&edges, &stroke_attr, ctx, svg);
+                return;
+            }
+            render_polyline(&polyline_vertices(polyline), polyline.is_closed(), closed_fill, &style, entity, ctx, svg);
+        }
+
+        EntityType::Circle(circle) => {
+            write!(
+                svg,
+                r#"<circle cx="{:.p$}" cy="{:.p$}" r="{:.p$}" {} />"#,
+                circle.center.x, circle.center.y, circle.radius, stroke_attr, p = precision
+            ).unwrap();
+        }
+
+        EntityType::Arc(arc) => {
+            let start_angle = arc.start_angle.to_radians();
+            let end_angle = arc.end_angle.to_radians();
+            let start_x = arc.center.x + arc.radius * start_angle.cos();
+            let start_y = arc.center.y + arc.radius * start_angle.sin();
+            let end_x = arc.center.x + arc.radius * end_angle.cos();
+            let end_y = arc.center.y + arc.radius * end_angle.sin();
+            
+            let sweep = if end_angle > start_angle { 1 } else { 0 };
+            let large_arc = if (end_angle - start_angle).abs() % (2.0 * PI) > PI { 1 } else { 0 };
+            
+            write!(
+                svg,
+                r#"<path d="M {:.p$},{:.p$} A {:.p$},{:.p$} 0 {} {} {:.p$},{:.p$}" {} />"#,
+                start_x, start_y,
+                arc.radius, arc.radius,
+                large_arc, sweep,
+                end_x, end_y,
+                stroke_attr, p = precision
+            ).unwrap();
+        }
+
+        EntityType::Ellipse(ellipse) => {
+            let shape = ellipse_shape(ellipse);
+            let axes = shape.axes();
+
+            if geometry::is_full_turn(ellipse.start_parameter, ellipse.end_parameter) {
+                write!(
+                    svg,
+                    r#"<ellipse cx="{:.p$}" cy="{:.p$}" rx="{:.p$}" ry="{:.p$}" transform="rotate({:.p$} {} {})" {} />"#,
+                    ellipse.center.x, ellipse.center.y,
+                    axes.radius_x,
+                    axes.radius_y,
+                    axes.rotation,
+                    ellipse.center.x, ellipse.center.y,  // Rotate around the center point
+                    stroke_attr, p = precision
+                ).unwrap();
+                return;
+            }
+
+            // counterclockwise parameters are a positive sweep inside the flipped group,
+            // unless the ellipse is seen from behind
+            let sweep = (ellipse.end_parameter - ellipse.start_parameter).rem_euclid(2.0 * PI);
+            let large_arc = if sweep > PI { 1 } else { 0 };
+            let sweep_flag = if axes.counterclockwise { 1 } else { 0 };
+            let (start_x, start_y) = shape.point(ellipse.start_parameter);
+            let (end_x, end_y) = shape.point(ellipse.end_parameter);
+            write!(
+                svg,
+                r#"<path d="M {:.p$},{:.p$} A {:.p$},{:.p$} {:.p$} {} {} {:.p$},{:.p$}" {} fill="none" />"#,
+                start_x, start_y,
+                axes.radius_x, axes.radius_y,
+                axes.rotation,
+                large_arc, sweep_flag,
+                end_x, end_y,
+                stroke_attr, p = precision
+            ).unwrap();
+        }
+
+        EntityType::Spline(spline) => {
+            let points = spline::spline_points(spline, ctx.options.curve_tolerance);
+            if points.len() < 2 {
+                return;
+            }
+            write!(svg, r#"<path d="{}" {} fill="none" />"#, emit::PathData(&points, precision), stroke_attr).unwrap();
+        }
+
+        EntityType::Text(text) => {
+            let style = text::TextStyle::resolve(&text.text_style_name, ctx.drawing, &ctx.options.font_map);
+            if let Some(font) = &style.stroke_font {
+                shx::render_text(text, &style, font, &stroke_attr, precision, svg);
+                return;
+            }
+            #[cfg(feature = "text-paths")]
+            if let Some(font) = &ctx.options.text_font {
+                outline::render_text(text, &style, font, color, precision, svg);
+                return;
+            }
+            text::render_text(text, &style, color, precision, svg);
+        }
+
+        EntityType::MText(mtext) => {
+            let style = text::TextStyle::resolve(&mtext.text_style_name, ctx.drawing, &ctx.options.font_map);
+            mtext::render_mtext(mtext, &style, color, precision, svg);
+        }
+
+        EntityType::ModelPoint(point) => {
+            let header = ctx.drawing.map(|drawing| &drawing.header);
+            let mode = ctx.options.point_mode.or(header.map(|header| header.point_display_mode)).unwrap_or(0);
+            let size = ctx.options.point_size.or(header.map(|header| header.point_display_size)).unwrap_or(0.0);
+            let view_height = ctx.view.map_or(0.0, |view| view.max_y - view.min_y);
+            point::render_point(
+                (point.location.x, point.location.y),
+                point::PointDisplay { mode, size: point::point_size(size, view_height) },
+                style.width / 2.0,
+                &stroke_attr,
+                color,
+                precision,
+                svg,
+            );
+        }
+        EntityType::Face3D(face) => {
+            // faces of meshed surfaces hide their diagonals, so only the visible edges are drawn
+            let edges = [
+                (&face.first_corner, &face.second_corner, face.is_first_edge_invisible()),
+                (&face.second_corner, &face.third_corner, face.is_second_edge_invisible()),
+                (&face.third_corner, &face.fourth_corner, face.is_third_edge_invisible()),
+                (&face.fourth_corner, &face.first_corner, face.is_fourth_edge_invisible()),
+            ];
+            for (start, end, invisible) in edges {
+                // triangles repeat their third corner as the fourth
+                if invisible || (start.x, start.y) == (end.x, end.y) {
+                    continue;
+                }
+                write!(
+                    svg,
+                    r#"<line x1="{:.p$}" y1="{:.p$}" x2="{:.p$}" y2="{:.p$}" {} fill="none" />"#,
+                    start.x, start.y, end.x, end.y, stroke_attr, p = precision
+                ).unwrap();
+            }
+        }
+        EntityType::Solid(solid) => {
+            let corners = [&solid.first_corner, &solid.second_corner, &solid.third_corner, &solid.fourth_corner];
+            render_filled_quad(corners, &stroke_attr, color, precision, svg);
+        }
+        EntityType::Leader(leader) => {
+            leader::render_leader(leader, ctx.drawing, &stroke_attr, color, precision, ctx.options.curve_tolerance, svg);
+            // the annotation is an entity of its own, drawn here only if it isn't converted anyway
+            let annotation = ctx.drawing.and_then(|drawing| leader.get_associated_annotation(drawing));
+            if let (Some(dxf::DrawingItem::Entity(annotation)), false) = (annotation, ctx.in_block) {
+                let converted = ctx.entity_handles.contains(&annotation.common.handle.0);
+                if !converted && is_layer_included(ctx.options, &annotation.common.layer) {
+                    render_entity_elements(annotation, ctx, svg);
+                }
+            }
+        }
+        EntityType::Ray(ray) => {
+            let direction = &ray.unit_direction_vector;
+            let origin = (ray.start_point.x, ray.start_point.y);
+            render_construction_line(origin, (direction.x, direction.y), 0.0, &stroke_attr, ctx, svg);
+        }
+        EntityType::XLine(xline) => {
+            let direction = &xline.unit_direction_vector;
+            let origin = (xline.first_point.x, xline.first_point.y);
+            render_construction_line(origin, (direction.x, direction.y), f64::NEG_INFINITY, &stroke_attr, ctx, svg);
+        }
+        EntityType::Image(image) => {
+            if image.image_size.x <= 0.0 || image.image_size.y <= 0.0 {
+                ctx.reporter.warn(degenerate(entity, "has no size"));
+            }
+            let paths = image::ImagePaths {
+                source_dir: ctx.source_dir,
+                output_dir: ctx.options.image_output_dir.as_deref(),
+                embed: ctx.options.embed_images,
+            };
+            image::render_image(image, ctx.drawing, paths, precision, svg);
+        }
+        EntityType::Wipeout(wipeout) => {
+            // a transparent background would leave what's underneath showing
+            let fill = match ctx.options.background_color.as_str() {
+                "none" | "transparent" => "white",
+                background => background,
+            };
+            if wipeout::outline(wipeout).len() < 3 {
+                ctx.reporter.warn(degenerate(entity, "has fewer than 3 clipping vertices"));
+            }
+            wipeout::render_wipeout(wipeout, fill, precision, svg);
+        }
+        EntityType::ProxyEntity(proxy) => {
+            for graphic in proxy::parse_graphics(&proxy.graphics_data_string, code_page::drawing_encoding(ctx.drawing)) {
+                let mut style = style.clone();
+                if let Some(color) = &graphic.color {
+                    style.color = color.clone();
+                }
+                let stroke_attr = ctx.stroke_attributes(&style, entity);
+                proxy::render_graphic(&graphic, &stroke_attr, &style.color, precision, svg);
+            }
+        }
+        EntityType::MLine(mline) => {
+            mline::render_mline(mline, ctx.drawing, &stroke_attr, precision, svg);
+        }
+        EntityType::Tolerance(tolerance) => {
+            tolerance::render_tolerance(tolerance, ctx.drawing, &stroke_attr, color, precision, svg);
+        }
+        EntityType::Helix(helix) => {
+            let points = helix::helix_points(helix, ctx.options.curve_tolerance);
+            if points.len() < 2 {
+                return;
+            }
+            write!(svg, r#"<path d="{}" {} fill="none" />"#, emit::PathData(&points, precision), stroke_attr).unwrap();
+        }
+        EntityType::Trace(trace) => {
+            let corners = [&trace.first_corner, &trace.second_corner, &trace.third_corner, &trace.fourth_corner];
+            render_filled_quad(corners, &stroke_attr, color, precision, svg);
+        }
+        EntityType::Shape(shape) => {
+            if let Some((font, number)) = ctx.options.font_map.find_shape(&shape.name) {
+                shx::render_shape(shape, font, number, &stroke_attr, precision, svg);
+                return;
+            }
+            // Render shape as a rectangle with the given size
+            let half_size = shape.size / 2.0;
+            write!(
+                svg,
+                r#"<rect x="{:.p$}" y="{:.p$}" width="{:.p$}" height="{:.p$}" 
+                transform="rotate({:.p$} {} {})" {} />"#,
+                shape.location.x - half_size,
+                shape.location.y - half_size,
+                shape.size,
+                shape.size,
+                shape.rotation_angle,
+                shape.location.x,
+                shape.location.y,
+                stroke_attr, p = precision
+            ).unwrap();
+        }
+        specific @ (EntityType::RotatedDimension(_)
+        | EntityType::AlignedDimension(_)
+        | EntityType::RadialDimension(_)
+        | EntityType::DiameterDimension(_)
+        | EntityType::AngularThreePointDimension(_)
+        | EntityType::OrdinateDimension(_)) => {
+            // the block AutoCAD drew the dimension into is the most faithful picture of it
+            let block = ctx.drawing
+                .zip(dimension::block_name(specific))
+                .and_then(|(drawing, name)| find_block(drawing, name));
+            // the block is in drawing coordinates, so it's only right without a projection or point transform
+            let untransformed = projection::is_identity(&ctx.options.projection, ctx.options.point_transform.as_deref());
+            if let (true, true, Some(block)) = (ctx.options.dimension_blocks, untransformed, block) {
+                write!(svg, r##"<use href="#{}" color="{}" />"##, block_id(&block.name), color).unwrap();
+                return;
+            }
+            let style = dimension::DimensionStyle::for_dimension(specific, ctx.drawing);
+            let writer = dimension::DimensionWriter { style: &style, stroke_attr: &stroke_attr, color, precision };
+            writer.render(specific, svg);
+        }
+        // records ending and making up old style polylines, nothing to draw on their own
+        EntityType::Seqend(_) | EntityType::Vertex(_) => {}
+        _ => {
+            if let Some(on_unsupported) = ctx.on_unsupported {
+                (on_unsupported.lock().unwrap())(entity);
+            }
+            ctx.reporter.skipped(entity_type_name(&entity.specific));
+            ctx.reporter.warn(report::Warning::Unsupported {
+                handle: entity.common.handle.0,
+                entity_type: entity_type_name(&entity.specific),
+            });
+        }
+    }
+}
+
+/// A warning that an entity's geometry can't be drawn.
+fn degenerate(entity: &Entity, reason: &'static str) -> report::Warning {
+    report::Warning::Degenerate {
+        handle: entity.common.handle.0,
+        entity_type: entity_type_name(&entity.specific),
+        reason,
+    }
+}
+
+/// Draws the part of a ray or construction line that crosses the view, starting at `start` along its direction.
+fn render_construction_line(
+    origin: (f64, f64),
+    direction: (f64, f64),
+    start: f64,
+    stroke_attr: &str,
+    ctx: &RenderContext,
+    svg: &mut String,
+) {
+    let Some(view) = ctx.view else {
+        return;
+    };
+    let clipped = geometry::clip_line(origin, direction, start, (view.min_x, view.min_y), (view.max_x, view.max_y));
+    if let Some((from, to)) = clipped {
+        write!(
+            svg,
+            r#"<line x1="{:.p$}" y1="{:.p$}" x2="{:.p$}" y2="{:.p$}" {} fill="none" />"#,
+            from.0, from.1, to.0, to.1, stroke_attr, p = ctx.precision
+        ).unwrap();
+    }
+}
+
+/**
+Draws a SOLID or TRACE filled. Their corners zigzag, the third being across from the first, so the last
+two are swapped to go around the outline instead of drawing a bowtie. Three corner solids repeat the third.
+*/
+fn render_filled_quad(corners: [&Point; 4], stroke_attr: &str, fill: &str, precision: usize, svg: &mut String) {
+    let [first, second, third, fourth] = corners;
+    let mut outline = vec![first, second, fourth, third];
+    if (fourth.x, fourth.y) == (third.x, third.y) {
+        outline.remove(2);
+    }
+    let points: Vec<(f64, f64)> = outline.iter().map(|point| (point.x, point.y)).collect();
+    write!(svg, r#"<polygon points="{}" {} fill="{}" />"#, emit::Points(&points, precision), stroke_attr, fill).unwrap();
+}
+
+/// The ellipse as seen from above. Its minor axis lies a quarter turn counterclockwise from the major
+/// axis around its normal, which may tilt the ellipse out of the XY plane.
+fn ellipse_shape(ellipse: &dxf::entities::Ellipse) -> geometry::EllipseShape {
+    let normal = &ellipse.normal;
+    let (nx, ny, nz) = if normal.x == 0.0 && normal.y == 0.0 && normal.z == 0.0 {
+        (0.0, 0.0, 1.0)
+    } else {
+        let length = (normal.x * normal.x + normal.y * normal.y + normal.z * normal.z).sqrt();
+        (normal.x / length, normal.y / length, normal.z / length)
+    };
+    let major = &ellipse.major_axis;
+    let ratio = ellipse.minor_axis_ratio;
+    geometry::EllipseShape {
+        center: (ellipse.center.x, ellipse.center.y),
+        major_axis: (major.x, major.y),
+        minor_axis: ((ny * major.z - nz * major.y) * ratio, (nz * major.x - nx * major.z) * ratio),
+    }
+}
+
+/// Render every block referenced by the given entities as a `<symbol>` in the definitions.
+/// Blocks inserted by those blocks are rendered too, so nested inserts resolve completely.
+fn render_block_defs(entities: &[&Entity], drawing: &Drawing, ctx: &RenderContext) {
+    let mut rendered = vec![];
+    for entity in entities {
+        if ctx.progress.is_cancelled() {
+            return;
+        }
+        if let EntityType::Insert(insert) = &entity.specific {
+            render_block_symbol(&insert.name, drawing, ctx, &mut vec![], &mut rendered);
+        }
+        if let (true, Some(name)) = (ctx.options.dimension_blocks, dimension::block_name(&entity.specific)) {
+            render_block_symbol(name, drawing, ctx, &mut vec![], &mut rendered);
+        }
+    }
+}
+
+/// Render a block and, depth first, every block it inserts.
+/// `stack` holds the blocks currently being expanded: an insert of one of those would make the
+/// symbol reference itself, so it's skipped instead of recursing forever.
+fn render_block_symbol(
+    name: &str,
+    drawing: &Drawing,
+    ctx: &RenderContext,
+    stack: &mut Vec<String>,
+    rendered: &mut Vec<String>,
+) {
+    if rendered.iter().any(|done| done.eq_ignore_ascii_case(name)) {
+        return;
+    }
+    let Some(block) = find_block(drawing, name) else {
+        return;
+    };
+    rendered.push(block.name.clone());
+    stack.push(block.name.clone());
+
+    let block_ctx = RenderContext {
+        in_block: true,
+        ..*ctx
+    };
+    let id = block_id(&block.name);
+    let mut symbol = format!(r#"<symbol id="{}" overflow="visible">"#, id);
+    let mut entities: Vec<&Entity> = block.entities.iter().collect();
+    draw_order::sort_by_draw_order(&mut entities, drawing);
+    for entity in entities {
+        if !is_layer_included(ctx.options, &entity.common.layer) || !is_drawable(entity, ctx.options, Some(ctx.reporter)) {
+            continue;
+        }
+        if let EntityType::Insert(insert) = &entity.specific {
+            if stack.iter().any(|parent| parent.eq_ignore_ascii_case(&insert.name)) {
+                ctx.reporter.warn(report::Warning::RecursiveInsert {
+                    block: insert.name.clone(),
+                    parent: block.name.clone(),
+                });
+                continue;
+            }
+            render_block_symbol(&insert.name, drawing, ctx, stack, rendered);
+        }
+        render_entity(entity, &block_ctx, &mut symbol);
+    }
+    symbol.push_str("</symbol>");
+    ctx.defs.add(&id, symbol);
+
+    stack.pop();
+}
+
+/**
+True if the entity can be drawn: its coordinates are valid, and it isn't degenerate when `skip_degenerate`
+is set. Entities that can't are reported as skipped to the `reporter`, if there is one. With `strict`,
+degenerate entities are reported even when they're drawn.
+*/
+fn is_drawable(entity: &Entity, options: &SvgOptions, reporter: Option<&report::Reporter>) -> bool {
+    let entity_type = entity_type_name(&entity.specific);
+    let warning = if !sanitize::has_valid_coordinates(entity) {
+        report::Warning::InvalidCoordinates { handle: entity.common.handle.0, entity_type }
+    } else if let Some(reason) = sanitize::degenerate_reason(entity) {
+        if !options.skip_degenerate {
+            if let (true, Some(reporter)) = (options.strict, reporter) {
+                reporter.warn(degenerate(entity, reason));
+            }
+            return true;
+        }
+        degenerate(entity, reason)
+    } else {
+        return true;
+    };
+    if let Some(reporter) = reporter {
+        reporter.entity(entity_type);
+        reporter.skipped(entity_type);
+        reporter.warn(warning);
+    }
+    false
+}
+
+/// True if the entity passes the `layer_filter`, `z_range` and `entity_filter`.
+fn is_converted(options: &SvgOptions, entity: &Entity) -> bool {
+    is_layer_included(options, &entity.common.layer)
+        && is_in_z_range(options, entity)
+        && options.entity_filter.as_ref().is_none_or(|filter| filter(entity))
+}
+
+fn is_layer_included(options: &SvgOptions, layer: &str) -> bool {
+    options.layer_filter.as_ref().is_none_or(|filter| filter.allows(layer))
+}
+
+/// True if every Z coordinate of the entity lies inside `z_range`, or there is no range.
+fn is_in_z_range(options: &SvgOptions, entity: &Entity) -> bool {
+    let Some((min_z, max_z)) = options.z_range else {
+        return true;
+    };
+    let entity = ocs::to_wcs(entity);
+    let z_values: Vec<f64> = match &entity.specific {
+        EntityType::Line(line) => vec![line.p1.z, line.p2.z],
+        EntityType::Circle(circle) => vec![circle.center.z],
+        EntityType::Arc(arc) => vec![arc.center.z],
+        EntityType::Ellipse(ellipse) => vec![ellipse.center.z],
+        EntityType::LwPolyline(lwpolyline) => match ocs::Ocs::new(&lwpolyline.extrusion_direction) {
+            Some(ocs) => lwpolyline.vertices.iter()
+                .map(|vertex| ocs.world_point(vertex.x, vertex.y, lwpolyline.elevation).2)
+                .collect(),
+            None => vec![lwpolyline.elevation],
+        },
+        EntityType::Polyline(polyline) => {
+            let ocs = if polyline::is_3d(polyline) { None } else { ocs::Ocs::new(&polyline.normal) };
+            polyline.vertices()
+                .map(|vertex| match &ocs {
+                    Some(ocs) => ocs.world_point(vertex.location.x, vertex.location.y, vertex.location.z).2,
+                    None => vertex.location.z,
+                })
+                .collect()
+        }
+        EntityType::Spline(spline) => spline.control_points.iter().chain(&spline.fit_points).map(|point| point.z).collect(),
+        EntityType::Text(text) => vec![text.location.z],
+        EntityType::ModelPoint(point) => vec![point.location.z],
+        EntityType::Face3D(face) => vec![face.first_corner.z, face.second_corner.z, face.third_corner.z, face.fourth_corner.z],
+        EntityType::Solid(solid) => vec![solid.first_corner.z, solid.second_corner.z, solid.third_corner.z, solid.fourth_corner.z],
+        EntityType::Trace(trace) => vec![trace.first_corner.z, trace.second_corner.z, trace.third_corner.z, trace.fourth_corner.z],
+        EntityType::Leader(leader) => leader.vertices.iter().map(|vertex| vertex.z).collect(),
+        EntityType::Insert(insert) => vec![insert.location.z],
+        EntityType::Shape(shape) => vec![shape.location.z],
+        _ => vec![],
+    };
+    z_values.iter().all(|z| (min_z..=max_z).contains(z))
+}
+
+fn find_block<'a>(drawing: &'a Drawing, name: &str) -> Option<&'a Block> {
+    drawing.blocks().find(|block| block.name.eq_ignore_ascii_case(name))
+}
+
+/// The id of the `<symbol>` generated for a block.
+fn block_id(name: &str) -> String {
+    svg_id("block", name)
+}
+
+/// Builds an element id from a DXF name. Names may contain characters that aren't valid in an id
+/// (`*Model_Space`, spaces), so those are replaced.
+fn svg_id(prefix: &str, name: &str) -> String {
+    let sanitized: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    format!("{}-{}", prefix, sanitized)
+}
+
+/// Builds the transform placing a block's geometry at an insert: the block's base point is moved
+/// to the origin, then scaled, offset to its cell of a MINSERT array, rotated and moved to the
+/// insertion point.
+fn insert_transform(insert: &Insert, base_point: Option<&Point>, column: f64, row: f64, precision: usize) -> String {
+    let mut transform = format!("translate({:.p$} {:.p$})", insert.location.x, insert.location.y, p = precision);
+    if insert.rotation != 0.0 {
+        write!(transform, " rotate({:.p$})", insert.rotation, p = precision).unwrap();
+    }
+    // the array spacing is measured along the rotated axes but isn't affected by the scale
+    let offset_x = column * insert.column_spacing;
+    let offset_y = row * insert.row_spacing;
+    if offset_x != 0.0 || offset_y != 0.0 {
+        write!(transform, " translate({:.p$} {:.p$})", offset_x, offset_y, p = precision).unwrap();
+    }
+    write!(transform, " scale({:.p$} {:.p$})", insert.x_scale_factor, insert.y_scale_factor, p = precision).unwrap();
+    if let Some(base) = base_point {
+        if base.x != 0.0 || base.y != 0.0 {
+            write!(transform, " translate({:.p$} {:.p$})", -base.x, -base.y, p = precision).unwrap();
+        }
+    }
+    transform
+}
+
+pub fn dxf_file_to_svg(file_path: &str, options: Option<SvgOptions>) -> String {
+    let drawing = load_drawing(file_path).unwrap();
+    render_svg(drawing.entities().collect(), Some(&drawing), Some(Path::new(file_path)), options).0
+}
+
+/**
+Converts a dxf file and writes it gzip-compressed as an `.svgz` file, which is usually
+a fraction of the size of the plain SVG.
+
+* `file_path` - the dxf file to convert.
+* `output_path` - where to write the `.svgz` file.
+
+Fails if the dxf file can't be read or parsed, parse errors being `InvalidData`, or the output can't be written.
+*/
+#[cfg(feature = "svgz")]
+pub fn dxf_file_to_svgz(file_path: &str, output_path: &str, options: Option<SvgOptions>) -> std::io::Result<()> {
+    let drawing = load_drawing(file_path).map_err(|error| match error {
+        dxf::DxfError::IoError(error) => error,
+        error => std::io::Error::new(std::io::ErrorKind::InvalidData, error.to_string()),
+    })?;
+    let svg = render_svg(drawing.entities().collect(), Some(&drawing), Some(Path::new(file_path)), options).0;
+    write_svgz(&svg, std::fs::File::create(output_path)?)
+}
+
+/// Writes an SVG string gzip-compressed, as the contents of an `.svgz` file.
+#[cfg(feature = "svgz")]
+pub fn write_svgz<W: std::io::Write>(svg: &str, writer: W) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let mut encoder = flate2::write::GzEncoder::new(writer, flate2::Compression::default());
+    encoder.write_all(svg.as_bytes())?;
+    encoder.finish()?;
+    Ok(())
+}
+
+/// Escape special characters in XML text content
+fn escape_xml_text(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use dxf::entities::{Circle, Line};
+    use dxf::Point;
+
+    #[test]
+    fn test_basic_entities() {
+        // Test empty vector
+        let empty_svg = dxf_to_svg(vec![], Some(SvgOptions {
             use_bounds: false,
             padding: 1.0,
             background_color: "white".to_string(),
             stroke_width: 1.0,
             default_color: "black".to_string(),
+            ..Default::default()
         }));
         assert!(empty_svg.contains("viewBox=\"0 0 100 100\""));
         assert!(empty_svg.starts_with("<svg"));
@@ -588,6 +2040,159 @@ mod tests {
         assert!(result.contains("stroke=\"black\""));
     }
 
+    #[test]
+    fn test_aci_colors() {
+        let mut red_line = Entity::new(EntityType::Line(
+            Line::new(Point::new(0.0, 0.0, 0.0), Point::new(1.0, 1.0, 0.0))
+        ));
+        red_line.common.color = dxf::Color::from_index(1);
+        let result = dxf_to_svg(vec![&red_line], None);
+        assert!(result.contains("stroke=\"#FF0000\""));
+    }
+
+    #[test]
+    fn test_plot_style_table() {
+        let mut red_line = Entity::new(EntityType::Line(
+            Line::new(Point::new(0.0, 0.0, 0.0), Point::new(1.0, 1.0, 0.0))
+        ));
+        red_line.common.color = dxf::Color::from_index(1);
+        let result = dxf_to_svg(vec![&red_line], Some(SvgOptions {
+            plot_style_table: Some(PlotStyleTable::monochrome()),
+            ..Default::default()
+        }));
+        assert!(result.contains("stroke=\"black\""));
+
+        // plotted lineweights are in millimetres, so they need a scale like the entities' own
+        let thick = PlotStyle { lineweight: Some(0.5), ..Default::default() };
+        let table = || Some(PlotStyleTable::ColorDependent(HashMap::from([(1, thick.clone())])));
+        let result = dxf_to_svg(vec![&red_line], Some(SvgOptions { plot_style_table: table(), ..Default::default() }));
+        assert!(result.contains(r##"stroke="#FF0000" stroke-width="1""##));
+        let result = dxf_to_svg(vec![&red_line], Some(SvgOptions {
+            plot_style_table: table(),
+            lineweight_scale: Some(10.0),
+            ..Default::default()
+        }));
+        assert!(result.contains(r##"stroke="#FF0000" stroke-width="5""##));
+    }
+
+    #[test]
+    fn test_hyperlinks() {
+        let mut line = Entity::new(EntityType::Line(
+            Line::new(Point::new(0.0, 0.0, 0.0), Point::new(1.0, 1.0, 0.0))
+        ));
+        line.common.x_data.push(dxf::XData {
+            application_name: "PE_URL".to_string(),
+            items: vec![dxf::XDataItem::Str("https://example.com".to_string())],
+        });
+        assert!(!dxf_to_svg(vec![&line], None).contains("<a "));
+        let svg = dxf_to_svg(vec![&line], Some(SvgOptions { hyperlinks: true, ..Default::default() }));
+        assert!(svg.contains(r#"<a xlink:href="https://example.com"><line"#));
+    }
+
+    #[test]
+    fn test_solid_corner_order() {
+        let solid = dxf::entities::Solid {
+            first_corner: Point::new(0.0, 0.0, 0.0),
+            second_corner: Point::new(4.0, 0.0, 0.0),
+            third_corner: Point::new(0.0, 2.0, 0.0),
+            fourth_corner: Point::new(4.0, 2.0, 0.0),
+            ..Default::default()
+        };
+        let entity = Entity::new(EntityType::Solid(solid));
+        let svg = dxf_to_svg(vec![&entity], None);
+        assert!(svg.contains(r#"<polygon points="0.000,0.000 4.000,0.000 4.000,2.000 0.000,2.000" stroke="#));
+        assert!(svg.contains(r#"fill="black" />"#));
+
+        let triangle = dxf::entities::Trace {
+            first_corner: Point::new(0.0, 0.0, 0.0),
+            second_corner: Point::new(4.0, 0.0, 0.0),
+            third_corner: Point::new(2.0, 2.0, 0.0),
+            fourth_corner: Point::new(2.0, 2.0, 0.0),
+            ..Default::default()
+        };
+        let entity = Entity::new(EntityType::Trace(triangle));
+        let svg = dxf_to_svg(vec![&entity], None);
+        assert!(svg.contains(r#"<polygon points="0.000,0.000 4.000,0.000 2.000,2.000" stroke="#));
+    }
+
+    #[test]
+    fn test_face_edge_visibility() {
+        let face = dxf::entities::Face3D {
+            first_corner: Point::new(0.0, 0.0, 0.0),
+            second_corner: Point::new(4.0, 0.0, 0.0),
+            third_corner: Point::new(4.0, 2.0, 0.0),
+            fourth_corner: Point::new(4.0, 2.0, 0.0),
+            // the edge from the third corner back to the first is a hidden diagonal
+            edge_flags: 8,
+        };
+        let entity = Entity::new(EntityType::Face3D(face));
+        let svg = dxf_to_svg(vec![&entity], None);
+        assert_eq!(svg.matches("<line ").count(), 2);
+        assert!(svg.contains(r#"<line x1="0.000" y1="0.000" x2="4.000" y2="0.000""#));
+        assert!(svg.contains(r#"<line x1="4.000" y1="0.000" x2="4.000" y2="2.000""#));
+    }
+
+    #[test]
+    fn test_on_unsupported() {
+        let unsupported = std::sync::Arc::new(Mutex::new(vec![]));
+        let seen = unsupported.clone();
+        let options = SvgOptions {
+            on_unsupported: Some(Box::new(move |entity: &Entity| seen.lock().unwrap().push(entity.common.layer.clone()))),
+            ..Default::default()
+        };
+        // attributes are only drawn as part of their block reference
+        let mut attribute = Entity::new(EntityType::Attribute(Default::default()));
+        attribute.common.layer = "TAGS".to_string();
+        let line = Entity::new(EntityType::Line(Line::new(Point::new(0.0, 0.0, 0.0), Point::new(1.0, 1.0, 0.0))));
+        dxf_to_svg(vec![&attribute, &line], Some(options));
+        assert_eq!(*unsupported.lock().unwrap(), ["TAGS"]);
+    }
+
+    #[test]
+    fn test_conversion_report() {
+        let mut drawing = Drawing::new();
+        let mut attribute = Entity::new(EntityType::Attribute(Default::default()));
+        attribute.common.handle = dxf::Handle(0x10);
+        drawing.add_entity(attribute);
+        let mut insert = Insert::default();
+        insert.name = "missing".to_string();
+        let mut insert = Entity::new(EntityType::Insert(insert));
+        insert.common.handle = dxf::Handle(0x11);
+        drawing.add_entity(insert);
+        let (svg, report) = drawing_to_svg_with_report(&drawing, None);
+        assert!(svg.ends_with("</svg>"));
+        assert_eq!(report.output_bytes, svg.len());
+        assert_eq!(report.entity_count(), 2);
+        assert_eq!(report.rendered_count(), 1);
+        assert_eq!(report.skipped.get("ATTRIB"), Some(&1));
+        assert!(report.warnings.iter().any(|warning| matches!(
+            warning,
+            Warning::MissingBlock { block, .. } if block == "missing"
+        )));
+
+        assert!(try_drawing_to_svg(&drawing, None).is_ok());
+        let strict = SvgOptions { strict: true, ..Default::default() };
+        assert!(matches!(
+            try_drawing_to_svg(&drawing, Some(strict)),
+            Err(ConversionError::Incomplete { handles, .. }) if handles == [0x10, 0x11]
+        ));
+    }
+
+    #[test]
+    fn test_style_hook() {
+        let options = SvgOptions {
+            style_hook: Some(Box::new(|entity: &Entity, style: Style| match entity.common.layer.as_str() {
+                "WALLS" => Style { color: "red".to_string(), width: style.width * 2.0, ..style },
+                _ => style,
+            })),
+            ..Default::default()
+        };
+        let mut wall = Entity::new(EntityType::Line(Line::new(Point::new(0.0, 0.0, 0.0), Point::new(1.0, 1.0, 0.0))));
+        wall.common.layer = "WALLS".to_string();
+        let svg = dxf_to_svg(vec![&wall], Some(options));
+        assert!(svg.contains(r#"stroke="red" stroke-width="2""#));
+    }
+
     #[test]
     fn test_text_escaping() {
         assert_eq!(
@@ -596,6 +2201,682 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_insert_renders_block_symbol() {
+        let mut drawing = Drawing::new();
+        let mut block = Block {
+            name: "DOOR".to_string(),
+            base_point: Point::new(1.0, 2.0, 0.0),
+            ..Default::default()
+        };
+        let mut line = Entity::new(EntityType::Line(
+            Line::new(Point::new(1.0, 2.0, 0.0), Point::new(3.0, 2.0, 0.0))
+        ));
+        line.common.color = dxf::Color::by_block();
+        block.entities.push(line);
+        drawing.add_block(block);
+        let mut insert = Insert::default();
+        insert.name = "DOOR".to_string();
+        insert.location = Point::new(10.0, 10.0, 0.0);
+        insert.x_scale_factor = 2.0;
+        insert.y_scale_factor = 2.0;
+        insert.rotation = 90.0;
+        let mut insert = Entity::new(EntityType::Insert(insert));
+        insert.common.color = dxf::Color::from_index(1);
+        drawing.add_entity(insert);
+
+        let svg = drawing_to_svg(&drawing, None);
+        assert!(svg.contains(r#"<symbol id="block-DOOR" overflow="visible"><line"#));
+        // definitions go in a single <defs> at the top, before the drawing
+        assert_eq!(svg.matches("<defs>").count(), 1);
+        assert!(svg.find("<defs>") < svg.find("<g transform"));
+        assert!(svg.contains(r#"stroke="currentColor""#));
+        assert!(svg.contains(
+            r##"<use href="#block-DOOR" transform="translate(10.000 10.000) rotate(90.000) scale(2.000 2.000) translate(-1.000 -2.000)" color="#FF0000" />"##
+        ));
+    }
+
+    #[test]
+    fn test_insert_attributes() {
+        let mut drawing = Drawing::new();
+        let mut insert = Insert::default();
+        insert.name = "ROOM".to_string();
+        for (tag, value, flags) in [("NUMBER", "101", 0), ("AREA", "12m2", 1)] {
+            let attribute = dxf::entities::Attribute {
+                location: Point::new(5.0, 6.0, 0.0),
+                text_height: 2.0,
+                attribute_tag: tag.to_string(),
+                value: value.to_string(),
+                flags,
+                ..Default::default()
+            };
+            insert.add_attribute(&mut drawing, attribute);
+        }
+        let insert = Entity::new(EntityType::Insert(insert));
+
+        let svg = dxf_to_svg(vec![&insert], None);
+        assert!(svg.contains(r#"<text transform="translate(5.000 6.000) scale(1 -1)" font-size="2.000""#));
+        assert!(svg.contains(">101</text>"));
+        assert!(!svg.contains("12m2"));
+
+        let options = SvgOptions { invisible_attributes: true, ..Default::default() };
+        assert!(dxf_to_svg(vec![&insert], Some(options)).contains(">12m2</text>"));
+    }
+
+    #[test]
+    fn test_by_layer_color() {
+        let mut drawing = Drawing::new();
+        drawing.add_layer(Layer {
+            name: "WALLS".to_string(),
+            color: dxf::Color::from_index(3),
+            ..Default::default()
+        });
+        let mut line = Entity::new(EntityType::Line(
+            Line::new(Point::new(0.0, 0.0, 0.0), Point::new(1.0, 1.0, 0.0))
+        ));
+        line.common.layer = "walls".to_string();
+        line.common.color = dxf::Color::by_layer();
+        drawing.add_entity(line);
+
+        assert!(drawing_to_svg(&drawing, None).contains("stroke=\"#00FF00\""));
+    }
+
+    #[test]
+    fn test_closed_polylines() {
+        let mut square = dxf::entities::LwPolyline::default();
+        for (x, y, bulge) in [(0.0, 0.0, 0.0), (1.0, 0.0, 0.0), (1.0, 1.0, 0.0), (0.0, 1.0, 1.0)] {
+            square.vertices.push(dxf::LwPolylineVertex { x, y, bulge, ..Default::default() });
+        }
+        square.set_is_closed(true);
+        let square = Entity::new(EntityType::LwPolyline(square));
+        let svg = dxf_to_svg(vec![&square], Some(SvgOptions {
+            fill_closed_polylines: true,
+            ..Default::default()
+        }));
+        assert!(svg.contains(
+            r#"<path d="M 0.000,0.000 L 1.000,0.000 L 1.000,1.000 L 0.000,1.000 A 0.500,0.500 0 0 1 0.000,0.000 Z" stroke="black" stroke-width="1" fill="black" />"#
+        ));
+    }
+
+    #[test]
+    fn test_polyline_widths() {
+        let mut wide = dxf::entities::LwPolyline { constant_width: 0.5, ..Default::default() };
+        for (x, y) in [(0.0, 0.0), (2.0, 0.0)] {
+            wide.vertices.push(dxf::LwPolylineVertex { x, y, ..Default::default() });
+        }
+        let wide = Entity::new(EntityType::LwPolyline(wide));
+        let svg = dxf_to_svg(vec![&wide], None);
+        assert!(svg.contains(r#"<polyline points="0.000,0.000 2.000,0.000" stroke="black" stroke-width="0.5" fill="none" />"#));
+
+        let mut arrow = dxf::entities::LwPolyline::default();
+        arrow.vertices.push(dxf::LwPolylineVertex { x: 0.0, y: 0.0, starting_width: 1.0, ..Default::default() });
+        arrow.vertices.push(dxf::LwPolylineVertex { x: 2.0, y: 0.0, ..Default::default() });
+        let arrow = Entity::new(EntityType::LwPolyline(arrow));
+        let svg = dxf_to_svg(vec![&arrow], None);
+        assert!(svg.contains(r#"<polygon points="0.000,0.500 2.000,0.000 2.000,0.000 0.000,-0.500" stroke="black" stroke-width="0" fill="black" />"#));
+
+        // an arrow keeps its thin shaft next to the tapered head, faded like the rest of the entity
+        let mut arrow = dxf::entities::LwPolyline::default();
+        arrow.vertices.push(dxf::LwPolylineVertex { x: 0.0, y: 0.0, ..Default::default() });
+        arrow.vertices.push(dxf::LwPolylineVertex { x: 5.0, y: 0.0, ..Default::default() });
+        arrow.vertices.push(dxf::LwPolylineVertex { x: 6.0, y: 0.0, starting_width: 2.0, ..Default::default() });
+        arrow.vertices.push(dxf::LwPolylineVertex { x: 8.0, y: 0.0, ..Default::default() });
+        let mut arrow = Entity::new(EntityType::LwPolyline(arrow));
+        arrow.common.transparency = 0x0200_007F;
+        let svg = dxf_to_svg(vec![&arrow], None);
+        assert!(svg.contains(r#"<path d="M 0.000,0.000 L 5.000,0.000 L 6.000,0.000" stroke="black" stroke-width="1""#));
+        assert!(svg.contains(r#"<polygon points="6.000,1.000 8.000,0.000 8.000,0.000 6.000,-1.000" stroke="black" stroke-width="0" stroke-opacity"#));
+        assert!(svg.contains(r#"fill-opacity="0.498" fill="black" />"#));
+    }
+
+    #[test]
+    fn test_closed_two_vertex_polylines() {
+        // a full circle, as DONUT draws it: two half circles back to back
+        let mut circle = dxf::entities::LwPolyline::default();
+        circle.set_is_closed(true);
+        circle.vertices.push(dxf::LwPolylineVertex { x: 0.0, y: 0.0, bulge: 1.0, ..Default::default() });
+        circle.vertices.push(dxf::LwPolylineVertex { x: 2.0, y: 0.0, bulge: 1.0, ..Default::default() });
+        let circle = Entity::new(EntityType::LwPolyline(circle));
+        let svg = dxf_to_svg(vec![&circle], None);
+        assert!(svg.contains(r#"d="M 0.000,0.000 A 1.000,1.000 0 0 1 2.000,0.000 A 1.000,1.000 0 0 1 0.000,0.000 Z""#));
+
+        // a donut with a width is stroked that wide all the way around
+        let mut donut = dxf::entities::LwPolyline { constant_width: 0.5, ..Default::default() };
+        donut.set_is_closed(true);
+        donut.vertices.push(dxf::LwPolylineVertex { x: 0.0, y: 0.0, bulge: 1.0, ..Default::default() });
+        donut.vertices.push(dxf::LwPolylineVertex { x: 2.0, y: 0.0, bulge: 1.0, ..Default::default() });
+        let donut = Entity::new(EntityType::LwPolyline(donut));
+        let svg = dxf_to_svg(vec![&donut], None);
+        assert!(svg.contains(r#"A 1.000,1.000 0 0 1 0.000,0.000 Z" stroke="black" stroke-width="0.5""#));
+
+        // with varying widths each half is its own filled outline
+        let mut tapered = dxf::entities::LwPolyline::default();
+        tapered.set_is_closed(true);
+        tapered.vertices.push(dxf::LwPolylineVertex { x: 0.0, y: 0.0, bulge: 1.0, starting_width: 0.5, ..Default::default() });
+        tapered.vertices.push(dxf::LwPolylineVertex { x: 2.0, y: 0.0, bulge: 1.0, ending_width: 0.5, ..Default::default() });
+        let tapered = Entity::new(EntityType::LwPolyline(tapered));
+        let svg = dxf_to_svg(vec![&tapered], None);
+        assert_eq!(svg.matches(r#"stroke-width="0" fill="black""#).count(), 2);
+        let bounds = calculate_bounds(&[&tapered], None);
+        assert!(bounds.min_y < -0.9 && bounds.max_y > 0.9);
+    }
+
+    #[test]
+    fn test_elliptical_arc() {
+        let ellipse = Entity::new(EntityType::Ellipse(dxf::entities::Ellipse {
+            center: Point::new(0.0, 0.0, 0.0),
+            major_axis: dxf::Vector::new(2.0, 0.0, 0.0),
+            normal: dxf::Vector::z_axis(),
+            minor_axis_ratio: 0.5,
+            start_parameter: 0.0,
+            end_parameter: PI / 2.0,
+        }));
+        let svg = dxf_to_svg(vec![&ellipse], None);
+        assert!(svg.contains(r#"<path d="M 2.000,0.000 A 2.000,1.000 0.000 0 1 0.000,1.000""#));
+    }
+
+    #[test]
+    fn test_z_range() {
+        let ground = Entity::new(EntityType::Line(
+            Line::new(Point::new(0.0, 0.0, 0.0), Point::new(1.0, 1.0, 0.0))
+        ));
+        let first_floor = Entity::new(EntityType::Line(
+            Line::new(Point::new(0.0, 0.0, 3.0), Point::new(2.0, 1.0, 3.0))
+        ));
+        let svg = dxf_to_svg(vec![&ground, &first_floor], Some(SvgOptions {
+            z_range: Some((2.5, 5.0)),
+            ..Default::default()
+        }));
+        assert!(svg.contains(r#"x2="2.000""#));
+        assert!(!svg.contains(r#"x2="1.000""#));
+    }
+
+    #[test]
+    fn test_calculate_bounds() {
+        let line = Entity::new(EntityType::Line(
+            Line::new(Point::new(0.0, 0.0, 0.0), Point::new(4.0, 2.0, 0.0))
+        ));
+        let circle = Entity::new(EntityType::Circle(Circle::new(Point::new(10.0, 10.0, 0.0), 1.0)));
+        let bounds = calculate_bounds(&[&line, &circle], None);
+        assert_eq!(bounds, Bounds { min_x: 0.0, min_y: 0.0, max_x: 11.0, max_y: 11.0 });
+        assert_eq!(bounds.center(), (5.5, 5.5));
+        assert!(bounds.contains(11.0, 0.0));
+        assert!(!bounds.contains(11.5, 0.0));
+
+        let options = SvgOptions {
+            entity_filter: Some(Box::new(|entity: &Entity| matches!(entity.specific, EntityType::Line(_)))),
+            ..Default::default()
+        };
+        let lines = calculate_bounds(&[&line, &circle], Some(&options));
+        assert_eq!((lines.width(), lines.height()), (4.0, 2.0));
+        assert_eq!(lines.union(&Bounds::empty()), lines);
+        assert!(calculate_bounds(&[], None).is_empty());
+        assert_eq!(Bounds::empty().width(), 0.0);
+    }
+
+    #[test]
+    fn test_insert_bounds() {
+        let mut drawing = Drawing::new();
+        let mut block = Block { name: "DOOR".to_string(), ..Default::default() };
+        block.entities.push(Entity::new(EntityType::Line(
+            Line::new(Point::new(0.0, 0.0, 0.0), Point::new(2.0, 1.0, 0.0))
+        )));
+        drawing.add_block(block);
+        let mut insert = Insert::default();
+        insert.name = "DOOR".to_string();
+        insert.location = Point::new(10.0, 10.0, 0.0);
+        insert.x_scale_factor = 2.0;
+        insert.y_scale_factor = 2.0;
+        insert.rotation = 90.0;
+        drawing.add_entity(Entity::new(EntityType::Insert(insert)));
+
+        let bounds = drawing_bounds(&drawing, None);
+        let expected = [8.0, 10.0, 10.0, 14.0];
+        let actual = [bounds.min_x, bounds.min_y, bounds.max_x, bounds.max_y];
+        assert!(actual.iter().zip(expected).all(|(a, e)| (a - e).abs() < 1e-9), "{:?}", bounds);
+    }
+
+    #[test]
+    fn test_degenerate_bounds() {
+        let (svg, report) = dxf_to_svg_with_report(vec![], None);
+        assert!(!svg.contains("NaN") && !svg.contains("inf"));
+        assert_eq!(report.warnings, [Warning::EmptyDrawing]);
+        assert!(matches!(
+            try_dxf_to_svg(vec![], Some(SvgOptions { strict: true, ..Default::default() })),
+            Err(ConversionError::EmptyDrawing)
+        ));
+
+        let point = Entity::new(EntityType::ModelPoint(dxf::entities::ModelPoint {
+            location: Point::new(5.0, 5.0, 0.0),
+            ..Default::default()
+        }));
+        let line = Entity::new(EntityType::Line(
+            Line::new(Point::new(0.0, 0.0, 0.0), Point::new(4.0, 0.0, 0.0))
+        ));
+        for entity in [&point, &line] {
+            let (svg, report) = dxf_to_svg_with_report(vec![entity], None);
+            assert!(!svg.contains("NaN") && !svg.contains("inf"), "{}", svg);
+            let bounds = report.bounds.unwrap();
+            assert!(bounds.width() > 0.0 && bounds.height() > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_invalid_coordinates() {
+        let line = Entity::new(EntityType::Line(
+            Line::new(Point::new(0.0, 0.0, 0.0), Point::new(1.0, 1.0, 0.0))
+        ));
+        let corrupt = Entity::new(EntityType::Line(
+            Line::new(Point::new(f64::NAN, 0.0, 0.0), Point::new(1e300, 1.0, 0.0))
+        ));
+        let (svg, report) = dxf_to_svg_with_report(vec![&line, &corrupt], None);
+        assert!(!svg.contains("NaN") && !svg.contains("inf"));
+        assert_eq!(svg.matches("<line").count(), 1);
+        assert!(matches!(report.warnings[..], [Warning::InvalidCoordinates { entity_type: "LINE", .. }]));
+        assert_eq!(report.skipped_count(), 1);
+        assert_eq!(report.entity_count(), 2);
+    }
+
+    #[test]
+    fn test_skip_degenerate() {
+        let line = Entity::new(EntityType::Line(
+            Line::new(Point::new(0.0, 0.0, 0.0), Point::new(1.0, 1.0, 0.0))
+        ));
+        let mut dot = Entity::new(EntityType::Line(
+            Line::new(Point::new(1.0, 1.0, 0.0), Point::new(1.0, 1.0, 0.0))
+        ));
+        let mut circle = Entity::new(EntityType::Circle(Circle::new(Point::new(0.0, 0.0, 0.0), 0.0)));
+        dot.common.handle = dxf::Handle(0x20);
+        circle.common.handle = dxf::Handle(0x10);
+        let svg = dxf_to_svg(vec![&line, &dot, &circle], None);
+        assert_eq!(svg.matches("<line").count(), 2);
+
+        // strict catches them even when they're drawn
+        let options = SvgOptions { strict: true, ..Default::default() };
+        let Err(ConversionError::Incomplete { handles, .. }) = try_dxf_to_svg(vec![&line, &dot, &circle], Some(options)) else {
+            panic!("degenerate entities should fail a strict conversion");
+        };
+        assert_eq!(handles, [0x10, 0x20]);
+
+        let options = SvgOptions { skip_degenerate: true, ..Default::default() };
+        let (svg, report) = dxf_to_svg_with_report(vec![&line, &dot, &circle], Some(options));
+        assert_eq!(svg.matches("<line").count(), 1);
+        assert!(!svg.contains("<circle"));
+        assert_eq!(report.skipped_count(), 2);
+        assert!(matches!(report.warnings[..], [Warning::Degenerate { .. }, Warning::Degenerate { .. }]));
+    }
+
+    #[test]
+    fn test_entity_filter() {
+        let line = Entity::new(EntityType::Line(
+            Line::new(Point::new(0.0, 0.0, 0.0), Point::new(1.0, 1.0, 0.0))
+        ));
+        let circle = Entity::new(EntityType::Circle(Circle::new(Point::new(10.0, 10.0, 0.0), 1.0)));
+        let svg = dxf_to_svg(vec![&line, &circle], Some(SvgOptions {
+            entity_filter: Some(Box::new(|entity: &Entity| !matches!(entity.specific, EntityType::Circle(_)))),
+            ..Default::default()
+        }));
+        assert!(svg.contains("<line"));
+        assert!(!svg.contains("<circle"));
+        // the circle doesn't stretch the bounds either
+        assert!(!svg.contains("11.000"));
+    }
+
+    #[test]
+    fn test_bounds_source() {
+        let mut drawing = Drawing::new();
+        drawing.add_entity(Entity::new(EntityType::Line(
+            Line::new(Point::new(0.0, 0.0, 0.0), Point::new(1.0, 1.0, 0.0))
+        )));
+        drawing.header.minimum_drawing_limits = Point::new(-1.0, -1.0, 0.0);
+        drawing.header.maximum_drawing_limits = Point::new(3.0, 1.0, 0.0);
+        let svg = drawing_to_svg(&drawing, Some(SvgOptions {
+            padding: 0.0,
+            bounds_source: BoundsSource::Limits,
+            ..Default::default()
+        }));
+        assert!(svg.contains(r#"<rect x="-1" y="-1" width="4" height="2""#));
+
+        // without usable extents the bounds are computed
+        drawing.header.minimum_drawing_extents = Point::new(1e20, 1e20, 1e20);
+        drawing.header.maximum_drawing_extents = Point::new(-1e20, -1e20, -1e20);
+        let svg = drawing_to_svg(&drawing, Some(SvgOptions {
+            padding: 0.0,
+            bounds_source: BoundsSource::Extents,
+            ..Default::default()
+        }));
+        assert!(svg.contains(r#"<rect x="0" y="-1" width="1" height="1""#));
+    }
+
+    #[test]
+    fn test_physical_size() {
+        let mut drawing = Drawing::new();
+        drawing.add_entity(Entity::new(EntityType::Line(
+            Line::new(Point::new(0.0, 0.0, 0.0), Point::new(0.21, 0.297, 0.0))
+        )));
+        drawing.header.default_drawing_units = dxf::enums::Units::Meters;
+        let svg = drawing_to_svg(&drawing, Some(SvgOptions {
+            padding: 0.0,
+            physical_size: true,
+            ..Default::default()
+        }));
+        assert!(svg.contains(r#"width="210.000mm" height="297.000mm""#));
+
+        // at 1:100 a metre of drawing is 10 millimetres of paper
+        let svg = drawing_to_svg(&drawing, Some(SvgOptions {
+            padding: 0.0,
+            scale: Some(0.1),
+            ..Default::default()
+        }));
+        assert!(svg.contains(r#"width="2.100mm" height="2.970mm""#));
+    }
+
+    #[test]
+    fn test_page() {
+        let line = Entity::new(EntityType::Line(
+            Line::new(Point::new(0.0, 0.0, 0.0), Point::new(100.0, 50.0, 0.0))
+        ));
+        let svg = dxf_to_svg(vec![&line], Some(SvgOptions {
+            padding: 0.0,
+            page: Some(PageSize::A4),
+            ..Default::default()
+        }));
+        assert!(svg.contains(r#"viewBox="0 0 297 210" width="297mm" height="210mm">"#));
+        assert!(svg.contains(r#"<g transform="translate(10, 35.75) scale(2.77, -2.77) translate(-0, -50)">"#));
+    }
+
+    #[test]
+    fn test_svg_size() {
+        let line = Entity::new(EntityType::Line(
+            Line::new(Point::new(0.0, 0.0, 0.0), Point::new(2.0, 1.0, 0.0))
+        ));
+        let svg = dxf_to_svg(vec![&line], Some(SvgOptions {
+            size: SvgSize::Width(800.0),
+            ..Default::default()
+        }));
+        assert!(svg.contains(r#"width="800" height="400""#));
+
+        let svg = dxf_to_svg(vec![&line], Some(SvgOptions {
+            size: SvgSize::Omit,
+            ..Default::default()
+        }));
+        assert!(!svg.contains("width=\"100%\""));
+    }
+
+    #[test]
+    fn test_precision() {
+        let line = Entity::new(EntityType::Line(
+            Line::new(Point::new(0.0, 0.0, 0.0), Point::new(2.0, 1.0, 0.0))
+        ));
+        let svg = dxf_to_svg(vec![&line], Some(SvgOptions {
+            precision: Some(1),
+            ..Default::default()
+        }));
+        assert!(svg.contains(r#"<line x1="0.0" y1="0.0" x2="2.0" y2="1.0""#));
+
+        // a drawing in geographic degrees keeps detail below a metre
+        let line = Entity::new(EntityType::Line(
+            Line::new(Point::new(34.78, 32.08, 0.0), Point::new(34.781234, 32.080001, 0.0))
+        ));
+        let svg = dxf_to_svg(vec![&line], None);
+        assert!(svg.contains(r#"x2="34.781234" y2="32.080001""#));
+    }
+
+    #[cfg(feature = "svgz")]
+    #[test]
+    fn test_write_svgz() {
+        use std::io::Read;
+
+        let svg = "<svg></svg>".repeat(100);
+        let mut compressed = vec![];
+        write_svgz(&svg, &mut compressed).unwrap();
+        assert!(compressed.len() < svg.len());
+
+        let mut decompressed = String::new();
+        flate2::read::GzDecoder::new(compressed.as_slice()).read_to_string(&mut decompressed).unwrap();
+        assert_eq!(decompressed, svg);
+
+        let missing = dxf_file_to_svgz("missing.dxf", "missing.svgz", None).unwrap_err();
+        assert_eq!(missing.kind(), std::io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_css_classes() {
+        let mut walls = Entity::new(EntityType::Line(
+            Line::new(Point::new(0.0, 0.0, 0.0), Point::new(1.0, 1.0, 0.0))
+        ));
+        walls.common.layer = "WALLS".to_string();
+        let svg = dxf_to_svg(vec![&walls, &walls], Some(SvgOptions {
+            css_classes: true,
+            ..Default::default()
+        }));
+        assert_eq!(svg.matches(r#"class="layer-WALLS entity-line s0""#).count(), 2);
+        assert!(!svg.contains("stroke=\""));
+        assert!(svg.contains("<style>.s0{stroke:black;stroke-width:1}</style>"));
+    }
+
+    #[test]
+    fn test_root_attributes() {
+        let line = Entity::new(EntityType::Line(
+            Line::new(Point::new(0.0, 0.0, 0.0), Point::new(1.0, 1.0, 0.0))
+        ));
+        let svg = dxf_to_svg(vec![&line], Some(SvgOptions {
+            root_attributes: vec![
+                ("id".to_string(), "plan".to_string()),
+                ("data-drawing-id".to_string(), "A&B".to_string()),
+            ],
+            ..Default::default()
+        }));
+        let root = &svg[..svg.find('>').unwrap()];
+        assert!(root.contains(r#" id="plan" data-drawing-id="A&amp;B""#));
+    }
+
+    #[test]
+    fn test_document_info() {
+        let line = Entity::new(EntityType::Line(
+            Line::new(Point::new(0.0, 0.0, 0.0), Point::new(1.0, 1.0, 0.0))
+        ));
+        let svg = dxf_to_svg(vec![&line], Some(SvgOptions {
+            title: Some("Ground floor".to_string()),
+            description: Some("Walls & doors".to_string()),
+            metadata: true,
+            ..Default::default()
+        }));
+        let root_end = svg.find('>').unwrap() + 1;
+        assert!(svg[root_end..].starts_with("<title>Ground floor</title><desc>Walls &amp; doors</desc><metadata><converted>"));
+
+        let mut drawing = Drawing::new();
+        drawing.header.version = dxf::enums::AcadVersion::R2018;
+        let svg = drawing_to_svg(&drawing, Some(SvgOptions { metadata: true, ..Default::default() }));
+        assert!(svg.contains("<dxf-version>R2018</dxf-version>"));
+        assert!(!svg.contains("<title>"));
+    }
+
+    #[test]
+    fn test_entity_ids() {
+        let mut line = Entity::new(EntityType::Line(
+            Line::new(Point::new(0.0, 0.0, 0.0), Point::new(1.0, 1.0, 0.0))
+        ));
+        line.common.handle = dxf::Handle(0x2A);
+        let svg = dxf_to_svg(vec![&line], None);
+        assert!(!svg.contains("id=\"e2A\""));
+        let svg = dxf_to_svg(vec![&line], Some(SvgOptions { entity_ids: true, ..Default::default() }));
+        assert!(svg.contains(r#"<line id="e2A" x1="#));
+    }
+
+    #[test]
+    fn test_data_attributes() {
+        let mut line = Entity::new(EntityType::Line(
+            Line::new(Point::new(0.0, 0.0, 0.0), Point::new(1.0, 1.0, 0.0))
+        ));
+        line.common.layer = "A-WALL".to_string();
+        line.common.handle = dxf::Handle(0x10);
+        let svg = dxf_to_svg(vec![&line], Some(SvgOptions {
+            entity_ids: true,
+            data_attributes: true,
+            ..Default::default()
+        }));
+        assert!(svg.contains(r#"<line id="e10" data-layer="A-WALL" data-entity="LINE" x1="#));
+
+        let svg = dxf_to_svg(vec![&line], Some(SvgOptions {
+            tooltip: Some(Tooltip::Layer),
+            ..Default::default()
+        }));
+        assert!(svg.contains("><title>A-WALL</title></line>"));
+    }
+
+    #[test]
+    fn test_group_by_block() {
+        let mut drawing = Drawing::new();
+        let mut block = Block { name: "DOOR".to_string(), ..Default::default() };
+        block.entities.push(Entity::new(EntityType::Line(
+            Line::new(Point::new(0.0, 0.0, 0.0), Point::new(1.0, 0.0, 0.0))
+        )));
+        drawing.add_block(block);
+        let mut insert = Insert::default();
+        insert.name = "DOOR".to_string();
+        insert.location = Point::new(5.0, 5.0, 0.0);
+        drawing.add_entity(Entity::new(EntityType::Insert(insert)));
+        drawing.add_entity(Entity::new(EntityType::Line(
+            Line::new(Point::new(0.0, 0.0, 0.0), Point::new(10.0, 10.0, 0.0))
+        )));
+
+        let svg = drawing_to_svg(&drawing, Some(SvgOptions { group_by: GroupBy::Block, ..Default::default() }));
+        assert!(svg.contains(r##"<g data-block="DOOR"><use href="#block-DOOR""##));
+        assert_eq!(svg.matches("data-block=").count(), 1);
+    }
+
+    #[test]
+    fn test_dimension_blocks() {
+        let mut drawing = Drawing::new();
+        let mut block = Block { name: "*D1".to_string(), ..Default::default() };
+        block.entities.push(Entity::new(EntityType::Line(
+            Line::new(Point::new(0.0, 0.0, 0.0), Point::new(10.0, 0.0, 0.0))
+        )));
+        drawing.add_block(block);
+        let mut dimension = dxf::entities::RotatedDimension::default();
+        dimension.dimension_base.block_name = "*D1".to_string();
+        dimension.definition_point_3 = Point::new(10.0, 0.0, 0.0);
+        drawing.add_entity(Entity::new(EntityType::RotatedDimension(dimension)));
+
+        let svg = drawing_to_svg(&drawing, None);
+        assert!(!svg.contains("<symbol"));
+        assert!(svg.contains(">10.00</text>"));
+
+        let svg = drawing_to_svg(&drawing, Some(SvgOptions { dimension_blocks: true, ..Default::default() }));
+        assert!(svg.contains(r#"<symbol id="block-_D1" overflow="visible"><line"#));
+        assert!(svg.contains(r##"<use href="#block-_D1" color="black" />"##));
+        assert!(!svg.contains("<text"));
+    }
+
+    #[test]
+    fn test_group_by_layer() {
+        let mut walls = Entity::new(EntityType::Line(
+            Line::new(Point::new(0.0, 0.0, 0.0), Point::new(1.0, 1.0, 0.0))
+        ));
+        walls.common.layer = "A-WALL FULL".to_string();
+        let doors = Entity::new(EntityType::Line(
+            Line::new(Point::new(0.0, 0.0, 0.0), Point::new(2.0, 1.0, 0.0))
+        ));
+        let svg = dxf_to_svg(vec![&walls, &doors, &walls], Some(SvgOptions {
+            group_by: GroupBy::Layer,
+            ..Default::default()
+        }));
+        assert_eq!(svg.matches("<g id=\"layer-A-WALL_FULL\">").count(), 1);
+        assert_eq!(svg.matches("<g id=\"layer-0\">").count(), 1);
+    }
+
+    #[test]
+    fn test_svg_fragments() {
+        let mut walls = Entity::new(EntityType::Line(
+            Line::new(Point::new(0.0, 0.0, 0.0), Point::new(1.0, 1.0, 0.0))
+        ));
+        walls.common.layer = "WALLS".to_string();
+        let doors = Entity::new(EntityType::Circle(Circle::new(Point::new(2.0, 1.0, 0.0), 0.5)));
+        let options = || Some(SvgOptions {
+            group_by: GroupBy::Layer,
+            css_classes: true,
+            ..Default::default()
+        });
+        let fragments: Vec<String> = svg_fragments(vec![&walls, &doors, &walls], options()).collect();
+        // the header, one piece per entity and the footer
+        assert_eq!(fragments.len(), 5);
+        assert!(fragments[0].starts_with("<svg"));
+        assert!(fragments[1].starts_with(r#"<g id="layer-WALLS"><line"#));
+        assert!(fragments[2].ends_with("</g>"));
+        assert!(fragments[4].ends_with("</svg>"));
+        assert_eq!(fragments.concat(), dxf_to_svg(vec![&walls, &doors, &walls], options()));
+
+        // just the header and the footer
+        let fragments: Vec<String> = svg_fragments(vec![], None).collect();
+        assert_eq!(fragments.len(), 2);
+        assert_eq!(fragments.concat(), dxf_to_svg(vec![], None));
+    }
+
+    #[test]
+    fn test_cancel() {
+        let line = Entity::new(EntityType::Line(
+            Line::new(Point::new(0.0, 0.0, 0.0), Point::new(1.0, 1.0, 0.0))
+        ));
+        let entities = vec![&line; 1000];
+        let cancel = Arc::new(AtomicBool::new(false));
+        let flag = cancel.clone();
+        let (svg, report) = dxf_to_svg_with_report(entities.clone(), Some(SvgOptions {
+            // cancelled by the first progress update, after 100 entities, though with `rayon` a few more may
+            // be under way by then
+            on_progress: Some(Box::new(move |_, _| flag.store(true, std::sync::atomic::Ordering::Relaxed))),
+            cancel: Some(cancel.clone()),
+            ..Default::default()
+        }));
+        assert!(report.cancelled);
+        assert!((100..1000).contains(&report.rendered_count()));
+        assert_eq!(svg.matches("<line").count(), report.rendered_count());
+        assert!(svg.ends_with("</svg>"));
+
+        let options = || Some(SvgOptions { cancel: Some(cancel.clone()), ..Default::default() });
+        assert_eq!(try_dxf_to_svg(entities.clone(), options()), Err(ConversionError::Cancelled));
+        // the stream still ends the document
+        let fragments: Vec<String> = svg_fragments(entities.clone(), options()).collect();
+        assert_eq!(fragments.len(), 2);
+        assert!(fragments[1].ends_with("</svg>"));
+
+        // cancelled in the middle of a layer, whose group is closed first
+        cancel.store(false, std::sync::atomic::Ordering::Relaxed);
+        let mut fragments = svg_fragments(entities, Some(SvgOptions {
+            group_by: GroupBy::Layer,
+            cancel: Some(cancel.clone()),
+            ..Default::default()
+        }));
+        assert!(fragments.next().unwrap().starts_with("<svg"));
+        assert!(fragments.next().unwrap().starts_with(r#"<g id="layer-0"><line"#));
+        cancel.store(true, std::sync::atomic::Ordering::Relaxed);
+        let end = fragments.next().unwrap();
+        assert!(end.starts_with("</g>") && end.ends_with("</svg>"));
+        assert_eq!(fragments.next(), None);
+    }
+
+    #[test]
+    fn test_nested_and_recursive_blocks() {
+        let mut drawing = Drawing::new();
+        for (name, inserted) in [("OUTER", "INNER"), ("INNER", "OUTER")] {
+            let mut insert = Insert::default();
+            insert.name = inserted.to_string();
+            let mut block = Block {
+                name: name.to_string(),
+                ..Default::default()
+            };
+            block.entities.push(Entity::new(EntityType::Insert(insert)));
+            drawing.add_block(block);
+        }
+        let mut insert = Insert::default();
+        insert.name = "OUTER".to_string();
+        drawing.add_entity(Entity::new(EntityType::Insert(insert)));
+
+        let svg = drawing_to_svg(&drawing, None);
+        assert!(svg.contains(r#"<symbol id="block-INNER" overflow="visible"></symbol>"#));
+        assert!(svg.contains(r##"<symbol id="block-OUTER" overflow="visible"><use href="#block-INNER""##));
+    }
+
     #[test]
     fn test_file_to_svg() {
         let svg = dxf_file_to_svg("tests/test.dxf", Some(SvgOptions::default()));
@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+/**
+Linetype definitions loaded from an AutoCAD `.lin` file.
+Set it as `SvgOptions::line_type_library` to resolve linetypes that aren't defined in the drawing's
+own LTYPE table, or when converting loose entities without a drawing.
+Shape and text elements of complex linetypes are skipped, only their dashes and gaps are kept.
+*/
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LineTypeLibrary {
+    /// dash patterns by upper case linetype name
+    patterns: HashMap<String, Vec<f64>>,
+}
+
+impl LineTypeLibrary {
+    /// Parses the contents of a `.lin` file.
+    pub fn parse(contents: &str) -> Self {
+        let mut patterns = HashMap::new();
+        let mut current_name: Option<String> = None;
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with(';') {
+                continue;
+            }
+            if let Some(header) = line.strip_prefix('*') {
+                let name = header.split(',').next().unwrap_or_default().trim();
+                current_name = Some(name.to_uppercase());
+            } else if let Some(name) = current_name.take() {
+                patterns.insert(name, parse_pattern(line));
+            }
+        }
+        LineTypeLibrary { patterns }
+    }
+
+    /// Reads and parses a `.lin` file.
+    pub fn load(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        Ok(Self::parse(&std::fs::read_to_string(path)?))
+    }
+
+    /// The dash pattern of a linetype, using the DXF convention of negative lengths for gaps.
+    pub fn pattern(&self, name: &str) -> Option<&[f64]> {
+        self.patterns.get(&name.to_uppercase()).map(Vec::as_slice)
+    }
+}
+
+/// Parses a pattern line like `A,.5,-.25,["GAS",STANDARD,S=.1],-.25`.
+fn parse_pattern(line: &str) -> Vec<f64> {
+    // drop the bracketed shape/text elements of complex linetypes
+    let mut simple = String::new();
+    let mut depth = 0;
+    for c in line.chars() {
+        match c {
+            '[' => depth += 1,
+            ']' => depth -= 1,
+            _ if depth == 0 => simple.push(c),
+            _ => {}
+        }
+    }
+    simple
+        .split(',')
+        .skip(1) // the alignment field, always "A"
+        .filter_map(|value| value.trim().parse().ok())
+        .collect()
+}
+
+/// Converts a DXF dash pattern into an SVG `stroke-dasharray` value.
+/// DXF patterns use positive lengths for dashes, negative lengths for gaps and zero for dots;
+/// SVG only needs the absolute lengths. Returns None for continuous patterns.
+pub(crate) fn dash_array(pattern: &[f64], scale: f64, precision: usize) -> Option<String> {
+    if pattern.iter().all(|length| *length >= 0.0) {
+        // without a gap the line is drawn solid
+        return None;
+    }
+    Some(
+        pattern
+            .iter()
+            .map(|length| format!("{:.p$}", length.abs() * scale, p = precision))
+            .collect::<Vec<_>>()
+            .join(" "),
+    )
+}
+
+/// Dots are zero length dashes, which are only visible with round line caps.
+pub(crate) fn has_dots(pattern: &[f64]) -> bool {
+    pattern.contains(&0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dash_array() {
+        assert_eq!(dash_array(&[0.5, -0.25], 2.0, 3).as_deref(), Some("1.000 0.500"));
+        assert_eq!(dash_array(&[0.5, -0.25, 0.0, -0.25], 1.0, 3).as_deref(), Some("0.500 0.250 0.000 0.250"));
+        assert_eq!(dash_array(&[], 1.0, 3), None);
+    }
+
+    #[test]
+    fn test_parse_lin_file() {
+        let library = LineTypeLibrary::parse(
+            ";; comment\n\
+            *DASHED,Dashed __ __ __ __\n\
+            A,.5,-.25\n\
+            *GAS_LINE,Gas line ----GAS----GAS----\n\
+            A,.5,-.2,[\"GAS\",STANDARD,S=.1,U=0.0,X=-0.1,Y=-.05],-.25\n",
+        );
+        assert_eq!(library.pattern("dashed"), Some(&[0.5, -0.25][..]));
+        assert_eq!(library.pattern("GAS_LINE"), Some(&[0.5, -0.2, -0.25][..]));
+        assert_eq!(library.pattern("HIDDEN"), None);
+    }
+}
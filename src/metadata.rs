@@ -0,0 +1,58 @@
+use std::fmt::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use dxf::Drawing;
+
+use crate::escape_xml_text;
+
+/// Builds the `<metadata>` element describing where the SVG came from.
+pub(crate) fn metadata_element(source: Option<&str>, drawing: Option<&Drawing>, converted: SystemTime) -> String {
+    let mut metadata = String::from("<metadata>");
+    if let Some(source) = source {
+        write!(metadata, "<source>{}</source>", escape_xml_text(source)).unwrap();
+    }
+    if let Some(drawing) = drawing {
+        write!(metadata, "<dxf-version>{:?}</dxf-version>", drawing.header.version).unwrap();
+    }
+    let seconds = converted.duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_secs());
+    write!(metadata, "<converted>{}</converted>", timestamp(seconds)).unwrap();
+    metadata.push_str("</metadata>");
+    metadata
+}
+
+/// Formats seconds since the Unix epoch as an ISO 8601 UTC timestamp.
+fn timestamp(seconds: u64) -> String {
+    let days = (seconds / 86_400) as i64;
+    let time = seconds % 86_400;
+    // days to a civil date, from Howard Hinnant's `civil_from_days`
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        time / 3_600,
+        time % 3_600 / 60,
+        time % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timestamp() {
+        assert_eq!(timestamp(0), "1970-01-01T00:00:00Z");
+        assert_eq!(timestamp(951_782_400), "2000-02-29T00:00:00Z");
+        assert_eq!(timestamp(1_791_812_096), "2026-10-12T13:34:56Z");
+    }
+}
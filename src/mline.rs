@@ -0,0 +1,216 @@
+use std::fmt::Write;
+
+use dxf::entities::MLine;
+use dxf::objects::{MLineStyle, ObjectType};
+use dxf::Drawing;
+
+use crate::emit::Points;
+
+/// MLINESTYLE flag: lines join the outer elements at each vertex
+const DISPLAY_MITERS: i32 = 2;
+/// MLINESTYLE flag: a line closes the start
+const START_SQUARE_CAP: i32 = 16;
+/// MLINESTYLE flag: an arc joins the outer elements at the start
+const START_ROUND_CAP: i32 = 64;
+/// MLINESTYLE flag: a line closes the end
+const END_SQUARE_CAP: i32 = 256;
+/// MLINESTYLE flag: an arc joins the outer elements at the end
+const END_ROUND_CAP: i32 = 1024;
+
+/// The element offsets of AutoCAD's STANDARD style, used when the drawing doesn't define the line's style
+const STANDARD_OFFSETS: [f64; 2] = [0.5, -0.5];
+
+/**
+Renders an MLINE as a polyline for each element of its MLINESTYLE, offset from the vertices by the
+element's offset times the line's scale and mitered at each vertex. The justification decides whether the
+vertices lie on the top element, on the style's zero line or on the bottom element. The start and end
+are capped with lines or arcs as the style asks, unless the line is closed or its caps are suppressed.
+*/
+pub(crate) fn render_mline(mline: &MLine, drawing: Option<&Drawing>, stroke_attr: &str, precision: usize, svg: &mut String) {
+    let vertices: Vec<(f64, f64)> = mline.vertices.iter().map(|vertex| (vertex.x, vertex.y)).collect();
+    if vertices.len() < 2 {
+        return;
+    }
+    let style = drawing.and_then(|drawing| find_style(drawing, &mline.style_name));
+    let offsets = style
+        .map(|style| style.element_offsets.clone())
+        .filter(|offsets| !offsets.is_empty())
+        .unwrap_or_else(|| STANDARD_OFFSETS.to_vec());
+    let flags = style.map_or(0, |style| style.flags);
+    let top = offsets.iter().copied().fold(f64::MIN, f64::max);
+    let bottom = offsets.iter().copied().fold(f64::MAX, f64::min);
+    let shift = match mline.justification {
+        0 => -top,
+        2 => -bottom,
+        _ => 0.0,
+    };
+    let closed = mline.is_closed() && vertices.len() > 2;
+    let miters = miter_offsets(&vertices, closed);
+    let element_points = |offset: f64| -> Vec<(f64, f64)> {
+        let distance = (offset + shift) * mline.scale_factor;
+        vertices
+            .iter()
+            .zip(&miters)
+            .map(|(vertex, miter)| (vertex.0 + miter.0 * distance, vertex.1 + miter.1 * distance))
+            .collect()
+    };
+    let element = if closed { "polygon" } else { "polyline" };
+    for offset in &offsets {
+        write!(
+            svg,
+            r#"<{} points="{}" {} fill="none" />"#,
+            element,
+            Points(&element_points(*offset), precision),
+            stroke_attr
+        ).unwrap();
+    }
+
+    let (outer, inner) = (element_points(top), element_points(bottom));
+    let radius = (top - bottom).abs() * mline.scale_factor.abs() / 2.0;
+    let mut joins = vec![];
+    if flags & DISPLAY_MITERS != 0 {
+        let interior = if closed { 0..vertices.len() } else { 1..vertices.len() - 1 };
+        joins.extend(interior.map(|i| join(outer[i], inner[i], None, precision)));
+    }
+    if !closed {
+        let last = vertices.len() - 1;
+        // round caps bulge away from the line: backwards at the start, forwards at the end
+        if !mline.suppress_start_caps() {
+            if flags & START_SQUARE_CAP != 0 {
+                joins.push(join(outer[0], inner[0], None, precision));
+            }
+            if flags & START_ROUND_CAP != 0 {
+                joins.push(join(outer[0], inner[0], Some((radius, 1)), precision));
+            }
+        }
+        if !mline.suppress_end_caps() {
+            if flags & END_SQUARE_CAP != 0 {
+                joins.push(join(outer[last], inner[last], None, precision));
+            }
+            if flags & END_ROUND_CAP != 0 {
+                joins.push(join(outer[last], inner[last], Some((radius, 0)), precision));
+            }
+        }
+    }
+    if !joins.is_empty() {
+        write!(svg, r#"<path d="{}" {} fill="none" />"#, joins.join(" "), stroke_attr).unwrap();
+    }
+}
+
+fn find_style<'a>(drawing: &'a Drawing, name: &str) -> Option<&'a MLineStyle> {
+    let name = if name.is_empty() { "STANDARD" } else { name };
+    drawing.objects().find_map(|object| match &object.specific {
+        ObjectType::MLineStyle(style) if style.style_name.eq_ignore_ascii_case(name) => Some(style),
+        _ => None,
+    })
+}
+
+/**
+How far, and which way, each vertex moves for every unit an element is offset to the left. Along a
+straight run that's the segment's left normal, at a corner it's the bisector of both normals,
+lengthened so the elements stay parallel to the segments.
+*/
+fn miter_offsets(vertices: &[(f64, f64)], closed: bool) -> Vec<(f64, f64)> {
+    let count = vertices.len();
+    let normal = |from: (f64, f64), to: (f64, f64)| {
+        let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+        let length = dx.hypot(dy);
+        if length <= f64::EPSILON {
+            None
+        } else {
+            Some((-dy / length, dx / length))
+        }
+    };
+    (0..count)
+        .map(|i| {
+            let incoming = match i {
+                0 if closed => normal(vertices[count - 1], vertices[0]),
+                0 => None,
+                _ => normal(vertices[i - 1], vertices[i]),
+            };
+            let outgoing = match i {
+                _ if i + 1 < count => normal(vertices[i], vertices[i + 1]),
+                _ if closed => normal(vertices[i], vertices[0]),
+                _ => None,
+            };
+            match (incoming, outgoing) {
+                (Some(a), Some(b)) => {
+                    let (x, y) = (a.0 + b.0, a.1 + b.1);
+                    let dot = (x * a.0 + y * a.1) / x.hypot(y);
+                    if dot <= 1e-6 {
+                        // the line turns back on itself
+                        b
+                    } else {
+                        (x / x.hypot(y) / dot, y / x.hypot(y) / dot)
+                    }
+                }
+                (Some(normal), None) | (None, Some(normal)) => normal,
+                (None, None) => (0.0, 0.0),
+            }
+        })
+        .collect()
+}
+
+/// Path data joining two points with a line, or with a half circle of the given radius and sweep flag.
+fn join(from: (f64, f64), to: (f64, f64), arc: Option<(f64, u8)>, precision: usize) -> String {
+    match arc {
+        Some((radius, sweep)) => format!(
+            "M {:.p$},{:.p$} A {r:.p$},{r:.p$} 0 0 {} {:.p$},{:.p$}",
+            from.0,
+            from.1,
+            sweep,
+            to.0,
+            to.1,
+            r = radius,
+            p = precision
+        ),
+        None => format!("M {:.p$},{:.p$} L {:.p$},{:.p$}", from.0, from.1, to.0, to.1, p = precision),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dxf::objects::Object;
+    use dxf::Point;
+
+    #[test]
+    fn test_miter_offsets() {
+        let corner = miter_offsets(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0)], false);
+        assert_eq!(corner[0], (0.0, 1.0));
+        assert!((corner[1].0 + 1.0).abs() < 1e-9 && (corner[1].1 - 1.0).abs() < 1e-9);
+        assert_eq!(corner[2], (-1.0, 0.0));
+    }
+
+    #[test]
+    fn test_render_mline() {
+        let mut drawing = Drawing::new();
+        drawing.add_object(Object {
+            common: Default::default(),
+            specific: ObjectType::MLineStyle(MLineStyle {
+                style_name: "WALL".to_string(),
+                flags: START_SQUARE_CAP | END_ROUND_CAP,
+                element_offsets: vec![1.0, 0.0],
+                ..Default::default()
+            }),
+        });
+        let mline = MLine {
+            style_name: "WALL".to_string(),
+            scale_factor: 2.0,
+            justification: 0,
+            vertices: vec![Point::new(0.0, 0.0, 0.0), Point::new(10.0, 0.0, 0.0)],
+            ..Default::default()
+        };
+        let mut svg = String::new();
+        render_mline(&mline, Some(&drawing), r#"stroke="red""#, 1, &mut svg);
+        // top justified, so the vertices lie on the outer element
+        assert_eq!(
+            svg,
+            concat!(
+                r#"<polyline points="0.0,0.0 10.0,0.0" stroke="red" fill="none" />"#,
+                r#"<polyline points="0.0,-2.0 10.0,-2.0" stroke="red" fill="none" />"#,
+                r#"<path d="M 0.0,0.0 L 0.0,-2.0 M 10.0,0.0 A 1.0,1.0 0 0 0 10.0,-2.0" stroke="red" fill="none" />"#
+            )
+        );
+    }
+}
@@ -0,0 +1,295 @@
+use std::fmt::Write;
+
+use dxf::entities::MText;
+use dxf::enums::AttachmentPoint;
+
+use crate::text::{control_code_symbol, read_escape, TextStyle, CHARACTER_WIDTH, DEFAULT_TEXT_HEIGHT};
+use crate::{color, escape_xml_text};
+
+/// The distance between baselines, in text heights, at a line spacing factor of 1
+const LINE_SPACING: f64 = 5.0 / 3.0;
+
+/// The formatting an MTEXT code sequence can switch on for a run of text.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Format {
+    font: Option<String>,
+    height: Option<f64>,
+    color: Option<String>,
+    underline: bool,
+    overline: bool,
+    strike_through: bool,
+}
+
+/// Text written with the same formatting.
+#[derive(Debug, Clone, PartialEq)]
+struct Run {
+    text: String,
+    format: Format,
+}
+
+/// Renders an MTEXT as a `<text>` with a `<tspan>` per line, wrapped to its reference width.
+pub(crate) fn render_mtext(mtext: &MText, style: &TextStyle, color: &str, precision: usize, svg: &mut String) {
+    let height = if mtext.initial_text_height > 0.0 { mtext.initial_text_height } else { DEFAULT_TEXT_HEIGHT };
+    let content: String = mtext.extended_text.concat() + &mtext.text;
+    let lines = wrap(parse(&content, height), mtext.reference_rectangle_width, height);
+    if lines.iter().all(|line| line.iter().all(|run| run.text.trim().is_empty())) {
+        return;
+    }
+
+    let spacing = if mtext.line_spacing_factor > 0.0 { mtext.line_spacing_factor } else { 1.0 };
+    let pitch = height * LINE_SPACING * spacing;
+    // from the first baseline to the last
+    let lines_height = pitch * (lines.len() - 1) as f64;
+    let (anchor, first_baseline) = match mtext.attachment_point {
+        AttachmentPoint::TopLeft => ("start", height),
+        AttachmentPoint::TopCenter => ("middle", height),
+        AttachmentPoint::TopRight => ("end", height),
+        AttachmentPoint::MiddleLeft => ("start", (height - lines_height) / 2.0),
+        AttachmentPoint::MiddleCenter => ("middle", (height - lines_height) / 2.0),
+        AttachmentPoint::MiddleRight => ("end", (height - lines_height) / 2.0),
+        AttachmentPoint::BottomLeft => ("start", -lines_height),
+        AttachmentPoint::BottomCenter => ("middle", -lines_height),
+        AttachmentPoint::BottomRight => ("end", -lines_height),
+    };
+    let direction = &mtext.x_axis_direction;
+    let rotation = if direction.x != 0.0 || direction.y != 0.0 {
+        direction.y.atan2(direction.x).to_degrees()
+    } else {
+        mtext.rotation_angle
+    };
+
+    // the output's Y axis is flipped, so the text is flipped back and its lines run downwards
+    write!(
+        svg,
+        r#"<text transform="translate({:.p$} {:.p$}) rotate({:.p$}) scale(1 -1)" font-size="{:.p$}"{} text-anchor="{}" fill="{}" stroke="none">"#,
+        mtext.insertion_point.x,
+        mtext.insertion_point.y,
+        rotation,
+        height,
+        style.font_attribute(),
+        anchor,
+        color,
+        p = precision
+    ).unwrap();
+    for (index, line) in lines.iter().enumerate() {
+        write!(
+            svg,
+            r#"<tspan x="0" y="{:.p$}">"#,
+            first_baseline + pitch * index as f64,
+            p = precision
+        ).unwrap();
+        for run in line {
+            svg.push_str(&run_element(run, precision));
+        }
+        svg.push_str("</tspan>");
+    }
+    svg.push_str("</text>");
+}
+
+/// A run as text, in a `<tspan>` carrying its formatting if it has any.
+fn run_element(run: &Run, precision: usize) -> String {
+    let text = escape_xml_text(&run.text);
+    let format = &run.format;
+    let mut attributes = String::new();
+    if let Some(font) = &format.font {
+        write!(attributes, r#" font-family="{}""#, escape_xml_text(font)).unwrap();
+    }
+    if let Some(height) = format.height {
+        write!(attributes, r#" font-size="{:.p$}""#, height, p = precision).unwrap();
+    }
+    if let Some(color) = &format.color {
+        write!(attributes, r#" fill="{}""#, color).unwrap();
+    }
+    let decorations: Vec<&str> = [
+        (format.underline, "underline"),
+        (format.overline, "overline"),
+        (format.strike_through, "line-through"),
+    ]
+    .iter()
+    .filter(|(on, _)| *on)
+    .map(|(_, decoration)| *decoration)
+    .collect();
+    if !decorations.is_empty() {
+        write!(attributes, r#" text-decoration="{}""#, decorations.join(" ")).unwrap();
+    }
+    if attributes.is_empty() {
+        text
+    } else {
+        format!("<tspan{}>{}</tspan>", attributes, text)
+    }
+}
+
+/// Splits MTEXT content into paragraphs of formatted runs, applying its inline codes.
+fn parse(content: &str, height: f64) -> Vec<Vec<Run>> {
+    let mut paragraphs = vec![vec![]];
+    let mut format = Format::default();
+    let mut stack = vec![];
+    let mut chars = content.chars().peekable();
+    while let Some(c) = chars.next() {
+        let paragraph = paragraphs.last_mut().unwrap();
+        match c {
+            '{' => stack.push(format.clone()),
+            '}' => format = stack.pop().unwrap_or_else(|| format.clone()),
+            '\n' => paragraphs.push(vec![]),
+            '%' if chars.peek() == Some(&'%') => {
+                chars.next();
+                // only the symbol codes work in MTEXT, which underlines with \L instead, others are dropped
+                if let Some(symbol) = chars.next().and_then(control_code_symbol) {
+                    push_text(paragraph, &symbol.to_string(), &format);
+                }
+            }
+            '\\' => {
+                let Some(code) = chars.next() else {
+                    push_text(paragraph, "\\", &format);
+                    break;
+                };
+                // codes with a value run up to a semicolon
+                let mut value = || chars.by_ref().take_while(|&c| c != ';').collect::<String>();
+                match code {
+                    'P' | 'X' => paragraphs.push(vec![]),
+                    '~' => push_text(paragraph, "\u{a0}", &format),
+                    '\\' | '{' | '}' => push_text(paragraph, &code.to_string(), &format),
+                    'L' => format.underline = true,
+                    'l' => format.underline = false,
+                    'O' => format.overline = true,
+                    'o' => format.overline = false,
+                    'K' => format.strike_through = true,
+                    'k' => format.strike_through = false,
+                    'f' | 'F' => {
+                        let value = value();
+                        let font = value.split('|').next().unwrap_or_default();
+                        format.font = Some(font.to_string()).filter(|font| !font.is_empty());
+                    }
+                    'H' => {
+                        let value = value();
+                        let current = format.height.unwrap_or(height);
+                        let new_height = match value.strip_suffix(['x', 'X']) {
+                            Some(factor) => factor.parse::<f64>().map(|factor| factor * current),
+                            None => value.parse::<f64>(),
+                        };
+                        if let Ok(new_height) = new_height {
+                            format.height = Some(new_height).filter(|new_height| *new_height != height);
+                        }
+                    }
+                    'C' => {
+                        format.color = match value().parse::<u16>() {
+                            Ok(index @ 1..=255) => Some(color::aci_to_hex(index as u8)),
+                            _ => None,
+                        };
+                    }
+                    'c' => {
+                        if let Ok(rgb) = value().parse::<u32>() {
+                            format.color = Some(format!("#{:06X}", rgb & 0xFF_FFFF));
+                        }
+                    }
+                    'S' => {
+                        // stacked text like a fraction, written on one line
+                        let value = value();
+                        let stacked = value.replacen(['^', '#'], "/", 1);
+                        push_text(paragraph, stacked.trim(), &format);
+                    }
+                    'U' | 'M' => match read_escape(code, &mut chars) {
+                        Some(decoded) => push_text(paragraph, &decoded.to_string(), &format),
+                        None => push_text(paragraph, &format!("\\{}", code), &format),
+                    },
+                    'A' | 'Q' | 'W' | 'T' | 'p' => {
+                        value();
+                    }
+                    _ => {
+                        push_text(paragraph, &format!("\\{}", code), &format);
+                    }
+                }
+            }
+            _ => push_text(paragraph, &c.to_string(), &format),
+        }
+    }
+    paragraphs
+}
+
+/// Appends text to a paragraph, extending its last run when the formatting matches.
+fn push_text(paragraph: &mut Vec<Run>, text: &str, format: &Format) {
+    match paragraph.last_mut() {
+        Some(run) if run.format == *format => run.text.push_str(text),
+        _ => paragraph.push(Run { text: text.to_string(), format: format.clone() }),
+    }
+}
+
+/// Breaks paragraphs into lines no wider than `width`, between words. A width of 0 doesn't wrap.
+fn wrap(paragraphs: Vec<Vec<Run>>, width: f64, height: f64) -> Vec<Vec<Run>> {
+    if width <= 0.0 {
+        return paragraphs;
+    }
+    let mut lines = vec![];
+    for paragraph in paragraphs {
+        let mut line: Vec<Run> = vec![];
+        let mut line_width = 0.0;
+        for run in paragraph {
+            let character_width = CHARACTER_WIDTH * run.format.height.unwrap_or(height);
+            for word in run.text.split_inclusive(' ') {
+                let word_width = word.trim_end().chars().count() as f64 * character_width;
+                if !line.is_empty() && line_width + word_width > width {
+                    if let Some(last) = line.last_mut() {
+                        last.text.truncate(last.text.trim_end().len());
+                    }
+                    lines.push(std::mem::take(&mut line));
+                    line_width = 0.0;
+                }
+                push_text(&mut line, word, &run.format);
+                line_width += word.chars().count() as f64 * character_width;
+            }
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dxf::Point;
+
+    fn texts(lines: &[Vec<Run>]) -> Vec<String> {
+        lines.iter().map(|line| line.iter().map(|run| run.text.as_str()).collect()).collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        let paragraphs = parse(r"Room\P{\fArial|b1;\H2x;\C1;\LBig} 1/2\S1#4;\~\\%%c\U+00B1", 2.5);
+        assert_eq!(texts(&paragraphs), ["Room", "Big 1/21/4\u{a0}\\Ø±"]);
+        let big = &paragraphs[1][0];
+        assert_eq!(big.format.font.as_deref(), Some("Arial"));
+        assert_eq!(big.format.height, Some(5.0));
+        assert_eq!(big.format.color.as_deref(), Some("#FF0000"));
+        assert!(big.format.underline);
+        assert_eq!(paragraphs[1][1].format, Format::default());
+        assert_eq!(texts(&parse(r"A\U+0000B", 2.5)), [r"A\U+0000B"]);
+    }
+
+    #[test]
+    fn test_wrap() {
+        // 0.6 units per character
+        let lines = wrap(parse("one two three four", 1.0), 5.0, 1.0);
+        assert_eq!(texts(&lines), ["one two", "three", "four"]);
+        assert_eq!(texts(&wrap(parse("one two", 1.0), 0.0, 1.0)), ["one two"]);
+    }
+
+    #[test]
+    fn test_render_mtext() {
+        let mtext = MText {
+            insertion_point: Point::new(10.0, 20.0, 0.0),
+            initial_text_height: 2.0,
+            attachment_point: AttachmentPoint::BottomCenter,
+            text: r"First\PSecond".to_string(),
+            ..Default::default()
+        };
+        let mut svg = String::new();
+        render_mtext(&mtext, &TextStyle::default(), "black", 3, &mut svg);
+        assert_eq!(
+            svg,
+            concat!(
+                r#"<text transform="translate(10.000 20.000) rotate(0.000) scale(1 -1)" font-size="2.000" text-anchor="middle" fill="black" stroke="none">"#,
+                r#"<tspan x="0" y="-3.333">First</tspan><tspan x="0" y="0.000">Second</tspan></text>"#
+            )
+        );
+    }
+}
@@ -0,0 +1,198 @@
+use std::borrow::Cow;
+
+use dxf::entities::{Ellipse, Entity, EntityType};
+use dxf::{Point, Vector};
+
+/**
+An object coordinate system. Planar entities like circles, arcs, polylines and inserts store their
+coordinates relative to a plane given by their extrusion direction, and these must be converted to
+world coordinates before being drawn in the XY plane.
+The plane's axes come from the DXF "arbitrary axis algorithm".
+*/
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Ocs {
+    x_axis: (f64, f64, f64),
+    y_axis: (f64, f64, f64),
+    z_axis: (f64, f64, f64),
+}
+
+impl Ocs {
+    /// The coordinate system for an extrusion direction, or None if it's the world's (or unset).
+    pub(crate) fn new(normal: &Vector) -> Option<Ocs> {
+        let length = (normal.x * normal.x + normal.y * normal.y + normal.z * normal.z).sqrt();
+        if length == 0.0 || !length.is_finite() {
+            return None;
+        }
+        let z_axis = (normal.x / length, normal.y / length, normal.z / length);
+        if z_axis.0 == 0.0 && z_axis.1 == 0.0 && z_axis.2 > 0.0 {
+            return None;
+        }
+        let x_axis = if z_axis.0.abs() < 1.0 / 64.0 && z_axis.1.abs() < 1.0 / 64.0 {
+            normalize(cross((0.0, 1.0, 0.0), z_axis))
+        } else {
+            normalize(cross((0.0, 0.0, 1.0), z_axis))
+        };
+        let y_axis = normalize(cross(z_axis, x_axis));
+        Some(Ocs { x_axis, y_axis, z_axis })
+    }
+
+    /// Converts a point from object to world coordinates.
+    pub(crate) fn world_point(&self, x: f64, y: f64, z: f64) -> (f64, f64, f64) {
+        (
+            self.x_axis.0 * x + self.y_axis.0 * y + self.z_axis.0 * z,
+            self.x_axis.1 * x + self.y_axis.1 * y + self.z_axis.1 * z,
+            self.x_axis.2 * x + self.y_axis.2 * y + self.z_axis.2 * z,
+        )
+    }
+
+    fn point(&self, point: &Point) -> Point {
+        let (x, y, z) = self.world_point(point.x, point.y, point.z);
+        Point::new(x, y, z)
+    }
+
+    /// The world direction, in degrees in the XY plane, of an angle measured in the object's plane.
+    fn angle(&self, degrees: f64) -> f64 {
+        let (x, y, _) = self.world_point(degrees.to_radians().cos(), degrees.to_radians().sin(), 0.0);
+        y.atan2(x).to_degrees()
+    }
+
+    /// True if the plane faces away from the viewer, which mirrors everything drawn on it.
+    pub(crate) fn is_mirrored(&self) -> bool {
+        self.z_axis.2 < 0.0
+    }
+
+    /// True if the plane is parallel to the XY plane, so circles stay circles.
+    fn is_flat(&self) -> bool {
+        self.z_axis.0 == 0.0 && self.z_axis.1 == 0.0
+    }
+}
+
+fn cross(a: (f64, f64, f64), b: (f64, f64, f64)) -> (f64, f64, f64) {
+    (a.1 * b.2 - a.2 * b.1, a.2 * b.0 - a.0 * b.2, a.0 * b.1 - a.1 * b.0)
+}
+
+fn normalize(v: (f64, f64, f64)) -> (f64, f64, f64) {
+    let length = (v.0 * v.0 + v.1 * v.1 + v.2 * v.2).sqrt();
+    (v.0 / length, v.1 / length, v.2 / length)
+}
+
+/**
+Returns the entity with its object coordinates converted to world coordinates, or the entity itself
+when there's nothing to convert.
+Circles and arcs on tilted planes become the ellipses they project to. Polylines are converted
+as their vertices are read.
+*/
+pub(crate) fn to_wcs(entity: &Entity) -> Cow<'_, Entity> {
+    let normal = match &entity.specific {
+        EntityType::Circle(circle) => &circle.normal,
+        EntityType::Arc(arc) => &arc.normal,
+        EntityType::Text(text) => &text.normal,
+        EntityType::Solid(solid) => &solid.extrusion_direction,
+        EntityType::Trace(trace) => &trace.extrusion_direction,
+        EntityType::Insert(insert) => &insert.extrusion_direction,
+        _ => return Cow::Borrowed(entity),
+    };
+    let Some(ocs) = Ocs::new(normal) else {
+        return Cow::Borrowed(entity);
+    };
+
+    let mut entity = entity.clone();
+    let specific = match entity.specific {
+        EntityType::Circle(circle) if !ocs.is_flat() => {
+            EntityType::Ellipse(tilted_ellipse(&ocs, &circle.center, circle.radius, 0.0, 360.0))
+        }
+        EntityType::Circle(mut circle) => {
+            circle.center = ocs.point(&circle.center);
+            circle.normal = Vector::z_axis();
+            EntityType::Circle(circle)
+        }
+        EntityType::Arc(arc) if !ocs.is_flat() => {
+            EntityType::Ellipse(tilted_ellipse(&ocs, &arc.center, arc.radius, arc.start_angle, arc.end_angle))
+        }
+        EntityType::Arc(mut arc) => {
+            arc.center = ocs.point(&arc.center);
+            if ocs.is_mirrored() {
+                // mirroring reverses the direction the arc runs in
+                (arc.start_angle, arc.end_angle) = (ocs.angle(arc.end_angle), ocs.angle(arc.start_angle));
+            }
+            arc.normal = Vector::z_axis();
+            EntityType::Arc(arc)
+        }
+        EntityType::Text(mut text) => {
+            text.location = ocs.point(&text.location);
+            text.second_alignment_point = ocs.point(&text.second_alignment_point);
+            text.rotation = ocs.angle(text.rotation);
+            text.normal = Vector::z_axis();
+            EntityType::Text(text)
+        }
+        EntityType::Solid(mut solid) => {
+            for corner in [&mut solid.first_corner, &mut solid.second_corner, &mut solid.third_corner, &mut solid.fourth_corner] {
+                *corner = ocs.point(corner);
+            }
+            solid.extrusion_direction = Vector::z_axis();
+            EntityType::Solid(solid)
+        }
+        EntityType::Trace(mut trace) => {
+            for corner in [&mut trace.first_corner, &mut trace.second_corner, &mut trace.third_corner, &mut trace.fourth_corner] {
+                *corner = ocs.point(corner);
+            }
+            trace.extrusion_direction = Vector::z_axis();
+            EntityType::Trace(trace)
+        }
+        EntityType::Insert(mut insert) => {
+            insert.location = ocs.point(&insert.location);
+            insert.rotation = ocs.angle(insert.rotation);
+            if ocs.is_mirrored() {
+                // the block's y axis ends up on the other side of its x axis
+                insert.y_scale_factor = -insert.y_scale_factor;
+                insert.row_spacing = -insert.row_spacing;
+            }
+            insert.extrusion_direction = Vector::z_axis();
+            EntityType::Insert(insert)
+        }
+        specific => specific,
+    };
+    entity.specific = specific;
+    Cow::Owned(entity)
+}
+
+/// The ellipse a circle or arc on a tilted plane projects to. Angles are in degrees.
+fn tilted_ellipse(ocs: &Ocs, center: &Point, radius: f64, start_angle: f64, end_angle: f64) -> Ellipse {
+    let (x, y, z) = ocs.x_axis;
+    Ellipse {
+        center: ocs.point(center),
+        major_axis: Vector::new(x * radius, y * radius, z * radius),
+        normal: Vector::new(ocs.z_axis.0, ocs.z_axis.1, ocs.z_axis.2),
+        minor_axis_ratio: 1.0,
+        start_parameter: start_angle.to_radians(),
+        end_parameter: end_angle.to_radians(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dxf::entities::Arc;
+
+    #[test]
+    fn test_mirrored_arc() {
+        let mut arc = Arc::new(Point::new(1.0, 2.0, 0.0), 1.0, 0.0, 90.0);
+        arc.normal = Vector::new(0.0, 0.0, -1.0);
+        let arc = Entity::new(EntityType::Arc(arc));
+        let EntityType::Arc(arc) = &to_wcs(&arc).specific else {
+            panic!("the arc should stay an arc");
+        };
+        assert_eq!(arc.center, Point::new(-1.0, 2.0, 0.0));
+        assert!((arc.start_angle - 90.0).abs() < 1e-9);
+        assert!((arc.end_angle - 180.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_arbitrary_axis() {
+        let ocs = Ocs::new(&Vector::new(1.0, 0.0, 0.0)).unwrap();
+        let (x, y, z) = ocs.world_point(1.0, 2.0, 3.0);
+        assert!((x - 3.0).abs() < 1e-9 && (y - 1.0).abs() < 1e-9 && (z - 2.0).abs() < 1e-9);
+        assert!(Ocs::new(&Vector::z_axis()).is_none());
+        assert!(Ocs::new(&Vector::zero()).is_none());
+    }
+}
@@ -0,0 +1,182 @@
+use std::fmt::{self, Write};
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+use dxf::entities::Text;
+use ttf_parser::{Face, GlyphId, OutlineBuilder};
+
+use crate::text::{self, TextStyle};
+
+/// The cap height, in em, of fonts that don't store theirs
+const CAP_HEIGHT: f64 = 0.7;
+
+/**
+A TrueType or OpenType font to write text in as `<path>` outlines instead of `<text>`.
+The SVG then looks the same everywhere, whatever fonts the viewer has installed, which matters for
+archiving and for laser cutters and engravers that can't render text.
+*/
+#[derive(Clone)]
+pub struct OutlineFont {
+    data: Arc<Vec<u8>>,
+}
+
+impl OutlineFont {
+    /// Reads a `.ttf` or `.otf` file.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::from_bytes(std::fs::read(path)?)
+    }
+
+    /// Uses the contents of a `.ttf` or `.otf` file, failing with `InvalidData` if they aren't a font.
+    pub fn from_bytes(data: Vec<u8>) -> io::Result<Self> {
+        Face::parse(&data, 0).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error.to_string()))?;
+        Ok(OutlineFont { data: Arc::new(data) })
+    }
+
+    fn face(&self) -> Face<'_> {
+        Face::parse(&self.data, 0).expect("the font was parsed when it was loaded")
+    }
+}
+
+impl fmt::Debug for OutlineFont {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OutlineFont").field("bytes", &self.data.len()).finish()
+    }
+}
+
+/// Writes glyph outlines as path data, scaled from font units and moved to their place in the line.
+struct PathWriter {
+    path: String,
+    scale: f64,
+    x: f64,
+    y: f64,
+    precision: usize,
+}
+
+impl PathWriter {
+    fn command(&mut self, command: char, points: &[(f32, f32)]) {
+        if !self.path.is_empty() {
+            self.path.push(' ');
+        }
+        self.path.push(command);
+        for (x, y) in points {
+            write!(
+                self.path,
+                " {:.p$},{:.p$}",
+                self.x + *x as f64 * self.scale,
+                self.y + *y as f64 * self.scale,
+                p = self.precision
+            ).unwrap();
+        }
+    }
+}
+
+impl OutlineBuilder for PathWriter {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.command('M', &[(x, y)]);
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.command('L', &[(x, y)]);
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        self.command('Q', &[(x1, y1), (x, y)]);
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        self.command('C', &[(x1, y1), (x2, y2), (x, y)]);
+    }
+
+    fn close(&mut self) {
+        self.command('Z', &[]);
+    }
+}
+
+/// The scale from font units to the drawing for text `height` high, its glyphs and the length of the line.
+fn measure(face: &Face, text: &Text, height: f64) -> (f64, Vec<GlyphId>, f64) {
+    let units_per_em = f64::from(face.units_per_em());
+    let cap_height = face
+        .capital_height()
+        .filter(|height| *height > 0)
+        .map_or(CAP_HEIGHT * units_per_em, f64::from);
+    let scale = height / cap_height;
+
+    let glyphs: Vec<GlyphId> = text::plain_text(&text.value)
+        .chars()
+        .map(|c| face.glyph_index(c).unwrap_or(GlyphId(0)))
+        .collect();
+    let advance: f64 = glyphs
+        .iter()
+        .map(|glyph| f64::from(face.glyph_hor_advance(*glyph).unwrap_or(0)) * scale)
+        .sum();
+    (scale, glyphs, advance)
+}
+
+/// How long a TEXT is in `font` and how far its glyphs reach below the baseline, before any stretching.
+pub(crate) fn text_metrics(text: &Text, style: &TextStyle, font: &OutlineFont) -> (f64, f64) {
+    let face = font.face();
+    let layout = text::layout(text, style, text::UNROUNDED);
+    let (scale, _, advance) = measure(&face, text, layout.height);
+    (advance, -f64::from(face.descender()) * scale)
+}
+
+/**
+Renders a TEXT as a `<path>` of its glyph outlines in `font`, placed like `text::render_text` places
+`<text>`. The text height is the height of capital letters. Glyphs are kept Y up in the drawing's
+coordinates, so unlike `<text>` they need no flipping.
+*/
+pub(crate) fn render_text(
+    text: &Text,
+    style: &TextStyle,
+    font: &OutlineFont,
+    color: &str,
+    precision: usize,
+    svg: &mut String,
+) {
+    let face = font.face();
+    let layout = text::layout(text, style, precision);
+    let (scale, glyphs, advance) = measure(&face, text, layout.height);
+    if advance <= 0.0 {
+        return;
+    }
+    let (x, y) = layout.path_offset(advance, -f64::from(face.descender()) * scale);
+
+    let mut writer = PathWriter { path: String::new(), scale, x, y, precision };
+    for glyph in glyphs {
+        face.outline_glyph(glyph, &mut writer);
+        writer.x += f64::from(face.glyph_hor_advance(glyph).unwrap_or(0)) * scale;
+    }
+    if writer.path.is_empty() {
+        return;
+    }
+
+    write!(
+        svg,
+        r#"<path transform="{}" d="{}" fill="{}" stroke="none" />"#,
+        layout.path_transform(advance, precision),
+        writer.path,
+        color
+    ).unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_writer() {
+        let mut writer = PathWriter { path: String::new(), scale: 0.5, x: 10.0, y: 0.0, precision: 1 };
+        writer.move_to(0.0, 0.0);
+        writer.line_to(2.0, 0.0);
+        writer.quad_to(4.0, 2.0, 2.0, 4.0);
+        writer.close();
+        assert_eq!(writer.path, "M 10.0,0.0 L 11.0,0.0 Q 12.0,1.0 11.0,2.0 Z");
+    }
+
+    #[test]
+    fn test_invalid_font() {
+        let error = OutlineFont::from_bytes(b"not a font".to_vec()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
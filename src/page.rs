@@ -0,0 +1,84 @@
+/// A sheet of paper to fit the drawing onto. Sizes are in millimetres, portrait.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PageSize {
+    /// 210 x 297 mm
+    A4,
+    /// 297 x 420 mm
+    A3,
+    /// US Letter, 8.5 x 11 inches
+    Letter,
+    /// Any other sheet, in millimetres
+    Custom { width: f64, height: f64 },
+}
+
+impl PageSize {
+    /// The width and height of the page in millimetres, in portrait orientation.
+    pub fn dimensions(&self) -> (f64, f64) {
+        match *self {
+            PageSize::A4 => (210.0, 297.0),
+            PageSize::A3 => (297.0, 420.0),
+            PageSize::Letter => (215.9, 279.4),
+            PageSize::Custom { width, height } => (width, height),
+        }
+    }
+}
+
+/// Where a drawing goes on a page.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct PageLayout {
+    /// The page size in millimetres, turned to landscape for wide drawings
+    pub(crate) width: f64,
+    pub(crate) height: f64,
+    /// Millimetres per drawing unit
+    pub(crate) scale: f64,
+    /// The position of the drawing's top left corner on the page
+    pub(crate) offset_x: f64,
+    pub(crate) offset_y: f64,
+}
+
+/**
+Lays a drawing of the given size out on a page: turned to match the drawing's orientation,
+scaled to fit inside the margins unless a fixed scale (millimetres per drawing unit) is given, and centered.
+*/
+pub(crate) fn page_layout(page: PageSize, margin: f64, width: f64, height: f64, scale: Option<f64>) -> PageLayout {
+    let (short, long) = {
+        let (a, b) = page.dimensions();
+        (a.min(b), a.max(b))
+    };
+    let (page_width, page_height) = if width > height { (long, short) } else { (short, long) };
+    let available_width = (page_width - 2.0 * margin).max(0.0);
+    let available_height = (page_height - 2.0 * margin).max(0.0);
+    let scale = scale.unwrap_or_else(|| {
+        let fit_x = if width > 0.0 { available_width / width } else { f64::INFINITY };
+        let fit_y = if height > 0.0 { available_height / height } else { f64::INFINITY };
+        let fit = fit_x.min(fit_y);
+        if fit.is_finite() { fit } else { 1.0 }
+    });
+    PageLayout {
+        width: page_width,
+        height: page_height,
+        scale,
+        offset_x: margin + (available_width - width * scale) / 2.0,
+        offset_y: margin + (available_height - height * scale) / 2.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_layout() {
+        // a wide drawing turns the page to landscape and is limited by the page's height
+        let layout = page_layout(PageSize::A4, 10.0, 400.0, 100.0, None);
+        assert_eq!((layout.width, layout.height), (297.0, 210.0));
+        assert_eq!(layout.scale, 277.0 / 400.0);
+        assert_eq!(layout.offset_x, 10.0);
+        assert!((layout.offset_y - (10.0 + (190.0 - 100.0 * layout.scale) / 2.0)).abs() < 1e-9);
+
+        // with a fixed scale the drawing is only centered
+        let layout = page_layout(PageSize::A4, 10.0, 100.0, 100.0, Some(1.0));
+        assert_eq!(layout.scale, 1.0);
+        assert_eq!((layout.offset_x, layout.offset_y), (55.0, 98.5));
+    }
+}
@@ -0,0 +1,155 @@
+use crate::color::aci_to_hex;
+use std::collections::HashMap;
+
+/// How an entity is plotted. Fields left as None keep the entity's own appearance.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PlotStyle {
+    /// The plotted color, e.g. "black" for monochrome output
+    pub color: Option<String>,
+    /// The plotted lineweight in millimetres, converted with `SvgOptions::lineweight_scale`.
+    /// Ignored if that isn't set, like the entities' own lineweights.
+    pub lineweight: Option<f64>,
+    /// The screening percentage: 100 plots at full intensity, 0 doesn't show at all
+    pub screening: Option<u8>,
+}
+
+/**
+A plot style table, replicating how a drawing appears when plotted with a CTB or STB file.
+
+* `ColorDependent` - a CTB table: plot styles by the ACI color index of the entity
+* `Named` - an STB table: plot styles by the plot style name assigned to the entity
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlotStyleTable {
+    ColorDependent(HashMap<u8, PlotStyle>),
+    Named(HashMap<String, PlotStyle>),
+}
+
+impl PlotStyleTable {
+    /// The equivalent of `monochrome.ctb`: every color plots black.
+    pub fn monochrome() -> Self {
+        let style = PlotStyle {
+            color: Some("black".to_string()),
+            ..Default::default()
+        };
+        PlotStyleTable::ColorDependent((1..=255).map(|index| (index, style.clone())).collect())
+    }
+
+    /**
+    Parses the decompressed text of a `.ctb` or `.stb` file. A table with `aci_table_available=TRUE` is a
+    color dependent CTB table, any other a named STB table. Colors, lineweights and screening set to "use object"
+    values are left as None.
+    */
+    pub fn parse(contents: &str) -> Self {
+        let mut color_dependent = false;
+        let mut lineweights: HashMap<String, f64> = HashMap::new();
+        let mut styles: Vec<HashMap<String, String>> = vec![];
+        let mut sections: Vec<String> = vec![];
+        for line in contents.lines() {
+            let line = line.trim();
+            if let Some(section) = line.strip_suffix('{') {
+                if sections.len() == 1 && sections[0] == "plot_style" {
+                    styles.push(HashMap::new());
+                }
+                sections.push(section.trim().to_string());
+            } else if line == "}" {
+                sections.pop();
+            } else if let Some((key, value)) = line.split_once('=') {
+                let (key, value) = (key.trim(), value.trim());
+                match sections.first().map(String::as_str) {
+                    None if key == "aci_table_available" => color_dependent = value.eq_ignore_ascii_case("TRUE"),
+                    Some("custom_lineweight_table") => {
+                        if let Ok(width) = value.parse() {
+                            lineweights.insert(key.to_string(), width);
+                        }
+                    }
+                    Some("plot_style") if sections.len() == 2 => {
+                        if let Some(style) = styles.last_mut() {
+                            let value = value.strip_prefix('"').unwrap_or(value);
+                            style.insert(key.to_string(), value.strip_suffix('"').unwrap_or(value).to_string());
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        let plot_style = |fields: &HashMap<String, String>| PlotStyle {
+            color: fields.get("color").and_then(|color| color.parse().ok()).and_then(plot_color),
+            lineweight: fields.get("lineweight").filter(|index| *index != "255").and_then(|index| lineweights.get(index)).copied(),
+            screening: fields.get("screen").and_then(|screen| screen.parse().ok()).filter(|screen| *screen < 100),
+        };
+        if color_dependent {
+            let styles = styles.iter().zip(1..=255).map(|(fields, index)| (index, plot_style(fields)));
+            PlotStyleTable::ColorDependent(styles.collect())
+        } else {
+            let styles = styles.iter().filter_map(|fields| Some((fields.get("name")?.clone(), plot_style(fields))));
+            PlotStyleTable::Named(styles.collect())
+        }
+    }
+
+    /// Reads a `.ctb` or `.stb` file, decompressing and parsing it. Needs the `plot-styles` feature.
+    #[cfg(feature = "plot-styles")]
+    pub fn load(path: impl AsRef<std::path::Path>) -> std::io::Result<Self> {
+        use std::io::Read;
+        let bytes = std::fs::read(path)?;
+        // a text header and three 4 byte sizes come before the zlib stream
+        let stream = bytes
+            .get(60..)
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidData, "plot style table too short"))?;
+        let mut contents = String::new();
+        flate2::read::ZlibDecoder::new(stream).read_to_string(&mut contents)?;
+        Ok(Self::parse(&contents))
+    }
+}
+
+/**
+Converts a plot style color, a 32 bit integer whose high byte tells how to read the rest: `0xC3` holds an ACI
+index in the low byte, or `0xFFFFFF` for "use object color", other values hold an RGB color.
+*/
+fn plot_color(color: i32) -> Option<String> {
+    let color = color as u32;
+    match color >> 24 {
+        0xC3 if color & 0xFFFFFF == 0xFFFFFF => None,
+        0xC3 => Some(aci_to_hex(color as u8)),
+        _ => Some(format!("#{:06X}", color & 0xFFFFFF)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CTB: &str = "description=\"\"\naci_table_available=TRUE\nplot_style{\n 0{\n  name=\"Color_1\n  color=-1006632961\n  screen=100\n  lineweight=255\n }\n 1{\n  name=\"Color_2\n  color=-16777216\n  screen=50\n  lineweight=2\n }\n 2{\n  name=\"Color_3\n  color=-1023410171\n }\n}\ncustom_lineweight_table{\n 0=0.0\n 1=0.05\n 2=0.09\n}\n";
+
+    #[test]
+    fn test_parse() {
+        let PlotStyleTable::ColorDependent(styles) = PlotStyleTable::parse(CTB) else { panic!("expected a CTB table") };
+        assert_eq!(styles[&1], PlotStyle::default());
+        assert_eq!(
+            styles[&2],
+            PlotStyle { color: Some("#000000".to_string()), lineweight: Some(0.09), screening: Some(50) }
+        );
+        assert_eq!(styles[&3].color.as_deref(), Some("#0000FF"));
+
+        let stb = CTB.replace("aci_table_available=TRUE", "aci_table_available=FALSE");
+        let PlotStyleTable::Named(styles) = PlotStyleTable::parse(&stb) else { panic!("expected an STB table") };
+        assert_eq!(styles["Color_2"].screening, Some(50));
+        assert_eq!(styles.len(), 3);
+    }
+
+    #[cfg(feature = "plot-styles")]
+    #[test]
+    fn test_load() {
+        use std::io::Write;
+
+        let mut bytes = b"PIAFILEVERSION_2.0,CTBVER1,compress\r\npmzlibcodec".to_vec();
+        bytes.resize(60, 0);
+        let mut encoder = flate2::write::ZlibEncoder::new(bytes, flate2::Compression::default());
+        encoder.write_all(CTB.as_bytes()).unwrap();
+        let path = std::env::temp_dir().join(format!("dxf_to_svg_test_{}.ctb", std::process::id()));
+        std::fs::write(&path, encoder.finish().unwrap()).unwrap();
+        let table = PlotStyleTable::load(&path);
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(table.unwrap(), PlotStyleTable::parse(CTB));
+    }
+}
@@ -0,0 +1,110 @@
+use std::fmt::Write;
+
+/// The symbol drawn at a POINT by `$PDMODE`, in its lowest 3 bits
+const SYMBOL_MASK: i32 = 7;
+/// `$PDMODE` flag: a circle around the symbol
+const CIRCLE: i32 = 32;
+/// `$PDMODE` flag: a square around the symbol
+const SQUARE: i32 = 64;
+
+/// How POINT entities are shown, from `$PDMODE` and the symbol size in drawing units.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct PointDisplay {
+    pub mode: i32,
+    pub size: f64,
+}
+
+/// The size of a POINT symbol in drawing units from `$PDSIZE`, which is either absolute or, when it's 0
+/// or negative, a percentage of the height of the view (5% for 0).
+pub(crate) fn point_size(size: f64, view_height: f64) -> f64 {
+    if size > 0.0 {
+        size
+    } else if size < 0.0 {
+        -size / 100.0 * view_height
+    } else {
+        0.05 * view_height
+    }
+}
+
+/**
+Renders a POINT at `(x, y)` as AutoCAD shows it for a `$PDMODE`: a dot (0), nothing (1), a cross (2),
+an X (3) or a tick upwards (4), optionally inside a circle (+32) and/or a square (+64), the display's
+size across. The dot is a small disc `dot_radius` wide, as it doesn't scale with `$PDSIZE`.
+*/
+pub(crate) fn render_point(
+    (x, y): (f64, f64),
+    display: PointDisplay,
+    dot_radius: f64,
+    stroke_attr: &str,
+    color: &str,
+    precision: usize,
+    svg: &mut String,
+) {
+    let mode = display.mode;
+    let half = display.size / 2.0;
+    let mut path = vec![];
+    let mut line = |x1: f64, y1: f64, x2: f64, y2: f64| {
+        path.push(format!("M {:.p$},{:.p$} L {:.p$},{:.p$}", x1, y1, x2, y2, p = precision));
+    };
+    match mode & SYMBOL_MASK {
+        2 => {
+            line(x - half, y, x + half, y);
+            line(x, y - half, x, y + half);
+        }
+        3 => {
+            line(x - half, y - half, x + half, y + half);
+            line(x - half, y + half, x + half, y - half);
+        }
+        4 => line(x, y, x, y + half),
+        _ => {}
+    }
+    if mode & CIRCLE != 0 {
+        path.push(format!(
+            "M {:.p$},{:.p$} A {:.p$},{:.p$} 0 1 1 {:.p$},{:.p$} A {:.p$},{:.p$} 0 1 1 {:.p$},{:.p$}",
+            x - half, y, half, half, x + half, y, half, half, x - half, y, p = precision
+        ));
+    }
+    if mode & SQUARE != 0 {
+        path.push(format!(
+            "M {:.p$},{:.p$} L {:.p$},{:.p$} L {:.p$},{:.p$} L {:.p$},{:.p$} Z",
+            x - half, y - half, x + half, y - half, x + half, y + half, x - half, y + half, p = precision
+        ));
+    }
+    if mode & SYMBOL_MASK == 0 {
+        write!(
+            svg,
+            r#"<circle cx="{:.p$}" cy="{:.p$}" r="{}" {} fill="{}" />"#,
+            x, y, dot_radius, stroke_attr, color, p = precision
+        ).unwrap();
+    }
+    if !path.is_empty() {
+        write!(svg, r#"<path d="{}" {} fill="none" />"#, path.join(" "), stroke_attr).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_point_size() {
+        assert_eq!(point_size(2.0, 100.0), 2.0);
+        assert_eq!(point_size(0.0, 100.0), 5.0);
+        assert_eq!(point_size(-10.0, 100.0), 10.0);
+    }
+
+    #[test]
+    fn test_render_point() {
+        let mut svg = String::new();
+        render_point((1.0, 2.0), PointDisplay { mode: 2 + SQUARE, size: 2.0 }, 0.1, r#"stroke="red""#, "red", 0, &mut svg);
+        assert_eq!(svg, r#"<path d="M 0,2 L 2,2 M 1,1 L 1,3 M 0,1 L 2,1 L 2,3 L 0,3 Z" stroke="red" fill="none" />"#);
+
+        let mut svg = String::new();
+        render_point((1.0, 2.0), PointDisplay { mode: 0, size: 2.0 }, 0.1, r#"stroke="red""#, "red", 0, &mut svg);
+        assert_eq!(svg, r#"<circle cx="1" cy="2" r="0.1" stroke="red" fill="red" />"#);
+
+        let mut svg = String::new();
+        render_point((1.0, 2.0), PointDisplay { mode: 1, size: 2.0 }, 0.1, r#"stroke="red""#, "red", 0, &mut svg);
+        assert!(svg.is_empty());
+    }
+}
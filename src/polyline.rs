@@ -0,0 +1,434 @@
+use std::fmt::Write;
+
+use dxf::entities::{Entity, LwPolyline, Polyline};
+
+use crate::emit::Points;
+use crate::geometry;
+use crate::ocs::Ocs;
+use crate::style::Style;
+use crate::RenderContext;
+
+/// A polyline vertex with the bulge and widths of the segment starting at it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct PolylineVertex {
+    pub(crate) x: f64,
+    pub(crate) y: f64,
+    pub(crate) bulge: f64,
+    pub(crate) start_width: f64,
+    pub(crate) end_width: f64,
+}
+
+/**
+The vertices of a lightweight polyline in world coordinates. A constant width overrides the per-vertex widths.
+Bulges keep their circular arcs, so arcs on tilted planes are only approximated.
+*/
+pub(crate) fn lwpolyline_vertices(lwpolyline: &LwPolyline) -> Vec<PolylineVertex> {
+    let ocs = Ocs::new(&lwpolyline.extrusion_direction);
+    lwpolyline
+        .vertices
+        .iter()
+        .map(|vertex| {
+            let (start_width, end_width) = if lwpolyline.constant_width != 0.0 {
+                (lwpolyline.constant_width, lwpolyline.constant_width)
+            } else {
+                (vertex.starting_width, vertex.ending_width)
+            };
+            to_wcs(ocs.as_ref(), PolylineVertex {
+                x: vertex.x,
+                y: vertex.y,
+                bulge: vertex.bulge,
+                start_width,
+                end_width,
+            }, lwpolyline.elevation)
+        })
+        .collect()
+}
+
+// POLYLINE flags (group code 70)
+const POLYLINE_CLOSED_M: i32 = 1;
+const POLYLINE_3D: i32 = 8;
+const POLYLINE_POLYGON_MESH: i32 = 16;
+const POLYLINE_CLOSED_N: i32 = 32;
+const POLYLINE_POLYFACE_MESH: i32 = 64;
+
+// VERTEX flags (group code 70)
+const VERTEX_SPLINE_FRAME_CONTROL_POINT: i32 = 16;
+const VERTEX_POLYFACE_MESH: i32 = 128;
+const VERTEX_LOCATION: i32 = 64;
+
+/// True for 3D polylines and meshes, whose vertices are in world coordinates rather than on the polyline's plane.
+pub(crate) fn is_3d(polyline: &Polyline) -> bool {
+    polyline.flags & (POLYLINE_3D | POLYLINE_POLYGON_MESH | POLYLINE_POLYFACE_MESH) != 0
+}
+
+/// True for the face records of a polyface mesh, which only hold vertex indices.
+fn is_face_record(flags: i32) -> bool {
+    flags & VERTEX_POLYFACE_MESH != 0 && flags & VERTEX_LOCATION == 0
+}
+
+/**
+The vertices of a polyline, projected to the XY plane. Vertices without their own widths use the
+polyline's default widths. 2D polylines are converted from their object coordinates like lightweight ones.
+Polyface face records and spline frame control points aren't part of the drawn outline and are skipped.
+*/
+pub(crate) fn polyline_vertices(polyline: &Polyline) -> Vec<PolylineVertex> {
+    let ocs = if is_3d(polyline) { None } else { Ocs::new(&polyline.normal) };
+    polyline
+        .vertices()
+        .filter(|vertex| !is_face_record(vertex.flags) && vertex.flags & VERTEX_SPLINE_FRAME_CONTROL_POINT == 0)
+        .map(|vertex| {
+            let (start_width, end_width) = if vertex.starting_width != 0.0 || vertex.ending_width != 0.0 {
+                (vertex.starting_width, vertex.ending_width)
+            } else {
+                (polyline.default_starting_width, polyline.default_ending_width)
+            };
+            to_wcs(ocs.as_ref(), PolylineVertex {
+                x: vertex.location.x,
+                y: vertex.location.y,
+                bulge: vertex.bulge,
+                start_width,
+                end_width,
+            }, vertex.location.z)
+        })
+        .collect()
+}
+
+/// Converts a vertex from object coordinates, flipping the direction of its bulge if the plane is mirrored.
+fn to_wcs(ocs: Option<&Ocs>, vertex: PolylineVertex, z: f64) -> PolylineVertex {
+    let Some(ocs) = ocs else {
+        return vertex;
+    };
+    let (x, y, _) = ocs.world_point(vertex.x, vertex.y, z);
+    let bulge = if ocs.is_mirrored() { -vertex.bulge } else { vertex.bulge };
+    PolylineVertex { x, y, bulge, ..vertex }
+}
+
+/**
+The edges of a polyface or polygon mesh projected to the XY plane, or None for ordinary polylines.
+Polyface edges given a negative vertex index are invisible and left out.
+*/
+pub(crate) fn mesh_edges(polyline: &Polyline) -> Option<Vec<[(f64, f64); 2]>> {
+    if polyline.flags & POLYLINE_POLYFACE_MESH != 0 {
+        Some(polyface_edges(polyline))
+    } else if polyline.flags & POLYLINE_POLYGON_MESH != 0 {
+        Some(polygon_mesh_edges(polyline))
+    } else {
+        None
+    }
+}
+
+fn polyface_edges(polyline: &Polyline) -> Vec<[(f64, f64); 2]> {
+    let points: Vec<_> = polyline
+        .vertices()
+        .filter(|vertex| !is_face_record(vertex.flags))
+        .map(|vertex| (vertex.location.x, vertex.location.y))
+        .collect();
+    let mut edges = vec![];
+    for face in polyline.vertices().filter(|vertex| is_face_record(vertex.flags)) {
+        let indices: Vec<i32> = [
+            face.polyface_mesh_vertex_index1,
+            face.polyface_mesh_vertex_index2,
+            face.polyface_mesh_vertex_index3,
+            face.polyface_mesh_vertex_index4,
+        ]
+        .into_iter()
+        .take_while(|&index| index != 0)
+        .collect();
+        for (i, &index) in indices.iter().enumerate() {
+            let next = indices[(i + 1) % indices.len()];
+            if index < 0 || indices.len() < 2 {
+                continue;
+            }
+            let point = |index: i32| points.get(index.unsigned_abs() as usize - 1).copied();
+            if let (Some(start), Some(end)) = (point(index), point(next)) {
+                edges.push([start, end]);
+            }
+        }
+    }
+    edges
+}
+
+fn polygon_mesh_edges(polyline: &Polyline) -> Vec<[(f64, f64); 2]> {
+    let points: Vec<_> = polyline.vertices().map(|vertex| (vertex.location.x, vertex.location.y)).collect();
+    let m = polyline.polygon_mesh_m_vertex_count.max(0) as usize;
+    let n = polyline.polygon_mesh_n_vertex_count.max(0) as usize;
+    if m == 0 || n == 0 || points.len() < m * n {
+        return vec![];
+    }
+    let closed_m = polyline.flags & POLYLINE_CLOSED_M != 0;
+    let closed_n = polyline.flags & POLYLINE_CLOSED_N != 0;
+    let mut edges = vec![];
+    for i in 0..m {
+        for j in 0..n {
+            let point = points[i * n + j];
+            if j + 1 < n || (closed_n && n > 2) {
+                edges.push([point, points[i * n + (j + 1) % n]]);
+            }
+            if i + 1 < m || (closed_m && m > 2) {
+                edges.push([point, points[((i + 1) % m) * n + j]]);
+            }
+        }
+    }
+    edges
+}
+
+/// Renders mesh edges as a single unfilled path.
+pub(crate) fn render_mesh_edges(edges: &[[(f64, f64); 2]], stroke_attr: &str, ctx: &RenderContext, svg: &mut String) {
+    let precision = ctx.precision;
+    if edges.is_empty() {
+        return;
+    }
+    svg.push_str(r#"<path d=""#);
+    for (i, [(x1, y1), (x2, y2)]) in edges.iter().enumerate() {
+        let separator = if i == 0 { "" } else { " " };
+        write!(svg, "{}M {:.p$},{:.p$} L {:.p$},{:.p$}", separator, x1, y1, x2, y2, p = precision).unwrap();
+    }
+    write!(svg, r#"" {} fill="none" />"#, stroke_attr).unwrap();
+}
+
+/// Pairs of consecutive polyline vertices, including the segment back to the start of closed polylines.
+pub(crate) fn polyline_segments(
+    vertices: &[PolylineVertex],
+    closed: bool,
+) -> impl Iterator<Item = (PolylineVertex, PolylineVertex)> + '_ {
+    let closing = match (vertices.first(), vertices.last()) {
+        (Some(&first), Some(&last)) if closed && vertices.len() >= 2 => Some((last, first)),
+        _ => None,
+    };
+    vertices.windows(2).map(|pair| (pair[0], pair[1])).chain(closing)
+}
+
+/// The fewest arcs a polyline needs to be taken for a revision cloud
+const REVISION_CLOUD_ARCS: usize = 3;
+
+/**
+True if every segment of a polyline is an arc bulging the same way by about the same amount, the
+scallops AutoCAD's REVCLOUD draws around changed areas.
+*/
+pub(crate) fn is_revision_cloud(vertices: &[PolylineVertex], closed: bool) -> bool {
+    let segments: Vec<_> = polyline_segments(vertices, closed).collect();
+    let Some((first, _)) = segments.first() else {
+        return false;
+    };
+    segments.len() >= REVISION_CLOUD_ARCS
+        && first.bulge != 0.0
+        && segments.iter().all(|(start, _)| {
+            start.bulge.signum() == first.bulge.signum() && (start.bulge - first.bulge).abs() <= 0.1 * first.bulge.abs()
+        })
+}
+
+/**
+Renders a polyline. Polylines without bulges are plain `<polyline>`s or `<polygon>`s, otherwise
+the bulged segments become arcs of a `<path>`.
+A polyline with one constant width is stroked that wide. When widths vary, tapered segments are drawn as
+filled outlines, with arcs flattened to within the curve tolerance, and the others are stroked as usual. Revision clouds get round joins between their
+scallops, and the `revcloud` class with `revision_cloud_class`.
+`fill` is only applied to closed polylines.
+*/
+pub(crate) fn render_polyline(
+    vertices: &[PolylineVertex],
+    closed: bool,
+    fill: &str,
+    style: &Style,
+    entity: &Entity,
+    ctx: &RenderContext,
+    svg: &mut String,
+) {
+    let precision = ctx.precision;
+    if vertices.is_empty() {
+        return;
+    }
+
+    let segments: Vec<_> = polyline_segments(vertices, closed).collect();
+    let first_width = segments.first().map_or(0.0, |(start, _)| start.start_width);
+    let constant_width = segments
+        .iter()
+        .all(|(start, _)| start.start_width == first_width && start.end_width == first_width);
+    if !constant_width {
+        render_tapered_segments(&segments, style, entity, ctx, svg);
+        return;
+    }
+
+    let mut style = style.clone();
+    if first_width > 0.0 {
+        style.width = first_width;
+    }
+    let stroke_attr = ctx.stroke_attributes(&style, entity);
+    let fill = if closed { fill } else { "none" };
+
+    if vertices.iter().all(|vertex| vertex.bulge == 0.0) {
+        let points: Vec<(f64, f64)> = vertices.iter().map(|vertex| (vertex.x, vertex.y)).collect();
+        write!(
+            svg,
+            r#"<{} points="{}" {} fill="{}" />"#,
+            if closed { "polygon" } else { "polyline" },
+            Points(&points, precision),
+            stroke_attr,
+            fill
+        ).unwrap();
+        return;
+    }
+
+    let mut stroke_attr = stroke_attr;
+    if is_revision_cloud(vertices, closed) {
+        stroke_attr.push_str(r#" stroke-linejoin="round""#);
+        if ctx.options.revision_cloud_class {
+            stroke_attr = match stroke_attr.strip_prefix(r#"class=""#) {
+                Some(classes) => format!(r#"class="revcloud {}"#, classes),
+                None => format!(r#"class="revcloud" {}"#, stroke_attr),
+            };
+        }
+    }
+    let mut path = format!("M {:.p$},{:.p$}", vertices[0].x, vertices[0].y, p = precision);
+    for (start, end) in &segments {
+        push_segment(&mut path, start, end, precision);
+    }
+    if closed {
+        path.push_str(" Z");
+    }
+    write!(svg, r#"<path d="{}" {} fill="{}" />"#, path, stroke_attr, fill).unwrap();
+}
+
+/// Adds a segment to the `d` of a path, as an arc if it bulges and a line otherwise.
+fn push_segment(path: &mut String, start: &PolylineVertex, end: &PolylineVertex, precision: usize) {
+    match geometry::bulge_arc(start.x, start.y, end.x, end.y, start.bulge) {
+        Some(arc) => {
+            // inside the flipped group a positive sweep runs counterclockwise, like a positive bulge
+            let large_arc = if start.bulge.abs() > 1.0 { 1 } else { 0 };
+            let sweep = if start.bulge > 0.0 { 1 } else { 0 };
+            write!(
+                path,
+                " A {:.p$},{:.p$} 0 {} {} {:.p$},{:.p$}",
+                arc.radius, arc.radius, large_arc, sweep, end.x, end.y, p = precision
+            ).unwrap();
+        }
+        None => write!(path, " L {:.p$},{:.p$}", end.x, end.y, p = precision).unwrap(),
+    }
+}
+
+/**
+Draws a polyline whose segments differ in width. Runs of segments keeping one width are stroked as a path,
+that wide or with the entity's stroke width if they have none. Tapered segments are filled outlines whose
+width runs from the segment's start width to its end width, in the entity's style without a stroke.
+*/
+fn render_tapered_segments(
+    segments: &[(PolylineVertex, PolylineVertex)],
+    style: &Style,
+    entity: &Entity,
+    ctx: &RenderContext,
+    svg: &mut String,
+) {
+    let precision = ctx.precision;
+    let outline_style = Style { width: 0.0, dash_array: None, round_caps: false, ..style.clone() };
+    let outline_attr = ctx.stroke_attributes(&outline_style, entity);
+    // the width and path of the segments stroked so far
+    let mut run: Option<(f64, String)> = None;
+    for (start, end) in segments {
+        let tapered = start.start_width != start.end_width;
+        if run.as_ref().is_some_and(|(width, _)| tapered || *width != start.start_width) {
+            stroke_run(run.take(), style, entity, ctx, svg);
+        }
+        if !tapered {
+            let (_, path) = run.get_or_insert_with(|| {
+                (start.start_width, format!("M {:.p$},{:.p$}", start.x, start.y, p = precision))
+            });
+            push_segment(path, start, end, precision);
+            continue;
+        }
+        let samples = segment_samples(start, end, ctx.options.curve_tolerance);
+        let last = (samples.len() - 1) as f64;
+        let mut left = vec![];
+        let mut right = vec![];
+        for (i, (x, y, normal_x, normal_y)) in samples.into_iter().enumerate() {
+            let half_width = (start.start_width + (start.end_width - start.start_width) * i as f64 / last) / 2.0;
+            left.push(format!("{:.p$},{:.p$}", x + normal_x * half_width, y + normal_y * half_width, p = precision));
+            right.push(format!("{:.p$},{:.p$}", x - normal_x * half_width, y - normal_y * half_width, p = precision));
+        }
+        right.reverse();
+        left.extend(right);
+        write!(svg, r#"<polygon points="{}" {} fill="{}" />"#, left.join(" "), outline_attr, style.color).unwrap();
+    }
+    stroke_run(run, style, entity, ctx, svg);
+}
+
+/// Strokes a run of segments of one width, if there is one.
+fn stroke_run(run: Option<(f64, String)>, style: &Style, entity: &Entity, ctx: &RenderContext, svg: &mut String) {
+    let Some((width, path)) = run else {
+        return;
+    };
+    let mut style = style.clone();
+    if width > 0.0 {
+        style.width = width;
+    }
+    write!(svg, r#"<path d="{}" {} fill="none" />"#, path, ctx.stroke_attributes(&style, entity)).unwrap();
+}
+
+/// Points along a segment as (x, y, normal x, normal y). Straight segments only need their end points,
+/// arcs are sampled along their length.
+fn segment_samples(start: &PolylineVertex, end: &PolylineVertex, tolerance: f64) -> Vec<(f64, f64, f64, f64)> {
+    let Some(arc) = geometry::bulge_arc(start.x, start.y, end.x, end.y, start.bulge) else {
+        let length = (end.x - start.x).hypot(end.y - start.y).max(f64::EPSILON);
+        let (normal_x, normal_y) = (-(end.y - start.y) / length, (end.x - start.x) / length);
+        return vec![(start.x, start.y, normal_x, normal_y), (end.x, end.y, normal_x, normal_y)];
+    };
+    let from = (start.y - arc.center_y).atan2(start.x - arc.center_x);
+    let sweep = 4.0 * start.bulge.atan();
+    let samples = geometry::arc_segments(arc.radius, sweep, tolerance);
+    (0..=samples)
+        .map(|i| {
+            let angle = from + sweep * i as f64 / samples as f64;
+            let (normal_x, normal_y) = (angle.cos(), angle.sin());
+            (arc.center_x + arc.radius * normal_x, arc.center_y + arc.radius * normal_y, normal_x, normal_y)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dxf::entities::Vertex;
+    use dxf::{Drawing, Point};
+
+    #[test]
+    fn test_is_revision_cloud() {
+        let vertex = |x: f64, bulge: f64| PolylineVertex { x, y: 0.0, bulge, start_width: 0.0, end_width: 0.0 };
+        let cloud = [vertex(0.0, 0.5), vertex(1.0, 0.52), vertex(2.0, 0.5), vertex(3.0, 0.5)];
+        assert!(is_revision_cloud(&cloud, false));
+        let mixed = [vertex(0.0, 0.5), vertex(1.0, -0.5), vertex(2.0, 0.5), vertex(3.0, 0.5)];
+        assert!(!is_revision_cloud(&mixed, false));
+        let straight = [vertex(0.0, 0.5), vertex(1.0, 0.5), vertex(2.0, 0.5), vertex(3.0, 0.0)];
+        assert!(is_revision_cloud(&straight, false));
+        assert!(!is_revision_cloud(&straight, true));
+        assert!(!is_revision_cloud(&cloud[..3], false));
+    }
+
+    #[test]
+    fn test_polyface_edges() {
+        let mut drawing = Drawing::new();
+        let mut polyface = Polyline::default();
+        polyface.flags = POLYLINE_POLYFACE_MESH;
+        for (x, y) in [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)] {
+            let vertex = Vertex { flags: VERTEX_POLYFACE_MESH | VERTEX_LOCATION, ..Vertex::new(Point::new(x, y, 0.0)) };
+            polyface.add_vertex(&mut drawing, vertex);
+        }
+        // two triangles sharing a hidden diagonal
+        for indices in [(1, 2, -3), (3, 4, -1)] {
+            let face = Vertex {
+                flags: VERTEX_POLYFACE_MESH,
+                polyface_mesh_vertex_index1: indices.0,
+                polyface_mesh_vertex_index2: indices.1,
+                polyface_mesh_vertex_index3: indices.2,
+                ..Default::default()
+            };
+            polyface.add_vertex(&mut drawing, face);
+        }
+
+        assert_eq!(polyline_vertices(&polyface).len(), 4);
+        assert_eq!(mesh_edges(&polyface), Some(vec![
+            [(0.0, 0.0), (1.0, 0.0)],
+            [(1.0, 0.0), (1.0, 1.0)],
+            [(1.0, 1.0), (0.0, 1.0)],
+            [(0.0, 1.0), (0.0, 0.0)],
+        ]));
+    }
+}
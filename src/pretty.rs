@@ -0,0 +1,143 @@
+/// The indentation added per nesting level.
+const INDENT: &str = "  ";
+
+/**
+Puts every element of an SVG on its own line, indented by how deeply it's nested.
+Elements that only hold text, and `<text>` elements with everything in them, like the `<tspan>` runs of
+formatted text, stay on one line so the text isn't changed. Whitespace between attributes is collapsed,
+attribute values are kept as they are.
+*/
+pub(crate) fn pretty_print(svg: &str) -> String {
+    let tokens = tokenize(svg);
+    let mut output = String::with_capacity(svg.len() + svg.len() / 4);
+    let mut depth = 0;
+    let mut i = 0;
+    while i < tokens.len() {
+        let token = tokens[i];
+        if !token.starts_with('<') {
+            // text outside an element of its own, e.g. between child elements
+            if !token.trim().is_empty() {
+                push_line(&mut output, depth, token.trim());
+            }
+            i += 1;
+            continue;
+        }
+
+        let tag = collapse_whitespace(token);
+        if tag.starts_with("</") {
+            depth = depth.saturating_sub(1);
+            push_line(&mut output, depth, &tag);
+        } else if tag.ends_with("/>") || tag.starts_with("<?") || tag.starts_with("<!") {
+            push_line(&mut output, depth, &tag);
+        } else if element_name(&tag) == "text" {
+            // a line break between runs would show as a space
+            let mut line = tag;
+            i += 1;
+            while let Some(token) = tokens.get(i) {
+                i += 1;
+                if token.starts_with('<') {
+                    line.push_str(&collapse_whitespace(token));
+                    if element_name(token) == "/text" {
+                        break;
+                    }
+                } else {
+                    line.push_str(token);
+                }
+            }
+            push_line(&mut output, depth, &line);
+            continue;
+        } else if let (Some(text), Some(close)) = (tokens.get(i + 1), tokens.get(i + 2)) {
+            if !text.starts_with('<') && close.starts_with("</") {
+                push_line(&mut output, depth, &format!("{}{}{}", tag, text, close));
+                i += 3;
+                continue;
+            }
+            push_line(&mut output, depth, &tag);
+            depth += 1;
+        } else {
+            push_line(&mut output, depth, &tag);
+            depth += 1;
+        }
+        i += 1;
+    }
+    output
+}
+
+/// Splits the SVG into tags and the text between them.
+fn tokenize(svg: &str) -> Vec<&str> {
+    let mut tokens = vec![];
+    let mut rest = svg;
+    while !rest.is_empty() {
+        let end = if rest.starts_with('<') {
+            rest.find('>').map_or(rest.len(), |end| end + 1)
+        } else {
+            rest.find('<').unwrap_or(rest.len())
+        };
+        tokens.push(&rest[..end]);
+        rest = &rest[end..];
+    }
+    tokens
+}
+
+/// The name of the element a tag opens, or of the one it closes with a leading `/`.
+fn element_name(tag: &str) -> &str {
+    let name = tag.trim_start_matches('<');
+    name.split(|c: char| c.is_whitespace() || c == '>').next().unwrap_or(name)
+}
+
+/// Collapses the whitespace between a tag's name and attributes into single spaces, outside of quoted values.
+fn collapse_whitespace(tag: &str) -> String {
+    let mut collapsed = String::with_capacity(tag.len());
+    let mut quote = None;
+    let mut space = false;
+    for c in tag.chars() {
+        match quote {
+            Some(open) => {
+                collapsed.push(c);
+                if c == open {
+                    quote = None;
+                }
+            }
+            None if c.is_whitespace() => space = !collapsed.is_empty(),
+            None => {
+                if space {
+                    collapsed.push(' ');
+                    space = false;
+                }
+                if c == '"' || c == '\'' {
+                    quote = Some(c);
+                }
+                collapsed.push(c);
+            }
+        }
+    }
+    collapsed
+}
+
+fn push_line(output: &mut String, depth: usize, line: &str) {
+    for _ in 0..depth {
+        output.push_str(INDENT);
+    }
+    output.push_str(line);
+    output.push('\n');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pretty_print() {
+        let svg = "<svg a=\"1\"\n      b=\"2\"><g><text x=\"0\">a  b</text><line /></g></svg>";
+        assert_eq!(
+            pretty_print(svg),
+            "<svg a=\"1\" b=\"2\">\n  <g>\n    <text x=\"0\">a  b</text>\n    <line />\n  </g>\n</svg>\n"
+        );
+
+        let svg = r#"<g><text x="0"><tspan text-decoration="underline">A</tspan>°</text></g>"#;
+        assert_eq!(pretty_print(svg), "<g>\n  <text x=\"0\"><tspan text-decoration=\"underline\">A</tspan>°</text>\n</g>\n");
+
+        let svg = "<g data-layer=\"A  B\"\n   title='x\ty'><line /></g>";
+        assert_eq!(pretty_print(svg), "<g data-layer=\"A  B\" title='x\ty'>\n  <line />\n</g>\n");
+    }
+}
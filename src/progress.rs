@@ -0,0 +1,56 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// How many entities are rendered between two calls to `on_progress`
+pub(crate) const PROGRESS_INTERVAL: usize = 100;
+
+/// Called with how many of the entities are rendered and how many there are in total, from whichever thread
+/// rendered the last of them.
+pub type ProgressCallback = Box<dyn FnMut(usize, usize) + Send + Sync>;
+
+/// Counts the entities rendered so far, passing the count on to the `on_progress` callback, and tells if the
+/// conversion was cancelled.
+pub(crate) struct Progress {
+    done: AtomicUsize,
+    total: usize,
+    on_progress: Option<Mutex<ProgressCallback>>,
+    cancel: Option<Arc<AtomicBool>>,
+}
+
+impl Progress {
+    pub(crate) fn new(total: usize, on_progress: Option<ProgressCallback>, cancel: Option<Arc<AtomicBool>>) -> Self {
+        Progress { done: AtomicUsize::new(0), total, on_progress: on_progress.map(Mutex::new), cancel }
+    }
+
+    /// True once the `cancel` flag is set.
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.cancel.as_ref().is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+    }
+
+    /// Counts an entity as rendered, calling `on_progress` every `PROGRESS_INTERVAL` entities and after the last.
+    pub(crate) fn step(&self) {
+        let done = self.done.fetch_add(1, Ordering::Relaxed) + 1;
+        if let Some(on_progress) = &self.on_progress {
+            if done.is_multiple_of(PROGRESS_INTERVAL) || done == self.total {
+                (on_progress.lock().unwrap())(done, self.total);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_progress() {
+        let calls = Arc::new(Mutex::new(vec![]));
+        let sink = calls.clone();
+        let progress = Progress::new(250, Some(Box::new(move |done, total| sink.lock().unwrap().push((done, total)))), None);
+        for _ in 0..250 {
+            progress.step();
+        }
+        assert_eq!(*calls.lock().unwrap(), [(100, 250), (200, 250), (250, 250)]);
+    }
+}
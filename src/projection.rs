@@ -0,0 +1,538 @@
+use std::borrow::Cow;
+
+use dxf::entities::{DimensionBase, Ellipse, Entity, EntityType};
+use dxf::{Point, Vector};
+
+use crate::geometry::EllipseShape;
+use crate::ocs::Ocs;
+use crate::polyline;
+
+/**
+The direction 3D drawings are viewed from. Every coordinate is run through the view transform
+before being drawn, so the output's x and y are the view's horizontal and vertical.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Projection {
+    /// Looking down the Z axis, the plan view every 2D drawing is made in.
+    #[default]
+    Top,
+    /// Looking along the Y axis, with Z pointing up.
+    Front,
+    /// Looking from the right, against the X axis, with Z pointing up.
+    Side,
+    /// The south-east isometric view, looking from (1, -1, 1).
+    Isometric,
+    /**
+    A row-major matrix applied to `(x, y, z, 1)`. The first two rows give the output's x and y.
+    Points are divided by the resulting w, but circles and other curves only use the matrix's
+    upper-left 3x3 part, so perspective matrices only move their end points.
+    */
+    Matrix([[f64; 4]; 4]),
+}
+
+impl Projection {
+    /// The view transform as a row-major 4x4 matrix.
+    pub fn matrix(&self) -> [[f64; 4]; 4] {
+        let (s2, s3, s6) = (2f64.sqrt(), 3f64.sqrt(), 6f64.sqrt());
+        match self {
+            Projection::Top => [[1.0, 0.0, 0.0, 0.0], [0.0, 1.0, 0.0, 0.0], [0.0, 0.0, 1.0, 0.0], [0.0, 0.0, 0.0, 1.0]],
+            Projection::Front => [[1.0, 0.0, 0.0, 0.0], [0.0, 0.0, 1.0, 0.0], [0.0, -1.0, 0.0, 0.0], [0.0, 0.0, 0.0, 1.0]],
+            Projection::Side => [[0.0, 1.0, 0.0, 0.0], [0.0, 0.0, 1.0, 0.0], [1.0, 0.0, 0.0, 0.0], [0.0, 0.0, 0.0, 1.0]],
+            Projection::Isometric => [
+                [1.0 / s2, 1.0 / s2, 0.0, 0.0],
+                [-1.0 / s6, 1.0 / s6, 2.0 / s6, 0.0],
+                [1.0 / s3, -1.0 / s3, 1.0 / s3, 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+            Projection::Matrix(matrix) => *matrix,
+        }
+    }
+
+    /// Where a point in world coordinates ends up in the view, then through the `transform` if there is one.
+    pub(crate) fn point(&self, point: &Point, transform: Option<&PointTransformFn>) -> Point {
+        View { matrix: self.matrix(), transform }.point(point)
+    }
+}
+
+/// A user supplied function moving points after the projection, see `SvgOptions::point_transform`.
+pub(crate) type PointTransformFn = dyn Fn(f64, f64, f64) -> (f64, f64) + Send + Sync;
+
+/**
+A view transform ready to apply to points and directions: the projection's matrix, then the user's
+point transform if there is one. Points go through the transform exactly. Directions, angles and sizes
+only have a meaning near a point, so they go through the transform's derivative there, which is exact
+when the transform is affine, like a unit conversion or georeferencing.
+*/
+struct View<'a> {
+    matrix: [[f64; 4]; 4],
+    transform: Option<&'a PointTransformFn>,
+}
+
+impl View<'_> {
+    fn point(&self, point: &Point) -> Point {
+        let point = self.projected(point);
+        match self.transform {
+            Some(transform) => {
+                let (x, y) = transform(point.x, point.y, point.z);
+                Point::new(x, y, point.z)
+            }
+            None => point,
+        }
+    }
+
+    /// A point through the projection's matrix only.
+    fn projected(&self, point: &Point) -> Point {
+        let m = &self.matrix;
+        let row = |r: &[f64; 4]| r[0] * point.x + r[1] * point.y + r[2] * point.z + r[3];
+        let w = row(&m[3]);
+        let w = if w == 0.0 { 1.0 } else { w };
+        Point::new(row(&m[0]) / w, row(&m[1]) / w, row(&m[2]) / w)
+    }
+
+    /// A direction starting `at` a point, in world coordinates.
+    fn vector(&self, at: &Point, (x, y, z): (f64, f64, f64)) -> (f64, f64, f64) {
+        let row = |r: &[f64; 4]| r[0] * x + r[1] * y + r[2] * z;
+        let (x, y, z) = (row(&self.matrix[0]), row(&self.matrix[1]), row(&self.matrix[2]));
+        let Some(transform) = self.transform else {
+            return (x, y, z);
+        };
+        let length = (x * x + y * y + z * z).sqrt();
+        if length == 0.0 {
+            return (0.0, 0.0, z);
+        }
+        // a central difference along the direction, with a step small next to it but not lost in the coordinates
+        let p = self.projected(at);
+        let step = (1e-3 * length).max(1e-7 * (1.0 + p.x.abs().max(p.y.abs()).max(p.z.abs()))) / length;
+        let (x1, y1) = transform(p.x + x * step, p.y + y * step, p.z + z * step);
+        let (x0, y0) = transform(p.x - x * step, p.y - y * step, p.z - z * step);
+        ((x1 - x0) / (2.0 * step), (y1 - y0) / (2.0 * step), z)
+    }
+
+    /// The direction, in degrees, an angle in the XY plane at a point ends up pointing in.
+    fn angle(&self, at: &Point, degrees: f64) -> f64 {
+        let (x, y, _) = self.vector(at, (degrees.to_radians().cos(), degrees.to_radians().sin(), 0.0));
+        y.atan2(x).to_degrees()
+    }
+
+    /// How much the point transform scales lengths in the XY plane at a point, on average, for text heights and the like.
+    fn scale(&self, at: &Point) -> f64 {
+        if self.transform.is_none() {
+            return 1.0;
+        }
+        let area = |view: &View| {
+            let (ax, ay, _) = view.vector(at, (1.0, 0.0, 0.0));
+            let (bx, by, _) = view.vector(at, (0.0, 1.0, 0.0));
+            (ax * by - ay * bx).abs()
+        };
+        let projected = area(&View { transform: None, ..*self });
+        if projected == 0.0 {
+            1.0
+        } else {
+            (area(self) / projected).sqrt()
+        }
+    }
+
+    /// True if the XY plane at a point is seen from below, which mirrors everything drawn in it.
+    fn is_mirrored(&self, at: &Point) -> bool {
+        let (ax, ay, _) = self.vector(at, (1.0, 0.0, 0.0));
+        let (bx, by, _) = self.vector(at, (0.0, 1.0, 0.0));
+        ax * by - ay * bx < 0.0
+    }
+
+    fn points<'a>(&self, points: impl IntoIterator<Item = &'a mut Point>) {
+        for point in points {
+            *point = self.point(point);
+        }
+    }
+
+    /// The U and V vectors of an image or wipeout placed at a point.
+    fn axes(&self, at: &Point, u: &Vector, v: &Vector) -> (Vector, Vector) {
+        let (ux, uy, uz) = self.vector(at, (u.x, u.y, u.z));
+        let (vx, vy, vz) = self.vector(at, (v.x, v.y, v.z));
+        (Vector::new(ux, uy, uz), Vector::new(vx, vy, vz))
+    }
+
+    /// The points and angles every kind of dimension has. A text position of 0,0 means there's none, so it stays.
+    fn dimension_base(&self, base: &mut DimensionBase) {
+        let at = base.definition_point_1.clone();
+        if base.text_rotation_angle != 0.0 {
+            base.text_rotation_angle = self.angle(&at, base.text_rotation_angle);
+        }
+        base.horizontal_direction_angle = self.angle(&at, base.horizontal_direction_angle);
+        let text = &base.text_mid_point;
+        if (text.x, text.y) != (0.0, 0.0) {
+            base.text_mid_point = self.point(text);
+        }
+        base.definition_point_1 = self.point(&at);
+        base.normal = Vector::z_axis();
+    }
+
+    /**
+    The ellipse `center + a cos t + b sin t` seen through the view, for t from `start` to `end` (in radians).
+    The projected axes are usually no longer perpendicular, so the true axes are found and the
+    parameters shifted to match.
+    */
+    fn ellipse(&self, center: &Point, a: (f64, f64, f64), b: (f64, f64, f64), start: f64, end: f64) -> Ellipse {
+        let (a, b) = (self.vector(center, a), self.vector(center, b));
+        let center = self.point(center);
+        let axes = EllipseShape { center: (center.x, center.y), major_axis: (a.0, a.1), minor_axis: (b.0, b.1) }.axes();
+        let rotation = axes.rotation.to_radians();
+        Ellipse {
+            center,
+            major_axis: Vector::new(axes.radius_x * rotation.cos(), axes.radius_x * rotation.sin(), 0.0),
+            // a normal pointing away from the viewer makes the ellipse run clockwise
+            normal: Vector::new(0.0, 0.0, if axes.counterclockwise { 1.0 } else { -1.0 }),
+            minor_axis_ratio: if axes.radius_x == 0.0 { 0.0 } else { axes.radius_y / axes.radius_x },
+            start_parameter: start - axes.parameter_offset,
+            end_parameter: end - axes.parameter_offset,
+        }
+    }
+}
+
+/// True if the projection and point transform leave every point where it is.
+pub(crate) fn is_identity(projection: &Projection, transform: Option<&PointTransformFn>) -> bool {
+    *projection == Projection::Top && transform.is_none()
+}
+
+/**
+Returns the entity as seen through the projection, then moved by the point `transform` if there is one.
+Entities must already be in world coordinates. Circles and arcs become the ellipses they look like from
+the chosen direction. Block contents are drawn in their own top view, only the insertion point,
+direction and scale of an insert are projected. None for proxy entities, whose graphics can't be moved.
+*/
+pub(crate) fn project<'a>(
+    entity: &'a Entity,
+    projection: &Projection,
+    transform: Option<&PointTransformFn>,
+) -> Option<Cow<'a, Entity>> {
+    if is_identity(projection, transform) {
+        return Some(Cow::Borrowed(entity));
+    }
+    let view = View { matrix: projection.matrix(), transform };
+
+    let mut entity = entity.clone();
+    let specific = match entity.specific {
+        EntityType::Line(mut line) => {
+            view.points([&mut line.p1, &mut line.p2]);
+            EntityType::Line(line)
+        }
+        EntityType::Circle(circle) => {
+            let r = circle.radius;
+            EntityType::Ellipse(view.ellipse(&circle.center, (r, 0.0, 0.0), (0.0, r, 0.0), 0.0, 2.0 * std::f64::consts::PI))
+        }
+        EntityType::Arc(arc) => {
+            let r = arc.radius;
+            let (start, end) = (arc.start_angle.to_radians(), arc.end_angle.to_radians());
+            EntityType::Ellipse(view.ellipse(&arc.center, (r, 0.0, 0.0), (0.0, r, 0.0), start, end))
+        }
+        EntityType::Ellipse(ellipse) => {
+            let n = &ellipse.normal;
+            let length = (n.x * n.x + n.y * n.y + n.z * n.z).sqrt();
+            let (nx, ny, nz) = if length == 0.0 { (0.0, 0.0, 1.0) } else { (n.x / length, n.y / length, n.z / length) };
+            let m = &ellipse.major_axis;
+            let ratio = ellipse.minor_axis_ratio;
+            let minor = ((ny * m.z - nz * m.y) * ratio, (nz * m.x - nx * m.z) * ratio, (nx * m.y - ny * m.x) * ratio);
+            EntityType::Ellipse(view.ellipse(
+                &ellipse.center,
+                (m.x, m.y, m.z),
+                minor,
+                ellipse.start_parameter,
+                ellipse.end_parameter,
+            ))
+        }
+        EntityType::LwPolyline(mut lwpolyline) => {
+            let ocs = Ocs::new(&lwpolyline.extrusion_direction);
+            let first = lwpolyline.vertices.first().map_or(Point::origin(), |vertex| Point::new(vertex.x, vertex.y, 0.0));
+            let mirrored = ocs.is_some_and(|ocs| ocs.is_mirrored()) != view.is_mirrored(&first);
+            for vertex in &mut lwpolyline.vertices {
+                let (x, y, z) = match &ocs {
+                    Some(ocs) => ocs.world_point(vertex.x, vertex.y, lwpolyline.elevation),
+                    None => (vertex.x, vertex.y, lwpolyline.elevation),
+                };
+                let point = view.point(&Point::new(x, y, z));
+                (vertex.x, vertex.y) = (point.x, point.y);
+                if mirrored {
+                    vertex.bulge = -vertex.bulge;
+                }
+            }
+            lwpolyline.extrusion_direction = Vector::z_axis();
+            lwpolyline.elevation = 0.0;
+            EntityType::LwPolyline(lwpolyline)
+        }
+        EntityType::Polyline(mut polyline) => {
+            let ocs = if polyline::is_3d(&polyline) { None } else { Ocs::new(&polyline.normal) };
+            let first = polyline.vertices().next().map_or(Point::origin(), |vertex| vertex.location.clone());
+            let mirrored = ocs.is_some_and(|ocs| ocs.is_mirrored()) != view.is_mirrored(&first);
+            for vertex in polyline.vertices_mut() {
+                let location = &vertex.location;
+                let (x, y, z) = match &ocs {
+                    Some(ocs) => ocs.world_point(location.x, location.y, location.z),
+                    None => (location.x, location.y, location.z),
+                };
+                vertex.location = view.point(&Point::new(x, y, z));
+                if mirrored {
+                    vertex.bulge = -vertex.bulge;
+                }
+            }
+            polyline.normal = Vector::z_axis();
+            EntityType::Polyline(polyline)
+        }
+        EntityType::Spline(mut spline) => {
+            view.points(spline.control_points.iter_mut().chain(spline.fit_points.iter_mut()));
+            EntityType::Spline(spline)
+        }
+        EntityType::Text(mut text) => {
+            text.rotation = view.angle(&text.location, text.rotation);
+            text.text_height *= view.scale(&text.location);
+            view.points([&mut text.location, &mut text.second_alignment_point]);
+            EntityType::Text(text)
+        }
+        EntityType::MText(mut mtext) => {
+            let at = mtext.insertion_point.clone();
+            let direction = &mtext.x_axis_direction;
+            if direction.x != 0.0 || direction.y != 0.0 {
+                let (x, y, z) = view.vector(&at, (direction.x, direction.y, direction.z));
+                mtext.x_axis_direction = Vector::new(x, y, z);
+            } else {
+                mtext.rotation_angle = view.angle(&at, mtext.rotation_angle);
+            }
+            let scale = view.scale(&at);
+            mtext.initial_text_height *= scale;
+            mtext.reference_rectangle_width *= scale;
+            view.points([&mut mtext.insertion_point]);
+            EntityType::MText(mtext)
+        }
+        EntityType::Ray(mut ray) => {
+            let direction = &ray.unit_direction_vector;
+            let (x, y, z) = view.vector(&ray.start_point, (direction.x, direction.y, direction.z));
+            ray.unit_direction_vector = Vector::new(x, y, z);
+            view.points([&mut ray.start_point]);
+            EntityType::Ray(ray)
+        }
+        EntityType::XLine(mut xline) => {
+            let direction = &xline.unit_direction_vector;
+            let (x, y, z) = view.vector(&xline.first_point, (direction.x, direction.y, direction.z));
+            xline.unit_direction_vector = Vector::new(x, y, z);
+            view.points([&mut xline.first_point]);
+            EntityType::XLine(xline)
+        }
+        EntityType::ModelPoint(mut point) => {
+            view.points([&mut point.location]);
+            EntityType::ModelPoint(point)
+        }
+        EntityType::Face3D(mut face) => {
+            view.points([&mut face.first_corner, &mut face.second_corner, &mut face.third_corner, &mut face.fourth_corner]);
+            EntityType::Face3D(face)
+        }
+        EntityType::Solid(mut solid) => {
+            view.points([&mut solid.first_corner, &mut solid.second_corner, &mut solid.third_corner, &mut solid.fourth_corner]);
+            EntityType::Solid(solid)
+        }
+        EntityType::Trace(mut trace) => {
+            view.points([&mut trace.first_corner, &mut trace.second_corner, &mut trace.third_corner, &mut trace.fourth_corner]);
+            EntityType::Trace(trace)
+        }
+        EntityType::Leader(mut leader) => {
+            view.points(leader.vertices.iter_mut());
+            EntityType::Leader(leader)
+        }
+        EntityType::Helix(mut helix) => {
+            let axis = &helix.axis_vector;
+            let (x, y, z) = view.vector(&helix.axis_base_point, (axis.x, axis.y, axis.z));
+            helix.axis_vector = Vector::new(x, y, z);
+            let scale = view.scale(&helix.axis_base_point);
+            helix.radius *= scale;
+            helix.turn_height *= scale;
+            view.points([&mut helix.axis_base_point, &mut helix.start_point]);
+            EntityType::Helix(helix)
+        }
+        EntityType::Shape(mut shape) => {
+            shape.rotation_angle = view.angle(&shape.location, shape.rotation_angle);
+            shape.size *= view.scale(&shape.location);
+            view.points([&mut shape.location]);
+            EntityType::Shape(shape)
+        }
+        EntityType::Insert(mut insert) => {
+            let at = insert.location.clone();
+            insert.rotation = view.angle(&at, insert.rotation);
+            let scale = view.scale(&at);
+            insert.x_scale_factor *= scale;
+            insert.y_scale_factor *= scale;
+            insert.column_spacing *= scale;
+            insert.row_spacing *= scale;
+            view.points([&mut insert.location]);
+            if view.is_mirrored(&at) {
+                insert.y_scale_factor = -insert.y_scale_factor;
+                insert.row_spacing = -insert.row_spacing;
+            }
+            EntityType::Insert(insert)
+        }
+        EntityType::RotatedDimension(mut dimension) => {
+            dimension.rotation_angle = view.angle(&dimension.definition_point_2, dimension.rotation_angle);
+            if dimension.extension_line_angle != 0.0 {
+                dimension.extension_line_angle = view.angle(&dimension.definition_point_2, dimension.extension_line_angle);
+            }
+            view.dimension_base(&mut dimension.dimension_base);
+            view.points([&mut dimension.insertion_point, &mut dimension.definition_point_2, &mut dimension.definition_point_3]);
+            EntityType::RotatedDimension(dimension)
+        }
+        EntityType::AlignedDimension(mut dimension) => {
+            view.dimension_base(&mut dimension.dimension_base);
+            view.points([&mut dimension.insertion_point, &mut dimension.definition_point_2, &mut dimension.definition_point_3]);
+            EntityType::AlignedDimension(dimension)
+        }
+        EntityType::RadialDimension(mut dimension) => {
+            dimension.leader_length *= view.scale(&dimension.definition_point_2);
+            view.dimension_base(&mut dimension.dimension_base);
+            view.points([&mut dimension.definition_point_2]);
+            EntityType::RadialDimension(dimension)
+        }
+        EntityType::DiameterDimension(mut dimension) => {
+            dimension.leader_length *= view.scale(&dimension.definition_point_2);
+            view.dimension_base(&mut dimension.dimension_base);
+            view.points([&mut dimension.definition_point_2]);
+            EntityType::DiameterDimension(dimension)
+        }
+        EntityType::AngularThreePointDimension(mut dimension) => {
+            view.dimension_base(&mut dimension.dimension_base);
+            view.points([
+                &mut dimension.definition_point_2,
+                &mut dimension.definition_point_3,
+                &mut dimension.definition_point_4,
+                &mut dimension.definition_point_5,
+            ]);
+            EntityType::AngularThreePointDimension(dimension)
+        }
+        EntityType::OrdinateDimension(mut dimension) => {
+            view.dimension_base(&mut dimension.dimension_base);
+            view.points([&mut dimension.definition_point_2, &mut dimension.definition_point_3]);
+            EntityType::OrdinateDimension(dimension)
+        }
+        EntityType::MLine(mut mline) => {
+            if let Some(first) = mline.vertices.first() {
+                mline.scale_factor *= view.scale(first);
+            }
+            for (i, vertex) in mline.vertices.iter().enumerate() {
+                for direction in [mline.segment_directions.get_mut(i), mline.miter_directions.get_mut(i)].into_iter().flatten() {
+                    let (x, y, z) = view.vector(vertex, (direction.x, direction.y, direction.z));
+                    *direction = Vector::new(x, y, z);
+                }
+            }
+            view.points(mline.vertices.iter_mut().chain([&mut mline.start_point]));
+            mline.normal = Vector::z_axis();
+            EntityType::MLine(mline)
+        }
+        EntityType::Tolerance(mut tolerance) => {
+            let direction = &tolerance.direction_vector;
+            let (x, y, z) = view.vector(&tolerance.insertion_point, (direction.x, direction.y, direction.z));
+            tolerance.direction_vector = Vector::new(x, y, z);
+            tolerance.extrusion_direction = Vector::z_axis();
+            view.points([&mut tolerance.insertion_point]);
+            EntityType::Tolerance(tolerance)
+        }
+        EntityType::Image(mut image) => {
+            (image.u_vector, image.v_vector) = view.axes(&image.location, &image.u_vector, &image.v_vector);
+            view.points([&mut image.location]);
+            EntityType::Image(image)
+        }
+        EntityType::Wipeout(mut wipeout) => {
+            (wipeout.u_vector, wipeout.v_vector) = view.axes(&wipeout.location, &wipeout.u_vector, &wipeout.v_vector);
+            view.points([&mut wipeout.location]);
+            EntityType::Wipeout(wipeout)
+        }
+        EntityType::ProxyEntity(_) => return None,
+        specific => specific,
+    };
+    entity.specific = specific;
+    Some(Cow::Owned(entity))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dxf::entities::{Circle, Line};
+
+    #[test]
+    fn test_front_projection() {
+        let line = Entity::new(EntityType::Line(Line::new(Point::new(1.0, 2.0, 3.0), Point::new(4.0, 5.0, 6.0))));
+        let EntityType::Line(line) = &project(&line, &Projection::Front, None).unwrap().specific else {
+            panic!("the line should stay a line");
+        };
+        assert_eq!((line.p1.x, line.p1.y), (1.0, 3.0));
+        assert_eq!((line.p2.x, line.p2.y), (4.0, 6.0));
+
+        // a circle in the XY plane is seen edge-on from the front
+        let circle = Entity::new(EntityType::Circle(Circle::new(Point::new(0.0, 0.0, 0.0), 2.0)));
+        let EntityType::Ellipse(ellipse) = &project(&circle, &Projection::Front, None).unwrap().specific else {
+            panic!("the circle should become an ellipse");
+        };
+        assert!((ellipse.major_axis.x.hypot(ellipse.major_axis.y) - 2.0).abs() < 1e-9);
+        assert!(ellipse.minor_axis_ratio.abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_isometric_projection() {
+        let circle = Entity::new(EntityType::Circle(Circle::new(Point::new(0.0, 0.0, 0.0), 1.0)));
+        let EntityType::Ellipse(ellipse) = &project(&circle, &Projection::Isometric, None).unwrap().specific else {
+            panic!("the circle should become an ellipse");
+        };
+        // an isometric circle is an ellipse with a horizontal major axis and a ratio of 1/sqrt(3)
+        assert!((ellipse.major_axis.x.abs() - 1.0).abs() < 1e-9);
+        assert!((ellipse.minor_axis_ratio - 1.0 / 3f64.sqrt()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_point_transform() {
+        // millimetres to metres, moved to a site origin
+        let transform = |x: f64, y: f64, _: f64| (1000.0 + x / 1000.0, 2000.0 + y / 1000.0);
+        let line = Entity::new(EntityType::Line(Line::new(Point::new(0.0, 0.0, 0.0), Point::new(500.0, 0.0, 0.0))));
+        let EntityType::Line(line) = &project(&line, &Projection::Top, Some(&transform)).unwrap().specific else {
+            panic!("the line should stay a line");
+        };
+        assert_eq!((line.p1.x, line.p1.y), (1000.0, 2000.0));
+        assert_eq!((line.p2.x, line.p2.y), (1000.5, 2000.0));
+
+        let circle = Entity::new(EntityType::Circle(Circle::new(Point::new(0.0, 0.0, 0.0), 2000.0)));
+        let EntityType::Ellipse(ellipse) = &project(&circle, &Projection::Top, Some(&transform)).unwrap().specific else {
+            panic!("the circle should become an ellipse");
+        };
+        assert!((ellipse.major_axis.x.hypot(ellipse.major_axis.y) - 2.0).abs() < 1e-6);
+        assert!((ellipse.minor_axis_ratio - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_transform_annotations() {
+        // millimetres to metres, turned a quarter turn counterclockwise
+        let transform = |x: f64, y: f64, _: f64| (-y / 1000.0, x / 1000.0);
+        let mut dimension = dxf::entities::RotatedDimension::default();
+        dimension.dimension_base.definition_point_1 = Point::new(0.0, 500.0, 0.0);
+        dimension.definition_point_2 = Point::new(0.0, 0.0, 0.0);
+        dimension.definition_point_3 = Point::new(1000.0, 0.0, 0.0);
+        let dimension = Entity::new(EntityType::RotatedDimension(dimension));
+        let EntityType::RotatedDimension(dimension) = &project(&dimension, &Projection::Top, Some(&transform)).unwrap().specific else {
+            panic!("the dimension should stay a dimension");
+        };
+        let base = &dimension.dimension_base;
+        assert_eq!((base.definition_point_1.x, base.definition_point_1.y), (-0.5, 0.0));
+        assert_eq!((base.text_mid_point.x, base.text_mid_point.y), (0.0, 0.0));
+        assert_eq!((dimension.definition_point_3.x, dimension.definition_point_3.y), (0.0, 1.0));
+        assert!((dimension.rotation_angle - 90.0).abs() < 1e-6);
+
+        let wipeout = Entity::new(EntityType::Wipeout(dxf::entities::Wipeout {
+            location: Point::new(1000.0, 0.0, 0.0),
+            u_vector: Vector::new(10.0, 0.0, 0.0),
+            v_vector: Vector::new(0.0, 10.0, 0.0),
+            ..Default::default()
+        }));
+        let EntityType::Wipeout(wipeout) = &project(&wipeout, &Projection::Top, Some(&transform)).unwrap().specific else {
+            panic!("the wipeout should stay a wipeout");
+        };
+        assert_eq!((wipeout.location.x, wipeout.location.y), (0.0, 1.0));
+        assert!((wipeout.u_vector.y - 0.01).abs() < 1e-9 && wipeout.u_vector.x.abs() < 1e-9);
+        assert!((wipeout.v_vector.x + 0.01).abs() < 1e-9 && wipeout.v_vector.y.abs() < 1e-9);
+
+        let proxy = Entity::new(EntityType::ProxyEntity(Default::default()));
+        assert!(project(&proxy, &Projection::Top, Some(&transform)).is_none());
+        assert!(project(&proxy, &Projection::Top, None).is_some());
+    }
+}
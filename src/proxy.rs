@@ -0,0 +1,339 @@
+use std::f64::consts::PI;
+use std::fmt::Write;
+
+use dxf::entities::Text;
+use encoding_rs::Encoding;
+
+use crate::color;
+use crate::emit::Points;
+use crate::text::{self, TextStyle};
+
+/// Proxy graphics command: a circle from its center, radius and normal
+const CIRCLE: u32 = 2;
+/// Proxy graphics command: an arc from its center, radius, normal, start direction and sweep
+const CIRCULAR_ARC: u32 = 4;
+/// Proxy graphics command: an open polyline
+const POLYLINE: u32 = 6;
+/// Proxy graphics command: a closed polyline
+const POLYGON: u32 = 7;
+/// Proxy graphics command: text in the drawing's code page
+const TEXT: u32 = 10;
+/// Proxy graphics command: text in the drawing's code page with its style and flags
+const TEXT2: u32 = 11;
+/// Proxy graphics command: the ACI color of what follows
+const COLOR: u32 = 14;
+/// Proxy graphics command: the true color of what follows
+const TRUE_COLOR: u32 = 22;
+/// Proxy graphics command: an open polyline followed by its normal
+const POLYLINE_WITH_NORMALS: u32 = 32;
+/// Proxy graphics command: UTF-16 text
+const UNICODE_TEXT: u32 = 36;
+/// Proxy graphics command: UTF-16 text with its style and flags
+const UNICODE_TEXT2: u32 = 38;
+
+/// What a command of a proxy entity's graphics draws.
+#[derive(Debug, Clone)]
+pub(crate) enum Primitive {
+    Polyline { points: Vec<(f64, f64)>, closed: bool },
+    Circle { center: (f64, f64), radius: f64 },
+    /// Angles are in radians, a negative sweep runs clockwise. Sectors and chords are closed by lines.
+    Arc { center: (f64, f64), radius: f64, start: f64, sweep: f64, closure: ArcClosure },
+    Text(Text),
+}
+
+/// How the ends of a proxy arc are joined.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ArcClosure {
+    Open,
+    /// Through the center, like a slice of pie
+    Sector,
+    /// Straight from end to end
+    Chord,
+}
+
+/// A primitive of a proxy entity's graphics with the color it's drawn in, None for the entity's own.
+#[derive(Debug, Clone)]
+pub(crate) struct Graphic {
+    pub primitive: Primitive,
+    pub color: Option<String>,
+}
+
+/// Reads the little endian values of a proxy graphics command.
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+    /// the code page of 8-bit strings
+    encoding: &'static Encoding,
+}
+
+impl Reader<'_> {
+    fn bytes<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let bytes = self.data.get(self.offset..self.offset + N)?.try_into().ok()?;
+        self.offset += N;
+        Some(bytes)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.bytes().map(u32::from_le_bytes)
+    }
+
+    fn f64(&mut self) -> Option<f64> {
+        self.bytes().map(f64::from_le_bytes)
+    }
+
+    /// A 3D point, of which only X and Y are kept.
+    fn point(&mut self) -> Option<(f64, f64)> {
+        let (x, y, _) = self.vector()?;
+        Some((x, y))
+    }
+
+    fn vector(&mut self) -> Option<(f64, f64, f64)> {
+        Some((self.f64()?, self.f64()?, self.f64()?))
+    }
+
+    fn points(&mut self) -> Option<Vec<(f64, f64)>> {
+        let count = self.u32()? as usize;
+        // each point takes 24 bytes, which also guards against absurd counts
+        if count > (self.data.len() - self.offset) / 24 {
+            return None;
+        }
+        (0..count).map(|_| self.point()).collect()
+    }
+
+    /// A NUL terminated 8-bit string, padded to a multiple of 4 bytes.
+    fn string(&mut self) -> Option<String> {
+        let rest = self.data.get(self.offset..)?;
+        let length = rest.iter().position(|byte| *byte == 0)?;
+        let text = self.encoding.decode_without_bom_handling(&rest[..length]).0.into_owned();
+        self.offset += (length + 1).next_multiple_of(4);
+        Some(text)
+    }
+
+    /// A NUL terminated UTF-16 string, padded to a multiple of 4 bytes.
+    fn unicode_string(&mut self) -> Option<String> {
+        let rest = self.data.get(self.offset..)?;
+        let units: Vec<u16> = rest
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .take_while(|unit| *unit != 0)
+            .collect();
+        self.offset += ((units.len() + 1) * 2).next_multiple_of(4);
+        Some(String::from_utf16_lossy(&units))
+    }
+}
+
+/**
+Decodes the graphics AutoCAD caches in a proxy entity, the primitives an application's custom entity
+(from Civil 3D, MEP and the like) draws itself with. The stream starts with its size and command count,
+then each command gives its size and type before its data. Circles, arcs, polylines, text and colors
+are read; other commands, like meshes and clipping, are skipped. A malformed command ends the stream.
+8-bit text is decoded with `encoding`, the drawing's code page.
+*/
+pub(crate) fn parse_graphics(data: &[u8], encoding: &'static Encoding) -> Vec<Graphic> {
+    let mut graphics = vec![];
+    let mut color = None;
+    let mut offset = 8;
+    while let Some(header) = data.get(offset..offset + 8) {
+        let size = u32::from_le_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        let command = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+        let Some(command_data) = data.get(offset + 8..offset + size).filter(|_| size >= 8) else {
+            break;
+        };
+        let mut reader = Reader { data: command_data, offset: 0, encoding };
+        match command {
+            COLOR => color = reader.u32().and_then(aci_color),
+            TRUE_COLOR => color = reader.u32().and_then(true_color),
+            _ => {
+                if let Some(primitive) = read_primitive(command, &mut reader) {
+                    graphics.push(Graphic { primitive, color: color.clone() });
+                }
+            }
+        }
+        offset += size;
+    }
+    graphics
+}
+
+fn read_primitive(command: u32, reader: &mut Reader) -> Option<Primitive> {
+    match command {
+        CIRCLE => Some(Primitive::Circle { center: reader.point()?, radius: reader.f64()? }),
+        CIRCULAR_ARC => {
+            let center = reader.point()?;
+            let radius = reader.f64()?;
+            let (_, _, normal_z) = reader.vector()?;
+            let (start_x, start_y, _) = reader.vector()?;
+            let sweep = reader.f64()?;
+            let closure = match reader.u32()? {
+                1 => ArcClosure::Sector,
+                2 => ArcClosure::Chord,
+                _ => ArcClosure::Open,
+            };
+            // arcs run counterclockwise around their normal, so clockwise seen from above when it points down
+            let sweep = if normal_z < 0.0 { -sweep } else { sweep };
+            Some(Primitive::Arc { center, radius, start: start_y.atan2(start_x), sweep, closure })
+        }
+        POLYLINE | POLYLINE_WITH_NORMALS => Some(Primitive::Polyline { points: reader.points()?, closed: false }),
+        POLYGON => Some(Primitive::Polyline { points: reader.points()?, closed: true }),
+        TEXT | UNICODE_TEXT | TEXT2 | UNICODE_TEXT2 => {
+            let (x, y, _) = reader.vector()?;
+            reader.vector()?;
+            let (direction_x, direction_y, _) = reader.vector()?;
+            let mut value = None;
+            if command == TEXT2 || command == UNICODE_TEXT2 {
+                value = Some(if command == TEXT2 { reader.string()? } else { reader.unicode_string()? });
+                // the string's length and whether it's raw, without control codes
+                reader.u32()?;
+                reader.u32()?;
+            }
+            let height = reader.f64()?;
+            let width_factor = reader.f64()?;
+            let oblique_angle = reader.f64()?;
+            let value = match (value, command) {
+                (Some(value), _) => value,
+                (None, TEXT) => reader.string()?,
+                _ => reader.unicode_string()?,
+            };
+            Some(Primitive::Text(Text {
+                location: dxf::Point::new(x, y, 0.0),
+                text_height: height,
+                value,
+                rotation: direction_y.atan2(direction_x).to_degrees(),
+                relative_x_scale_factor: width_factor,
+                oblique_angle: oblique_angle.to_degrees(),
+                ..Default::default()
+            }))
+        }
+        _ => None,
+    }
+}
+
+/// An ACI color, None for ByBlock and ByLayer, which keep the entity's color.
+fn aci_color(index: u32) -> Option<String> {
+    match index {
+        1..=255 => Some(color::aci_to_hex(index as u8)),
+        _ => None,
+    }
+}
+
+/// A true color, whose top byte says whether the rest is an RGB value or an ACI index.
+fn true_color(value: u32) -> Option<String> {
+    match value >> 24 {
+        0xC2 => Some(format!("#{:06X}", value & 0xFF_FFFF)),
+        0xC3 => aci_color(value & 0xFF),
+        _ => None,
+    }
+}
+
+/// The points a graphic reaches, for the bounds.
+pub(crate) fn graphic_points(graphic: &Graphic) -> Vec<(f64, f64)> {
+    match &graphic.primitive {
+        Primitive::Polyline { points, .. } => points.clone(),
+        Primitive::Circle { center: (x, y), radius } => vec![(x - radius, y - radius), (x + radius, y + radius)],
+        Primitive::Arc { center: (x, y), radius, start, sweep, .. } => {
+            let (start, end) = if *sweep < 0.0 { (start + sweep, *start) } else { (*start, start + sweep) };
+            crate::geometry::arc_extremes(*x, *y, *radius, start, end)
+        }
+        Primitive::Text(text) => vec![(text.location.x, text.location.y)],
+    }
+}
+
+/// Renders a graphic of a proxy entity, with `color` filling its text.
+pub(crate) fn render_graphic(graphic: &Graphic, stroke_attr: &str, color: &str, precision: usize, svg: &mut String) {
+    match &graphic.primitive {
+        Primitive::Polyline { points, closed } => {
+            if points.len() < 2 {
+                return;
+            }
+            write!(
+                svg,
+                r#"<{} points="{}" {} fill="none" />"#,
+                if *closed { "polygon" } else { "polyline" },
+                Points(points, precision),
+                stroke_attr
+            ).unwrap();
+        }
+        Primitive::Circle { center: (x, y), radius } => {
+            write!(
+                svg,
+                r#"<circle cx="{:.p$}" cy="{:.p$}" r="{:.p$}" {} fill="none" />"#,
+                x, y, radius, stroke_attr, p = precision
+            ).unwrap();
+        }
+        Primitive::Arc { center, radius, start, sweep, closure } => {
+            if sweep.abs() >= 2.0 * PI {
+                let circle = Graphic { primitive: Primitive::Circle { center: *center, radius: *radius }, color: None };
+                render_graphic(&circle, stroke_attr, color, precision, svg);
+                return;
+            }
+            let point = |angle: f64| (center.0 + radius * angle.cos(), center.1 + radius * angle.sin());
+            let (start_point, end_point) = (point(*start), point(start + sweep));
+            let mut path = format!(
+                "M {:.p$},{:.p$} A {:.p$},{:.p$} 0 {} {} {:.p$},{:.p$}",
+                start_point.0,
+                start_point.1,
+                radius,
+                radius,
+                u8::from(sweep.abs() > PI),
+                u8::from(*sweep > 0.0),
+                end_point.0,
+                end_point.1,
+                p = precision
+            );
+            match closure {
+                ArcClosure::Open => {}
+                ArcClosure::Sector => write!(path, " L {:.p$},{:.p$} Z", center.0, center.1, p = precision).unwrap(),
+                ArcClosure::Chord => path.push_str(" Z"),
+            }
+            write!(svg, r#"<path d="{}" {} fill="none" />"#, path, stroke_attr).unwrap();
+        }
+        Primitive::Text(text) => text::render_text(text, &TextStyle::default(), color, precision, svg),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command(command: u32, data: &[u8]) -> Vec<u8> {
+        let mut bytes = ((data.len() + 8) as u32).to_le_bytes().to_vec();
+        bytes.extend(command.to_le_bytes());
+        bytes.extend(data);
+        bytes
+    }
+
+    fn doubles(values: &[f64]) -> Vec<u8> {
+        values.iter().flat_map(|value| value.to_le_bytes()).collect()
+    }
+
+    #[test]
+    fn test_parse_graphics() {
+        let mut polyline = 2u32.to_le_bytes().to_vec();
+        polyline.extend(doubles(&[0.0, 0.0, 0.0, 10.0, 5.0, 0.0]));
+        let mut text = doubles(&[1.0, 2.0, 0.0, 0.0, 0.0, 1.0, 0.0, 1.0, 0.0, 2.5, 1.0, 0.0]);
+        text.extend(b"R\xf6hre\0\0");
+        let commands = [
+            command(POLYLINE, &polyline),
+            command(COLOR, &1u32.to_le_bytes()),
+            command(CIRCLE, &doubles(&[3.0, 4.0, 0.0, 2.0, 0.0, 0.0, 1.0])),
+            command(TRUE_COLOR, &0xC2_00_80_FFu32.to_le_bytes()),
+            command(TEXT, &text),
+            // a command cut short ends the stream
+            vec![100, 0, 0, 0, 2, 0, 0, 0],
+        ];
+        let mut data = vec![0; 8];
+        data.extend(commands.concat());
+
+        let graphics = parse_graphics(&data, encoding_rs::WINDOWS_1252);
+        assert_eq!(graphics.len(), 3);
+        assert!(matches!(&graphics[0].primitive, Primitive::Polyline { points, closed: false } if *points == [(0.0, 0.0), (10.0, 5.0)]));
+        assert_eq!(graphics[0].color, None);
+        assert!(matches!(graphics[1].primitive, Primitive::Circle { center: (3.0, 4.0), radius: 2.0 }));
+        assert_eq!(graphics[1].color.as_deref(), Some("#FF0000"));
+        let Primitive::Text(text) = &graphics[2].primitive else {
+            panic!("expected text");
+        };
+        assert_eq!(text.value, "Röhre");
+        assert_eq!(text.rotation, 90.0);
+        assert_eq!(graphics[2].color.as_deref(), Some("#0080FF"));
+    }
+}
@@ -0,0 +1,111 @@
+use std::fmt;
+
+use dxf::entities::Entity;
+use dxf::Drawing;
+
+/// SVG markup drawing an entity, written into the output as is.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SvgFragment(pub String);
+
+impl From<String> for SvgFragment {
+    fn from(markup: String) -> Self {
+        SvgFragment(markup)
+    }
+}
+
+/**
+What a custom renderer knows about the entity it's drawing and the conversion. Coordinates are
+written as they are in the drawing (after the `projection`), inside a group that flips the Y axis,
+so `<text>` needs a `scale(1 -1)` to read upright.
+*/
+#[derive(Debug, Clone, Copy)]
+pub struct EntityContext<'a> {
+    /// The drawing the entity is from, None when converting loose entities
+    pub drawing: Option<&'a Drawing>,
+    /// Decimals to write coordinates with
+    pub precision: usize,
+    /// The entity's resolved color, by layer and by block colors included
+    pub color: &'a str,
+    /// The stroke attributes built-in elements get, like `stroke="#FF0000" stroke-width="0.1"`
+    pub stroke_attributes: &'a str,
+    /// True while rendering the contents of a block, which are written once as a `<symbol>`
+    pub in_block: bool,
+}
+
+/**
+Draws entities in place of the built-in rendering, e.g. to replace the POINT symbol or to draw entity
+types the crate doesn't cover. Returning None leaves the entity to the next renderer, or to the
+built-in rendering when no renderer takes it.
+*/
+pub trait EntityRenderer: Send + Sync {
+    fn render(&self, entity: &Entity, ctx: &EntityContext) -> Option<SvgFragment>;
+}
+
+impl<F> EntityRenderer for F
+where
+    F: Fn(&Entity, &EntityContext) -> Option<SvgFragment> + Send + Sync,
+{
+    fn render(&self, entity: &Entity, ctx: &EntityContext) -> Option<SvgFragment> {
+        self(entity, ctx)
+    }
+}
+
+/// The custom renderers of a conversion. The renderer registered last is asked first.
+#[derive(Default)]
+pub struct RendererRegistry {
+    renderers: Vec<Box<dyn EntityRenderer>>,
+}
+
+impl RendererRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a renderer, asked before the ones registered earlier.
+    pub fn register<R: EntityRenderer + 'static>(&mut self, renderer: R) {
+        self.renderers.push(Box::new(renderer));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.renderers.is_empty()
+    }
+
+    /// The markup of the first renderer that draws the entity, latest first.
+    pub(crate) fn render(&self, entity: &Entity, ctx: &EntityContext) -> Option<SvgFragment> {
+        self.renderers.iter().rev().find_map(|renderer| renderer.render(entity, ctx))
+    }
+}
+
+impl fmt::Debug for RendererRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RendererRegistry").field("renderers", &self.renderers.len()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dxf::entities::{EntityType, Line, ModelPoint};
+    use dxf::Point;
+
+    #[test]
+    fn test_registry_order() {
+        let mut registry = RendererRegistry::new();
+        registry.register(|_: &Entity, _: &EntityContext| Some(SvgFragment::from("<first />".to_string())));
+        registry.register(|entity: &Entity, ctx: &EntityContext| match &entity.specific {
+            EntityType::ModelPoint(point) => Some(SvgFragment(format!(
+                r##"<use href="#pin" x="{:.p$}" y="{:.p$}" />"##,
+                point.location.x,
+                point.location.y,
+                p = ctx.precision
+            ))),
+            _ => None,
+        });
+        let ctx = EntityContext { drawing: None, precision: 1, color: "black", stroke_attributes: "", in_block: false };
+
+        let point = Entity::new(EntityType::ModelPoint(ModelPoint { location: Point::new(1.0, 2.0, 0.0), ..Default::default() }));
+        assert_eq!(registry.render(&point, &ctx), Some(SvgFragment(r##"<use href="#pin" x="1.0" y="2.0" />"##.to_string())));
+        let line = Entity::new(EntityType::Line(Line::default()));
+        assert_eq!(registry.render(&line, &ctx), Some(SvgFragment("<first />".to_string())));
+    }
+}
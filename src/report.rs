@@ -0,0 +1,232 @@
+use std::sync::Mutex;
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::Bounds;
+
+/// Something the conversion couldn't do as the drawing asked.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Warning {
+    /// An entity of a type the converter can't draw was skipped
+    Unsupported { handle: u64, entity_type: &'static str },
+    /// A block inserts itself, directly or through other blocks, so the insert closing the loop was skipped
+    RecursiveInsert { block: String, parent: String },
+    /// An INSERT refers to a block the drawing doesn't define, so nothing was drawn for it
+    MissingBlock { handle: u64, block: String },
+    /// An entity's geometry is too small or malformed to draw, like an image without a size
+    Degenerate { handle: u64, entity_type: &'static str, reason: &'static str },
+    /// An entity holds NaN, infinite or absurdly large coordinates, as corrupt files sometimes do, so it was skipped
+    InvalidCoordinates { handle: u64, entity_type: &'static str },
+    /// Nothing in the drawing has any extent, so a default view was shown
+    EmptyDrawing,
+    /// An entity can't go through the `projection` or `point_transform`, like a proxy entity's graphics, so it was skipped
+    Untransformable { handle: u64, entity_type: &'static str },
+}
+
+impl Warning {
+    /// The handle of the entity the warning is about, if it's about one.
+    pub fn handle(&self) -> Option<u64> {
+        match self {
+            Warning::Unsupported { handle, .. }
+            | Warning::MissingBlock { handle, .. }
+            | Warning::Degenerate { handle, .. }
+            | Warning::InvalidCoordinates { handle, .. }
+            | Warning::Untransformable { handle, .. } => Some(*handle),
+            Warning::RecursiveInsert { .. } | Warning::EmptyDrawing => None,
+        }
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Warning::Unsupported { handle, entity_type } => {
+                write!(f, "skipped unsupported {} entity {:X}", entity_type, handle)
+            }
+            Warning::RecursiveInsert { block, parent } => {
+                write!(f, "skipped recursive insert of block {:?} in block {:?}", block, parent)
+            }
+            Warning::MissingBlock { handle, block } => write!(f, "insert {:X} refers to missing block {:?}", handle, block),
+            Warning::Degenerate { handle, entity_type, reason } => write!(f, "{} {:X} {}", entity_type, handle, reason),
+            Warning::InvalidCoordinates { handle, entity_type } => {
+                write!(f, "skipped {} entity {:X} with invalid coordinates", entity_type, handle)
+            }
+            Warning::EmptyDrawing => write!(f, "nothing in the drawing has any extent"),
+            Warning::Untransformable { handle, entity_type } => {
+                write!(f, "skipped {} entity {:X} that can't be projected or transformed", entity_type, handle)
+            }
+        }
+    }
+}
+
+/**
+What happened during a conversion, returned alongside the SVG by the `*_with_report` functions.
+Batch pipelines can use its statistics to flag suspicious conversions, like one skipping most entities.
+*/
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConversionReport {
+    /// Every warning, in the order they came up
+    pub warnings: Vec<Warning>,
+    /// How many entities of each type were converted, including the contents of each block once, by DXF type name
+    pub entities: BTreeMap<&'static str, usize>,
+    /// How many entities of each type were skipped, as unsupported, degenerate or with invalid coordinates, by DXF type name
+    pub skipped: BTreeMap<&'static str, usize>,
+    /// The area the SVG shows in drawing units, padding included
+    pub bounds: Option<Bounds>,
+    /// The length of the SVG in bytes
+    pub output_bytes: usize,
+    /// True if the `cancel` flag was set, leaving out the entities not rendered by then
+    pub cancelled: bool,
+}
+
+impl ConversionReport {
+    /// How many entities were converted in total, drawn or skipped.
+    pub fn entity_count(&self) -> usize {
+        self.entities.values().sum()
+    }
+
+    /// How many entities were drawn.
+    pub fn rendered_count(&self) -> usize {
+        self.entity_count().saturating_sub(self.skipped_count())
+    }
+
+    /// How many entities were skipped in total.
+    pub fn skipped_count(&self) -> usize {
+        self.skipped.values().sum()
+    }
+
+    /// The share of the entities that were skipped, from 0 to 1. 0 when there were no entities.
+    pub fn skipped_ratio(&self) -> f64 {
+        match self.entity_count() {
+            0 => 0.0,
+            count => self.skipped_count() as f64 / count as f64,
+        }
+    }
+
+    /// True if nothing went wrong.
+    pub fn is_clean(&self) -> bool {
+        self.warnings.is_empty()
+    }
+}
+
+/// Why a conversion failed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConversionError {
+    /// `strict` is set and parts of the drawing couldn't be drawn: these warnings came up, about the entities
+    /// with these handles
+    Incomplete { handles: Vec<u64>, warnings: Vec<Warning> },
+    /// `strict` is set and nothing in the drawing has any extent to show
+    EmptyDrawing,
+    /// The `cancel` flag was set before the conversion finished
+    Cancelled,
+}
+
+impl ConversionError {
+    /// The error for a strict conversion, if its report has any warnings.
+    pub(crate) fn strict(report: &ConversionReport) -> Result<(), ConversionError> {
+        if report.is_clean() {
+            return Ok(());
+        }
+        // an empty drawing is only worth reporting as such when nothing was dropped to make it empty
+        if report.warnings == [Warning::EmptyDrawing] {
+            return Err(ConversionError::EmptyDrawing);
+        }
+        let mut handles: Vec<u64> = report.warnings.iter().filter_map(Warning::handle).collect();
+        handles.sort_unstable();
+        handles.dedup();
+        Err(ConversionError::Incomplete { handles, warnings: report.warnings.clone() })
+    }
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConversionError::Incomplete { handles, warnings } => {
+                let handles: Vec<String> = handles.iter().map(|handle| format!("{:X}", handle)).collect();
+                write!(f, "the drawing couldn't be converted completely ({} warnings", warnings.len())?;
+                if !handles.is_empty() {
+                    write!(f, ", entities {}", handles.join(", "))?;
+                }
+                write!(f, ")")
+            }
+            ConversionError::EmptyDrawing => write!(f, "the drawing is empty"),
+            ConversionError::Cancelled => write!(f, "the conversion was cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for ConversionError {}
+
+/// Called with each warning as it comes up, from whichever thread rendered the entity.
+pub type WarningCallback = Box<dyn FnMut(&Warning) + Send + Sync>;
+
+/// Collects the warnings of a conversion into its report and passes them on to the `on_warning` callback.
+pub(crate) struct Reporter {
+    report: Mutex<ConversionReport>,
+    on_warning: Option<Mutex<WarningCallback>>,
+}
+
+impl Reporter {
+    pub(crate) fn new(on_warning: Option<WarningCallback>) -> Self {
+        Reporter { report: Mutex::default(), on_warning: on_warning.map(Mutex::new) }
+    }
+
+    pub(crate) fn warn(&self, warning: Warning) {
+        #[cfg(feature = "tracing")]
+        tracing::warn!(%warning);
+        if let Some(on_warning) = &self.on_warning {
+            (on_warning.lock().unwrap())(&warning);
+        }
+        self.report.lock().unwrap().warnings.push(warning);
+    }
+
+    /// Counts an entity that wasn't drawn.
+    pub(crate) fn skipped(&self, entity_type: &'static str) {
+        *self.report.lock().unwrap().skipped.entry(entity_type).or_default() += 1;
+    }
+
+    /// Counts an entity being converted.
+    pub(crate) fn entity(&self, entity_type: &'static str) {
+        *self.report.lock().unwrap().entities.entry(entity_type).or_default() += 1;
+    }
+
+    /// The report, finished with the area the SVG shows and its length.
+    pub(crate) fn into_report(self, bounds: Bounds, output_bytes: usize) -> ConversionReport {
+        ConversionReport { bounds: Some(bounds), output_bytes, ..self.report.into_inner().unwrap() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reporter() {
+        let seen = std::sync::Arc::new(Mutex::new(vec![]));
+        let sink = seen.clone();
+        let reporter = Reporter::new(Some(Box::new(move |warning: &Warning| sink.lock().unwrap().push(warning.to_string()))));
+        reporter.warn(Warning::Unsupported { handle: 0x2A, entity_type: "ATTRIB" });
+        reporter.warn(Warning::Unsupported { handle: 0x2B, entity_type: "ATTRIB" });
+        reporter.warn(Warning::MissingBlock { handle: 0x2C, block: "door".to_string() });
+        for entity_type in ["ATTRIB", "ATTRIB", "LINE", "LINE"] {
+            reporter.entity(entity_type);
+        }
+        reporter.skipped("ATTRIB");
+        reporter.skipped("ATTRIB");
+        assert_eq!(*seen.lock().unwrap(), [
+            "skipped unsupported ATTRIB entity 2A",
+            "skipped unsupported ATTRIB entity 2B",
+            r#"insert 2C refers to missing block "door""#,
+        ]);
+        let report = reporter.into_report(Bounds { min_x: 0.0, min_y: 0.0, max_x: 1.0, max_y: 1.0 }, 100);
+        assert_eq!(report.warnings.len(), 3);
+        assert_eq!(report.skipped_count(), 2);
+        assert_eq!(report.rendered_count(), 2);
+        assert_eq!(report.skipped_ratio(), 0.5);
+        assert_eq!(report.skipped.get("ATTRIB"), Some(&2));
+
+        let error = ConversionError::strict(&report).unwrap_err();
+        assert_eq!(error.to_string(), "the drawing couldn't be converted completely (3 warnings, entities 2A, 2B, 2C)");
+        assert_eq!(ConversionError::strict(&ConversionReport::default()), Ok(()));
+    }
+}
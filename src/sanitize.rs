@@ -0,0 +1,154 @@
+use dxf::entities::{Entity, EntityType};
+use dxf::{Point, Vector};
+
+use crate::dimension;
+
+/// Coordinates and sizes further than this from 0 are taken as corrupt: no real drawing spans them, and
+/// they would swamp the bounds of everything else
+const MAX_COORDINATE: f64 = 1e12;
+
+/**
+True if every coordinate and size of an entity is a finite number of sensible magnitude. Corrupt files
+sometimes hold NaN or absurd values, which would poison the bounds and end up as invalid SVG attributes.
+*/
+pub(crate) fn has_valid_coordinates(entity: &Entity) -> bool {
+    values(&entity.specific).into_iter().all(|value| value.is_finite() && value.abs() <= MAX_COORDINATE)
+}
+
+/**
+Why an entity draws nothing, or next to nothing: a line without length, a circle or arc without a radius,
+or a polyline with a single vertex. None if it's fine.
+*/
+pub(crate) fn degenerate_reason(entity: &Entity) -> Option<&'static str> {
+    match &entity.specific {
+        EntityType::Line(line) if line.p1 == line.p2 => Some("has no length"),
+        EntityType::Circle(circle) if circle.radius <= 0.0 => Some("has no radius"),
+        EntityType::Arc(arc) if arc.radius <= 0.0 => Some("has no radius"),
+        EntityType::LwPolyline(lwpolyline) if lwpolyline.vertices.len() < 2 => Some("has fewer than 2 vertices"),
+        EntityType::Polyline(polyline) if polyline.vertices().nth(1).is_none() => Some("has fewer than 2 vertices"),
+        _ => None,
+    }
+}
+
+fn point(point: &Point) -> [f64; 3] {
+    [point.x, point.y, point.z]
+}
+
+fn vector(vector: &Vector) -> [f64; 3] {
+    [vector.x, vector.y, vector.z]
+}
+
+/// The coordinates, sizes and angles the geometry of an entity is drawn from.
+fn values(specific: &EntityType) -> Vec<f64> {
+    match specific {
+        EntityType::Line(line) => [point(&line.p1), point(&line.p2)].concat(),
+        EntityType::Circle(circle) => [&point(&circle.center)[..], &[circle.radius]].concat(),
+        EntityType::Arc(arc) => [&point(&arc.center)[..], &[arc.radius, arc.start_angle, arc.end_angle]].concat(),
+        EntityType::Ellipse(ellipse) => [
+            &point(&ellipse.center)[..],
+            &vector(&ellipse.major_axis),
+            &[ellipse.minor_axis_ratio, ellipse.start_parameter, ellipse.end_parameter],
+        ]
+        .concat(),
+        EntityType::LwPolyline(lwpolyline) => lwpolyline
+            .vertices
+            .iter()
+            .flat_map(|vertex| [vertex.x, vertex.y, vertex.bulge])
+            .chain([lwpolyline.elevation])
+            .collect(),
+        EntityType::Polyline(polyline) => polyline
+            .vertices()
+            .flat_map(|vertex| [&point(&vertex.location)[..], &[vertex.bulge]].concat())
+            .collect(),
+        EntityType::Spline(spline) => spline.control_points.iter().chain(&spline.fit_points).flat_map(point).collect(),
+        EntityType::Text(text) => [
+            &point(&text.location)[..],
+            &point(&text.second_alignment_point),
+            &[text.text_height, text.rotation],
+        ]
+        .concat(),
+        EntityType::MText(mtext) => [
+            &point(&mtext.insertion_point)[..],
+            &[mtext.initial_text_height, mtext.reference_rectangle_width, mtext.rotation_angle],
+        ]
+        .concat(),
+        EntityType::ModelPoint(model_point) => point(&model_point.location).to_vec(),
+        EntityType::Face3D(face) => {
+            [&face.first_corner, &face.second_corner, &face.third_corner, &face.fourth_corner].into_iter().flat_map(point).collect()
+        }
+        EntityType::Solid(solid) => {
+            [&solid.first_corner, &solid.second_corner, &solid.third_corner, &solid.fourth_corner].into_iter().flat_map(point).collect()
+        }
+        EntityType::Trace(trace) => {
+            [&trace.first_corner, &trace.second_corner, &trace.third_corner, &trace.fourth_corner].into_iter().flat_map(point).collect()
+        }
+        EntityType::Insert(insert) => [
+            &point(&insert.location)[..],
+            &[insert.x_scale_factor, insert.y_scale_factor, insert.rotation, insert.column_spacing, insert.row_spacing],
+        ]
+        .concat(),
+        EntityType::Leader(leader) => leader.vertices.iter().flat_map(point).collect(),
+        EntityType::Ray(ray) => [point(&ray.start_point), vector(&ray.unit_direction_vector)].concat(),
+        EntityType::XLine(xline) => [point(&xline.first_point), vector(&xline.unit_direction_vector)].concat(),
+        EntityType::Helix(helix) => [
+            &point(&helix.axis_base_point)[..],
+            &point(&helix.start_point),
+            &vector(&helix.axis_vector),
+            &[helix.radius, helix.turn_height, helix.number_of_turns],
+        ]
+        .concat(),
+        EntityType::Shape(shape) => [&point(&shape.location)[..], &[shape.size, shape.rotation_angle]].concat(),
+        EntityType::Image(image) => {
+            [point(&image.location), vector(&image.u_vector), vector(&image.v_vector), vector(&image.image_size)].concat()
+        }
+        EntityType::Wipeout(wipeout) => [
+            point(&wipeout.location),
+            vector(&wipeout.u_vector),
+            vector(&wipeout.v_vector),
+            vector(&wipeout.image_size),
+        ]
+        .concat(),
+        EntityType::MLine(mline) => mline.vertices.iter().flat_map(point).collect(),
+        EntityType::Tolerance(tolerance) => point(&tolerance.insertion_point).to_vec(),
+        specific @ (EntityType::RotatedDimension(_)
+        | EntityType::AlignedDimension(_)
+        | EntityType::RadialDimension(_)
+        | EntityType::DiameterDimension(_)
+        | EntityType::AngularThreePointDimension(_)
+        | EntityType::OrdinateDimension(_)) => {
+            dimension::definition_points(specific).into_iter().flat_map(|(x, y)| [x, y]).collect()
+        }
+        _ => vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dxf::entities::{Circle, Helix, Line};
+
+    #[test]
+    fn test_has_valid_coordinates() {
+        let line = Entity::new(EntityType::Line(Line::new(Point::new(0.0, 0.0, 0.0), Point::new(1.0, 1.0, 0.0))));
+        assert!(has_valid_coordinates(&line));
+        let line = Entity::new(EntityType::Line(Line::new(Point::new(0.0, f64::NAN, 0.0), Point::new(1.0, 1.0, 0.0))));
+        assert!(!has_valid_coordinates(&line));
+        let circle = Entity::new(EntityType::Circle(Circle::new(Point::new(0.0, 0.0, 0.0), 1e300)));
+        assert!(!has_valid_coordinates(&circle));
+        let circle = Entity::new(EntityType::Circle(Circle::new(Point::new(0.0, 0.0, f64::INFINITY), 1.0)));
+        assert!(!has_valid_coordinates(&circle));
+        let helix = Entity::new(EntityType::Helix(Helix { number_of_turns: f64::NAN, ..Default::default() }));
+        assert!(!has_valid_coordinates(&helix));
+    }
+
+    #[test]
+    fn test_degenerate_reason() {
+        let point = Point::new(1.0, 2.0, 0.0);
+        let line = Entity::new(EntityType::Line(Line::new(point.clone(), point.clone())));
+        assert_eq!(degenerate_reason(&line), Some("has no length"));
+        let line = Entity::new(EntityType::Line(Line::new(point.clone(), Point::new(1.0, 2.0, 3.0))));
+        assert_eq!(degenerate_reason(&line), None);
+        let circle = Entity::new(EntityType::Circle(Circle::new(point, 0.0)));
+        assert_eq!(degenerate_reason(&circle), Some("has no radius"));
+    }
+}
@@ -0,0 +1,549 @@
+use std::collections::HashMap;
+use std::fmt::{self, Write};
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+use dxf::entities::{Shape, Text};
+
+use crate::text::{self, TextStyle};
+
+/// The directions of vector codes, a 16th of a turn apart starting east, each as long as its longest side.
+const DIRECTIONS: [(f64, f64); 16] = [
+    (1.0, 0.0),
+    (1.0, 0.5),
+    (1.0, 1.0),
+    (0.5, 1.0),
+    (0.0, 1.0),
+    (-0.5, 1.0),
+    (-1.0, 1.0),
+    (-1.0, 0.5),
+    (-1.0, 0.0),
+    (-1.0, -0.5),
+    (-1.0, -1.0),
+    (-0.5, -1.0),
+    (0.0, -1.0),
+    (0.5, -1.0),
+    (1.0, -1.0),
+    (1.0, -0.5),
+];
+
+/// How deep subshapes may reference other subshapes, which stops fonts referencing themselves
+const MAX_SUBSHAPE_DEPTH: usize = 8;
+
+/// Where AutoCAD's standard fonts keep the symbols of the `%%d`, `%%p` and `%%c` codes.
+const SYMBOL_SHAPES: &[(char, u16)] = &[('°', 127), ('±', 128), ('Ø', 129), ('⌀', 129)];
+
+#[derive(Debug, PartialEq)]
+struct Shapes {
+    /// shape specification bytes by shape number
+    shapes: HashMap<u16, Vec<u8>>,
+    names: HashMap<String, u16>,
+    /// the height of capital letters, in vector units
+    above: f64,
+    /// how far descenders reach below the baseline, in vector units
+    below: f64,
+    /// unifont shapes are numbered by unicode and reference subshapes with two bytes
+    unicode: bool,
+}
+
+/**
+A compiled AutoCAD shape or stroke font (`.shx`), like `txt.shx` or `simplex.shx`.
+Text in a style using the font is drawn as the font's strokes, looking exactly like it does in AutoCAD,
+and SHAPE entities are drawn from the shapes they name. AutoCAD's fonts can't be redistributed, so
+they have to be loaded from an AutoCAD installation. Shape fonts and unicode fonts are supported,
+big fonts for Asian languages aren't.
+*/
+#[derive(Clone, PartialEq)]
+pub struct ShxFont {
+    shapes: Arc<Shapes>,
+}
+
+/// A step of a glyph's strokes, in vector units.
+#[derive(Debug, Clone, PartialEq)]
+enum Stroke {
+    Move(f64, f64),
+    Line(f64, f64),
+    /// an arc of at most half a turn
+    Arc { radius: f64, counterclockwise: bool, x: f64, y: f64 },
+}
+
+/// Runs shape specifications, turning the pen's movements into strokes.
+struct Pen<'a> {
+    font: &'a Shapes,
+    strokes: Vec<Stroke>,
+    x: f64,
+    y: f64,
+    down: bool,
+    /// true when the last stroke ends where the pen is, so drawing on needs no move
+    attached: bool,
+    scale: f64,
+    stack: Vec<(f64, f64)>,
+}
+
+impl ShxFont {
+    /// Reads an `.shx` file.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+
+    /// Parses the contents of an `.shx` file, failing with `InvalidData` if it isn't a supported font.
+    pub fn from_bytes(data: &[u8]) -> io::Result<Self> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+        let header_end = data
+            .iter()
+            .position(|byte| *byte == 0x1A)
+            .ok_or_else(|| invalid("not an shx file"))?;
+        let header = String::from_utf8_lossy(&data[..header_end]).to_lowercase();
+        let mut reader = Reader { data, position: header_end + 1 };
+        let unicode = if header.starts_with("autocad-86 shapes") {
+            false
+        } else if header.starts_with("autocad-86 unifont") {
+            true
+        } else if header.starts_with("autocad-86 bigfont") {
+            return Err(invalid("big fonts aren't supported"));
+        } else {
+            return Err(invalid("not an shx file"));
+        };
+
+        let mut definitions = vec![];
+        if unicode {
+            let count = reader.u32().ok_or_else(|| invalid("truncated shx file"))?;
+            let info_length = reader.u16().ok_or_else(|| invalid("truncated shx file"))?;
+            definitions.push((0, reader.bytes(info_length as usize).ok_or_else(|| invalid("truncated shx file"))?));
+            for _ in 1..count {
+                let Some((number, length)) = reader.u16().zip(reader.u16()) else { break };
+                let Some(bytes) = reader.bytes(length as usize) else { break };
+                definitions.push((number, bytes));
+            }
+        } else {
+            let header = reader.u16().zip(reader.u16()).zip(reader.u16());
+            let ((_first, _last), count) = header.ok_or_else(|| invalid("truncated shx file"))?;
+            let mut index = vec![];
+            for _ in 0..count {
+                index.push(reader.u16().zip(reader.u16()).ok_or_else(|| invalid("truncated shx file"))?);
+            }
+            for (number, length) in index {
+                definitions.push((number, reader.bytes(length as usize).ok_or_else(|| invalid("truncated shx file"))?));
+            }
+        }
+
+        let mut shapes = Shapes { shapes: HashMap::new(), names: HashMap::new(), above: 1.0, below: 0.0, unicode };
+        for (number, bytes) in definitions {
+            // each definition is its name, then its specification
+            let name_end = bytes.iter().position(|byte| *byte == 0).unwrap_or(bytes.len());
+            let name = String::from_utf8_lossy(&bytes[..name_end]).to_string();
+            let specification = bytes.get(name_end + 1..).unwrap_or_default().to_vec();
+            if number == 0 {
+                // the font's description: the height above and below the baseline
+                if let [above, below, ..] = specification[..] {
+                    shapes.above = f64::from(above.max(1));
+                    shapes.below = f64::from(below);
+                }
+                continue;
+            }
+            if !name.is_empty() {
+                shapes.names.insert(name.to_uppercase(), number);
+            }
+            shapes.shapes.insert(number, specification);
+        }
+        Ok(ShxFont { shapes: Arc::new(shapes) })
+    }
+
+    /// The number of a shape by its name, as SHAPE entities reference them.
+    pub(crate) fn shape_number(&self, name: &str) -> Option<u16> {
+        self.shapes.names.get(&name.to_uppercase()).copied()
+    }
+
+    /// The strokes of a line of text and how far it advances, in vector units.
+    fn text_strokes(&self, text: &str) -> (Vec<Stroke>, f64) {
+        let mut pen = Pen::new(&self.shapes);
+        for c in text.chars() {
+            let number = self.glyph_number(c);
+            if let Some(number) = number {
+                pen.draw_shape(number, 0);
+            }
+        }
+        (pen.strokes, pen.x)
+    }
+
+    /// The shape drawing a character, if the font has one.
+    fn glyph_number(&self, c: char) -> Option<u16> {
+        let shapes = &self.shapes.shapes;
+        let code = u16::try_from(u32::from(c)).ok().filter(|code| shapes.contains_key(code));
+        code.or_else(|| {
+            SYMBOL_SHAPES
+                .iter()
+                .find(|(symbol, number)| *symbol == c && shapes.contains_key(number))
+                .map(|(_, number)| *number)
+        })
+    }
+}
+
+impl fmt::Debug for ShxFont {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShxFont").field("shapes", &self.shapes.shapes.len()).finish()
+    }
+}
+
+/// Reads the little endian numbers of an `.shx` file.
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl Reader<'_> {
+    fn bytes(&mut self, length: usize) -> Option<Vec<u8>> {
+        let bytes = self.data.get(self.position..self.position + length)?.to_vec();
+        self.position += length;
+        Some(bytes)
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        self.bytes(2).map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.bytes(4).map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+}
+
+impl<'a> Pen<'a> {
+    fn new(font: &'a Shapes) -> Self {
+        Pen { font, strokes: vec![], x: 0.0, y: 0.0, down: true, attached: false, scale: 1.0, stack: vec![] }
+    }
+
+    /// Moves the pen by a displacement, drawing a line if it's down.
+    fn move_by(&mut self, dx: f64, dy: f64) {
+        let (x, y) = (self.x + dx * self.scale, self.y + dy * self.scale);
+        if self.down {
+            self.attach();
+            self.strokes.push(Stroke::Line(x, y));
+        } else {
+            self.attached = false;
+        }
+        self.x = x;
+        self.y = y;
+    }
+
+    /// Moves the pen along an arc around a center, drawing it if the pen is down. Angles are in degrees.
+    fn arc(&mut self, radius: f64, start: f64, sweep: f64) {
+        let radius = radius * self.scale;
+        let center = (self.x - radius * start.to_radians().cos(), self.y - radius * start.to_radians().sin());
+        // SVG arcs of a full turn draw nothing, so arcs are split into half turns or less
+        let pieces = (sweep.abs() / 180.0).ceil().max(1.0);
+        for piece in 1..=pieces as usize {
+            let angle = (start + sweep * piece as f64 / pieces).to_radians();
+            let (x, y) = (center.0 + radius * angle.cos(), center.1 + radius * angle.sin());
+            if self.down {
+                self.attach();
+                self.strokes.push(Stroke::Arc { radius, counterclockwise: sweep > 0.0, x, y });
+            }
+            self.x = x;
+            self.y = y;
+        }
+        self.attached &= self.down;
+    }
+
+    /// Moves the pen by a displacement along an arc bulging by `bulge` (-127 to 127, 127 being a half circle).
+    fn bulge_by(&mut self, dx: f64, dy: f64, bulge: i8) {
+        if bulge == 0 {
+            return self.move_by(dx, dy);
+        }
+        let (dx, dy) = (dx * self.scale, dy * self.scale);
+        let chord = (dx * dx + dy * dy).sqrt();
+        let height = f64::from(bulge) / 127.0 * chord / 2.0;
+        let radius = (chord * chord / 4.0 + height * height) / (2.0 * height.abs());
+        let (x, y) = (self.x + dx, self.y + dy);
+        if self.down {
+            self.attach();
+            self.strokes.push(Stroke::Arc { radius, counterclockwise: bulge > 0, x, y });
+        } else {
+            self.attached = false;
+        }
+        self.x = x;
+        self.y = y;
+    }
+
+    /// Starts a new stroke at the pen unless the last one ends there.
+    fn attach(&mut self) {
+        if !self.attached {
+            self.strokes.push(Stroke::Move(self.x, self.y));
+            self.attached = true;
+        }
+    }
+
+    fn draw_shape(&mut self, number: u16, depth: usize) {
+        let font = self.font;
+        let Some(specification) = font.shapes.get(&number) else { return };
+        if depth > MAX_SUBSHAPE_DEPTH {
+            return;
+        }
+        self.down = true;
+        self.scale = 1.0;
+        let mut bytes = specification.iter().copied();
+        while let Some(code) = bytes.next() {
+            if code == 0 {
+                break;
+            }
+            if code == 14 {
+                // the next command is only for vertical text, so it's run without effect
+                let (strokes, x, y, down, attached, scale, stack) =
+                    (self.strokes.len(), self.x, self.y, self.down, self.attached, self.scale, self.stack.clone());
+                self.command(&mut bytes, depth);
+                self.strokes.truncate(strokes);
+                (self.x, self.y, self.down, self.attached, self.scale, self.stack) = (x, y, down, attached, scale, stack);
+                continue;
+            }
+            self.run(code, &mut bytes, depth);
+        }
+    }
+
+    /// Runs the command starting with the next byte.
+    fn command(&mut self, bytes: &mut impl Iterator<Item = u8>, depth: usize) {
+        if let Some(code) = bytes.next() {
+            self.run(code, bytes, depth);
+        }
+    }
+
+    fn run(&mut self, code: u8, bytes: &mut impl Iterator<Item = u8>, depth: usize) {
+        let mut byte = || bytes.next().unwrap_or(0);
+        match code {
+            0 | 14 => {}
+            1 => self.down = true,
+            2 => {
+                self.down = false;
+                self.attached = false;
+            }
+            3 => self.scale /= f64::from(byte().max(1)),
+            4 => self.scale *= f64::from(byte()),
+            5 => self.stack.push((self.x, self.y)),
+            6 => {
+                if let Some((x, y)) = self.stack.pop() {
+                    (self.x, self.y) = (x, y);
+                    self.attached = false;
+                }
+            }
+            7 => {
+                let number = if self.font.unicode {
+                    u16::from_be_bytes([byte(), byte()])
+                } else {
+                    u16::from(byte())
+                };
+                let (down, scale) = (self.down, self.scale);
+                self.draw_shape(number, depth + 1);
+                (self.down, self.scale) = (down, scale);
+            }
+            8 => {
+                let (dx, dy) = (byte() as i8, byte() as i8);
+                self.move_by(f64::from(dx), f64::from(dy));
+            }
+            9 => loop {
+                let (dx, dy) = (byte() as i8, byte() as i8);
+                if dx == 0 && dy == 0 {
+                    break;
+                }
+                self.move_by(f64::from(dx), f64::from(dy));
+            },
+            10 => {
+                let radius = f64::from(byte());
+                let (start, sweep) = octant_arc(byte() as i8);
+                self.arc(radius, start, sweep);
+            }
+            11 => {
+                let (start_offset, end_offset) = (f64::from(byte()) * 45.0 / 256.0, f64::from(byte()) * 45.0 / 256.0);
+                let radius = f64::from(u16::from_be_bytes([byte(), byte()]));
+                let (start, sweep) = octant_arc(byte() as i8);
+                // the arc starts and ends part way into its first and last octants
+                let direction = sweep.signum();
+                let end = start + direction * (sweep.abs() - 45.0 + end_offset);
+                let start = start + direction * start_offset;
+                self.arc(radius, start, end - start);
+            }
+            12 => {
+                let (dx, dy, bulge) = (byte() as i8, byte() as i8, byte() as i8);
+                self.bulge_by(f64::from(dx), f64::from(dy), bulge);
+            }
+            13 => loop {
+                let (dx, dy) = (byte() as i8, byte() as i8);
+                if dx == 0 && dy == 0 {
+                    break;
+                }
+                let bulge = byte() as i8;
+                self.bulge_by(f64::from(dx), f64::from(dy), bulge);
+            },
+            vector => {
+                let length = f64::from(vector >> 4);
+                let (dx, dy) = DIRECTIONS[usize::from(vector & 0x0F)];
+                self.move_by(dx * length, dy * length);
+            }
+        }
+    }
+}
+
+/// The start angle and signed sweep, in degrees, of an octant arc specification: the sign gives the
+/// direction (negative is clockwise), then the starting octant and the number of octants, 0 meaning all 8.
+fn octant_arc(specification: i8) -> (f64, f64) {
+    let bits = specification as u8;
+    let start = f64::from((bits >> 4) & 0x07) * 45.0;
+    let octants = match bits & 0x07 {
+        0 => 8.0,
+        count => f64::from(count),
+    };
+    let direction = if specification < 0 { -1.0 } else { 1.0 };
+    (start, direction * octants * 45.0)
+}
+
+/// Writes strokes as path data, scaled from vector units and moved by an offset.
+fn path_data(strokes: &[Stroke], scale: f64, offset: (f64, f64), precision: usize) -> String {
+    let point = |x: f64, y: f64| format!("{:.p$},{:.p$}", offset.0 + x * scale, offset.1 + y * scale, p = precision);
+    strokes
+        .iter()
+        .map(|stroke| match stroke {
+            Stroke::Move(x, y) => format!("M {}", point(*x, *y)),
+            Stroke::Line(x, y) => format!("L {}", point(*x, *y)),
+            Stroke::Arc { radius, counterclockwise, x, y } => format!(
+                "A {r:.p$} {r:.p$} 0 0 {} {}",
+                u8::from(*counterclockwise),
+                point(*x, *y),
+                r = radius * scale,
+                p = precision
+            ),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/**
+Renders a TEXT as a `<path>` of its strokes in an SHX font, placed like `text::render_text` places
+`<text>`. The text height is the font's height above the baseline.
+*/
+pub(crate) fn render_text(
+    text: &Text,
+    style: &TextStyle,
+    font: &ShxFont,
+    stroke_attr: &str,
+    precision: usize,
+    svg: &mut String,
+) {
+    let layout = text::layout(text, style, precision);
+    let (strokes, advance) = font.text_strokes(&text::plain_text(&text.value));
+    if strokes.is_empty() {
+        return;
+    }
+    let scale = layout.height / font.shapes.above;
+    let advance = advance * scale;
+    let offset = layout.path_offset(advance, font.shapes.below * scale);
+    write!(
+        svg,
+        r#"<path transform="{}" d="{}" fill="none" {} />"#,
+        layout.path_transform(advance, precision),
+        path_data(&strokes, scale, offset, precision),
+        stroke_attr
+    ).unwrap();
+}
+
+/// Renders a SHAPE entity from its shape in an SHX font, scaled so the font's height is its size.
+pub(crate) fn render_shape(shape: &Shape, font: &ShxFont, number: u16, stroke_attr: &str, precision: usize, svg: &mut String) {
+    let mut pen = Pen::new(&font.shapes);
+    pen.draw_shape(number, 0);
+    if pen.strokes.is_empty() {
+        return;
+    }
+    let mut transform = format!("translate({:.p$} {:.p$})", shape.location.x, shape.location.y, p = precision);
+    if shape.rotation_angle != 0.0 {
+        write!(transform, " rotate({:.p$})", shape.rotation_angle, p = precision).unwrap();
+    }
+    if shape.relative_x_scale_factor > 0.0 && shape.relative_x_scale_factor != 1.0 {
+        write!(transform, " scale({:.p$} 1)", shape.relative_x_scale_factor, p = precision).unwrap();
+    }
+    if shape.oblique_angle != 0.0 {
+        write!(transform, " skewX({:.p$})", shape.oblique_angle, p = precision).unwrap();
+    }
+    let size = if shape.size > 0.0 { shape.size } else { 1.0 };
+    write!(
+        svg,
+        r#"<path transform="{}" d="{}" fill="none" {} />"#,
+        transform,
+        path_data(&pen.strokes, size / font.shapes.above, (0.0, 0.0), precision),
+        stroke_attr
+    ).unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A shape font with a description (4 up, 1 down), an "L" and a space.
+    fn test_font() -> ShxFont {
+        let definitions: [(u16, &[u8]); 3] = [
+            (0, b"TEST\0\x04\x01\x00\x00"),
+            // pen up, 4 north, pen down, 4 south, 2 east, pen up, 1 east
+            (u16::from(b'L'), b"\0\x02\x44\x01\x4C\x20\x02\x10\x00"),
+            // pen up, 3 east
+            (u16::from(b' '), b"\0\x02\x30\x00"),
+        ];
+        let mut data = b"AutoCAD-86 shapes 1.0\r\n\x1A".to_vec();
+        data.extend(1u16.to_le_bytes());
+        data.extend(255u16.to_le_bytes());
+        data.extend((definitions.len() as u16).to_le_bytes());
+        for (number, bytes) in &definitions {
+            data.extend(number.to_le_bytes());
+            data.extend((bytes.len() as u16).to_le_bytes());
+        }
+        for (_, bytes) in &definitions {
+            data.extend(*bytes);
+        }
+        ShxFont::from_bytes(&data).unwrap()
+    }
+
+    #[test]
+    fn test_text_strokes() {
+        let font = test_font();
+        let (strokes, advance) = font.text_strokes("L L");
+        assert_eq!(advance, 9.0);
+        assert_eq!(
+            strokes,
+            [
+                Stroke::Move(0.0, 4.0),
+                Stroke::Line(0.0, 0.0),
+                Stroke::Line(2.0, 0.0),
+                Stroke::Move(6.0, 4.0),
+                Stroke::Line(6.0, 0.0),
+                Stroke::Line(8.0, 0.0),
+            ]
+        );
+        assert_eq!(path_data(&strokes[..3], 0.5, (1.0, 0.0), 1), "M 1.0,2.0 L 1.0,0.0 L 2.0,0.0");
+    }
+
+    #[test]
+    fn test_arcs() {
+        assert_eq!(octant_arc(0x02), (0.0, 90.0));
+        assert_eq!(octant_arc(-0x3C), (180.0, -180.0));
+
+        let shapes = &test_font().shapes;
+        let mut pen = Pen::new(shapes);
+        // a full circle of radius 2 starting east is drawn as two half turns
+        pen.arc(2.0, 0.0, 360.0);
+        assert_eq!(pen.strokes.len(), 3);
+        assert!(matches!(pen.strokes[1], Stroke::Arc { counterclockwise: true, .. }));
+        assert!(pen.x.abs() < 1e-9 && pen.y.abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_render_text() {
+        let text = Text { value: "L".to_string(), text_height: 2.0, ..Default::default() };
+        let mut svg = String::new();
+        render_text(&text, &TextStyle::default(), &test_font(), r#"stroke="red""#, 1, &mut svg);
+        assert_eq!(
+            svg,
+            r#"<path transform="translate(0.0 0.0)" d="M 0.0,2.0 L 0.0,0.0 L 1.0,0.0" fill="none" stroke="red" />"#
+        );
+    }
+
+    #[test]
+    fn test_invalid_font() {
+        assert_eq!(ShxFont::from_bytes(b"AutoCAD-86 bigfont 1.0\r\n\x1A").unwrap_err().kind(), io::ErrorKind::InvalidData);
+        assert!(ShxFont::from_bytes(b"not a font").is_err());
+    }
+}
@@ -0,0 +1,202 @@
+use dxf::entities::Spline;
+
+use crate::geometry;
+
+/**
+Flattens a spline into a list of points, none of which strays more than `tolerance` from the curve,
+by evaluating it as a NURBS curve: its degree, knot vector and weights define how the control points are blended.
+Splines without a usable knot vector get a clamped uniform one, and missing weights default to 1.
+Splines that only carry fit points are interpolated through them instead.
+*/
+pub(crate) fn spline_points(spline: &Spline, tolerance: f64) -> Vec<(f64, f64)> {
+    if spline.control_points.len() < 2 && spline.fit_points.len() >= 2 {
+        return fit_point_curve(spline, tolerance);
+    }
+    let control_points: Vec<(f64, f64)> = spline.control_points.iter().map(|point| (point.x, point.y)).collect();
+    let count = control_points.len();
+    if count < 2 {
+        return control_points;
+    }
+
+    let degree = (spline.degree_of_curve.max(1) as usize).min(count - 1);
+    let knots = if spline.knot_values.len() == count + degree + 1 {
+        spline.knot_values.clone()
+    } else {
+        clamped_uniform_knots(count, degree)
+    };
+    let weights = if spline.weight_values.len() == count {
+        spline.weight_values.clone()
+    } else {
+        vec![1.0; count]
+    };
+
+    let mut points = vec![];
+    for span in degree..count {
+        let (start, end) = (knots[span], knots[span + 1]);
+        if end <= start {
+            continue;
+        }
+        let segments = geometry::curve_segments(
+            max_second_difference(&control_points[span - degree..=span]),
+            degree,
+            tolerance,
+        );
+        let first_sample = if points.is_empty() { 0 } else { 1 };
+        for i in first_sample..=segments {
+            let t = start + (end - start) * i as f64 / segments as f64;
+            points.push(de_boor(span, t, degree, &knots, &control_points, &weights));
+        }
+    }
+    points
+}
+
+/// The curve through the fit points of a spline.
+fn fit_point_curve(spline: &Spline, tolerance: f64) -> Vec<(f64, f64)> {
+    let fit_points: Vec<(f64, f64)> = spline.fit_points.iter().map(|point| (point.x, point.y)).collect();
+    fit_curve(fit_points, spline.is_closed(), tolerance)
+}
+
+/**
+A Catmull-Rom curve through fit points. The curve passes through every fit point,
+closed curves wrap around and open ones start and end on their outer fit points.
+*/
+pub(crate) fn fit_curve(mut fit_points: Vec<(f64, f64)>, closed: bool, tolerance: f64) -> Vec<(f64, f64)> {
+    if fit_points.len() < 2 {
+        return fit_points;
+    }
+    let closed = closed && fit_points.len() > 2;
+    if closed && fit_points.first() == fit_points.last() {
+        fit_points.pop();
+    }
+    let count = fit_points.len();
+    let point = |i: isize| {
+        if closed {
+            fit_points[i.rem_euclid(count as isize) as usize]
+        } else {
+            fit_points[i.clamp(0, count as isize - 1) as usize]
+        }
+    };
+
+    let segments = if closed { count } else { count - 1 };
+    let mut points = vec![fit_points[0]];
+    for segment in 0..segments as isize {
+        let (p0, p1, p2, p3) = (point(segment - 1), point(segment), point(segment + 1), point(segment + 2));
+        // the same segment as a cubic bezier, to measure how much it bends
+        let bezier = [
+            p1,
+            (p1.0 + (p2.0 - p0.0) / 6.0, p1.1 + (p2.1 - p0.1) / 6.0),
+            (p2.0 - (p3.0 - p1.0) / 6.0, p2.1 - (p3.1 - p1.1) / 6.0),
+            p2,
+        ];
+        let segments = geometry::curve_segments(max_second_difference(&bezier), 3, tolerance);
+        for i in 1..=segments {
+            let t = i as f64 / segments as f64;
+            points.push((catmull_rom(p0.0, p1.0, p2.0, p3.0, t), catmull_rom(p0.1, p1.1, p2.1, p3.1, t)));
+        }
+    }
+    points
+}
+
+/// The largest `|P[i-1] - 2 P[i] + P[i+1]|` of a run of control points.
+fn max_second_difference(points: &[(f64, f64)]) -> f64 {
+    points
+        .windows(3)
+        .map(|w| (w[0].0 - 2.0 * w[1].0 + w[2].0).hypot(w[0].1 - 2.0 * w[1].1 + w[2].1))
+        .fold(0.0, f64::max)
+}
+
+/// One coordinate of a uniform Catmull-Rom segment between `p1` and `p2`.
+fn catmull_rom(p0: f64, p1: f64, p2: f64, p3: f64, t: f64) -> f64 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    0.5 * (2.0 * p1 + (p2 - p0) * t + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2 + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t3)
+}
+
+/// A knot vector that makes the curve start and end on its first and last control points.
+fn clamped_uniform_knots(count: usize, degree: usize) -> Vec<f64> {
+    let spans = (count - degree) as f64;
+    (0..count + degree + 1)
+        .map(|i| (i.saturating_sub(degree) as f64).min(spans))
+        .collect()
+}
+
+/// Evaluates the rational curve at `t`, which lies in the knot span starting at `knots[span]`.
+fn de_boor(span: usize, t: f64, degree: usize, knots: &[f64], points: &[(f64, f64)], weights: &[f64]) -> (f64, f64) {
+    // work in homogeneous coordinates so the weights are blended along with the points
+    let mut d: Vec<(f64, f64, f64)> = (0..=degree)
+        .map(|j| {
+            let (x, y) = points[j + span - degree];
+            let w = weights[j + span - degree];
+            (x * w, y * w, w)
+        })
+        .collect();
+    for r in 1..=degree {
+        for j in (r..=degree).rev() {
+            let left = knots[j + span - degree];
+            let right = knots[j + 1 + span - r];
+            let alpha = if right > left { (t - left) / (right - left) } else { 0.0 };
+            d[j] = (
+                (1.0 - alpha) * d[j - 1].0 + alpha * d[j].0,
+                (1.0 - alpha) * d[j - 1].1 + alpha * d[j].1,
+                (1.0 - alpha) * d[j - 1].2 + alpha * d[j].2,
+            );
+        }
+    }
+    let (x, y, w) = d[degree];
+    if w == 0.0 { (x, y) } else { (x / w, y / w) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dxf::Point;
+
+    fn assert_close(actual: (f64, f64), expected: (f64, f64)) {
+        assert!((actual.0 - expected.0).abs() < 1e-9 && (actual.1 - expected.1).abs() < 1e-9, "{actual:?} != {expected:?}");
+    }
+
+    #[test]
+    fn test_spline_points() {
+        // a quadratic rational bezier with the middle weight cos(45°) is exactly a quarter circle
+        let spline = Spline {
+            degree_of_curve: 2,
+            knot_values: vec![0.0, 0.0, 0.0, 1.0, 1.0, 1.0],
+            weight_values: vec![1.0, 0.5f64.sqrt(), 1.0],
+            control_points: vec![Point::new(1.0, 0.0, 0.0), Point::new(1.0, 1.0, 0.0), Point::new(0.0, 1.0, 0.0)],
+            ..Default::default()
+        };
+        let points = spline_points(&spline, 0.01);
+        assert_eq!(points.len(), 7);
+        assert_close(points[0], (1.0, 0.0));
+        assert_close(points[6], (0.0, 1.0));
+        for (x, y) in points {
+            assert!((x.hypot(y) - 1.0).abs() < 1e-9);
+        }
+
+        // without knots the curve is clamped to its end points
+        let spline = Spline {
+            degree_of_curve: 3,
+            control_points: (0..5).map(|i| Point::new(i as f64, (i % 2) as f64, 0.0)).collect(),
+            ..Default::default()
+        };
+        let points = spline_points(&spline, 0.01);
+        assert_close(points[0], (0.0, 0.0));
+        assert_close(*points.last().unwrap(), (4.0, 0.0));
+    }
+
+    #[test]
+    fn test_fit_point_spline() {
+        let fit_points = [(0.0, 0.0), (1.0, 1.0), (2.0, 0.0), (3.0, 1.0)];
+        let spline = Spline {
+            fit_points: fit_points.iter().map(|&(x, y)| Point::new(x, y, 0.0)).collect(),
+            ..Default::default()
+        };
+        for (i, &fit_point) in fit_points.iter().enumerate() {
+            assert!(spline_points(&spline, 0.01).iter().any(|&point| {
+                (point.0 - fit_point.0).abs() < 1e-9 && (point.1 - fit_point.1).abs() < 1e-9
+            }), "the curve misses fit point {i}");
+        }
+        // a coarser tolerance gives fewer points
+        assert!(spline_points(&spline, 0.1).len() < spline_points(&spline, 0.001).len());
+    }
+}
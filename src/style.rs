@@ -0,0 +1,270 @@
+use std::fmt::Write;
+use std::sync::Mutex;
+
+use dxf::entities::Entity;
+use dxf::objects::ObjectType;
+use dxf::{Color, DrawingItem, XDataItem};
+
+use crate::{color, linetype, PlotStyle, PlotStyleTable, RenderContext, SvgOptions};
+
+const LINEWEIGHT_BY_LAYER: i16 = -1;
+const TRANSPARENCY_BY_BLOCK: i32 = 0x0100_0000;
+const TRANSPARENCY_EXPLICIT: i32 = 0x0200_0000;
+
+/// The resolved appearance of an entity's stroke, which a `style_hook` can change.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Style {
+    /// Any SVG color, like `#FF0000`, also used to fill text and filled shapes
+    pub color: String,
+    /// The stroke width in drawing units
+    pub width: f64,
+    /// A `stroke-dasharray` value for the entity's linetype, None for continuous lines
+    pub dash_array: Option<String>,
+    /// Round line caps make the dots of a linetype visible
+    pub round_caps: bool,
+    /// From 0 for invisible to 1 for opaque, None for opaque
+    pub opacity: Option<f64>,
+}
+
+impl Style {
+    /// The style as SVG presentation attributes.
+    pub(crate) fn attributes(&self) -> String {
+        let mut attributes = format!("stroke=\"{}\" stroke-width=\"{}\"", self.color, self.width);
+        if let Some(dash_array) = &self.dash_array {
+            write!(attributes, " stroke-dasharray=\"{}\"", dash_array).unwrap();
+        }
+        if self.round_caps {
+            attributes.push_str(" stroke-linecap=\"round\"");
+        }
+        if let Some(opacity) = self.opacity {
+            write!(attributes, " stroke-opacity=\"{:.3}\" fill-opacity=\"{:.3}\"", opacity, opacity).unwrap();
+        }
+        attributes
+    }
+
+    /// The style as CSS declarations, for the class based output.
+    fn declarations(&self) -> String {
+        let mut declarations = format!("stroke:{};stroke-width:{}", self.color, self.width);
+        if let Some(dash_array) = &self.dash_array {
+            write!(declarations, ";stroke-dasharray:{}", dash_array).unwrap();
+        }
+        if self.round_caps {
+            declarations.push_str(";stroke-linecap:round");
+        }
+        if let Some(opacity) = self.opacity {
+            write!(declarations, ";stroke-opacity:{:.3};fill-opacity:{:.3}", opacity, opacity).unwrap();
+        }
+        declarations
+    }
+}
+
+/**
+Collects the distinct styles of a class based conversion. Each one becomes a single CSS rule,
+so elements only carry a short class instead of repeating their stroke attributes.
+*/
+#[derive(Default)]
+pub(crate) struct StyleSheet {
+    rules: Mutex<Vec<String>>,
+}
+
+impl StyleSheet {
+    /// A `class` attribute with the given classes and the class of the style.
+    pub(crate) fn class_attribute(&self, style: &Style, classes: &str) -> String {
+        let declarations = style.declarations();
+        let mut rules = self.rules.lock().unwrap();
+        let index = match rules.iter().position(|rule| *rule == declarations) {
+            Some(index) => index,
+            None => {
+                rules.push(declarations);
+                rules.len() - 1
+            }
+        };
+        format!("class=\"{} s{}\"", classes, index)
+    }
+
+    /// The `<style>` element holding every collected rule.
+    pub(crate) fn style_element(&self) -> String {
+        let rules: String = self.rules.lock().unwrap()
+            .iter()
+            .enumerate()
+            .map(|(index, declarations)| format!(".s{}{{{}}}", index, declarations))
+            .collect();
+        format!("<style>{}</style>", rules)
+    }
+}
+
+/// Resolves the color, width, linetype and transparency of an entity, then applies the plot style table.
+pub(crate) fn entity_style(entity: &Entity, ctx: &RenderContext) -> Style {
+    let mut style = Style {
+        color: entity_color(entity, ctx),
+        width: entity_stroke_width(entity, ctx),
+        dash_array: None,
+        round_caps: false,
+        opacity: entity_opacity(entity, ctx),
+    };
+    if let Some(pattern) = entity_line_type_pattern(entity, ctx) {
+        let scale = ctx.drawing.map_or(1.0, |drawing| drawing.header.line_type_scale) * entity.common.line_type_scale;
+        style.dash_array = linetype::dash_array(pattern, scale, ctx.precision);
+        style.round_caps = style.dash_array.is_some() && linetype::has_dots(pattern);
+    }
+    if let Some(plot_style) = entity_plot_style(entity, ctx) {
+        if let Some(color) = &plot_style.color {
+            style.color = color.clone();
+        }
+        // like the entity's own lineweight, it's only drawn once millimetres can be converted to drawing units
+        if let (Some(lineweight), Some(scale)) = (plot_style.lineweight, ctx.options.lineweight_scale) {
+            style.width = lineweight * scale;
+        }
+        if let Some(screening) = plot_style.screening {
+            // plots are screened against white paper, which is the same as fading them out
+            let screening = f64::from(screening.min(100)) / 100.0;
+            style.opacity = Some(style.opacity.unwrap_or(1.0) * screening);
+        }
+    }
+    match &ctx.options.style_hook {
+        Some(style_hook) => style_hook(entity, style),
+        None => style,
+    }
+}
+
+/// The stroke color of an entity: its color name if it has one, otherwise its ACI color.
+/// ByLayer colors are looked up in the drawing's layer table when a drawing is available.
+fn entity_color(entity: &Entity, ctx: &RenderContext) -> String {
+    if !entity.common.color_name.trim().is_empty() {
+        return entity.common.color_name.clone();
+    }
+    // `<use>` elements set `color` to the color of their insert, so ByBlock entities follow it
+    if entity.common.color.is_by_block() && ctx.in_block {
+        return "currentColor".to_string();
+    }
+    aci_color(resolved_color(entity, ctx), ctx.options)
+}
+
+/// The entity's color with ByLayer resolved to the color of its layer.
+fn resolved_color<'a>(entity: &'a Entity, ctx: &RenderContext<'a>) -> &'a Color {
+    let color = &entity.common.color;
+    if color.is_by_layer() {
+        if let Some(layer) = ctx.layer(&entity.common.layer) {
+            return &layer.color;
+        }
+    }
+    color
+}
+
+/// ACI 7 is drawn black on light backgrounds and white on dark ones, so it uses `default_color`,
+/// as do colors that don't resolve to an index.
+fn aci_color(color: &Color, options: &SvgOptions) -> String {
+    match color.index() {
+        Some(7) | None => options.default_color.clone(),
+        Some(index) => color::aci_to_hex(index),
+    }
+}
+
+/// Looks up the entity in the plot style table: by color for CTB tables, by plot style name for STB tables.
+fn entity_plot_style<'a>(entity: &Entity, ctx: &RenderContext<'a>) -> Option<&'a PlotStyle> {
+    match ctx.options.plot_style_table.as_ref()? {
+        PlotStyleTable::ColorDependent(styles) => styles.get(&resolved_color(entity, ctx).index()?),
+        PlotStyleTable::Named(styles) => {
+            let name = plot_style_name(entity, ctx)?;
+            styles.iter().find(|(style_name, _)| style_name.eq_ignore_ascii_case(name)).map(|(_, style)| style)
+        }
+    }
+}
+
+/// Named plot styles are referenced by handle; their names are the keys of the plot style dictionary.
+fn plot_style_name<'a>(entity: &Entity, ctx: &RenderContext<'a>) -> Option<&'a str> {
+    let drawing = ctx.drawing?;
+    let Some(DrawingItem::Object(plot_style)) = entity.common.get_plot_style(drawing) else {
+        return None;
+    };
+    drawing.objects().find_map(|object| match &object.specific {
+        ObjectType::Dictionary(dictionary) => dictionary
+            .value_handles
+            .iter()
+            .find(|(_, handle)| **handle == plot_style.common.handle)
+            .map(|(name, _)| name.as_str()),
+        _ => None,
+    })
+}
+
+/// The stroke width of an entity. Lineweights are stored in hundredths of a millimetre and are only
+/// used when `lineweight_scale` is set; ByBlock, default and hairline (0) lineweights use `stroke_width`.
+fn entity_stroke_width(entity: &Entity, ctx: &RenderContext) -> f64 {
+    let options = ctx.options;
+    let Some(scale) = options.lineweight_scale else {
+        return options.stroke_width;
+    };
+    let mut lineweight = entity.common.lineweight_enum_value;
+    if lineweight == LINEWEIGHT_BY_LAYER {
+        if let Some(layer) = ctx.layer(&entity.common.layer) {
+            lineweight = layer.line_weight.get_raw_value();
+        }
+    }
+    if lineweight > 0 {
+        f64::from(lineweight) / 100.0 * scale
+    } else {
+        options.stroke_width
+    }
+}
+
+/// The opacity of an entity with an explicit or ByLayer transparency. None means opaque, or ByBlock.
+fn entity_opacity(entity: &Entity, ctx: &RenderContext) -> Option<f64> {
+    let transparency = entity.common.transparency;
+    if transparency & TRANSPARENCY_BY_BLOCK != 0 {
+        return None;
+    }
+    if let Some(opacity) = transparency_to_opacity(transparency) {
+        return Some(opacity);
+    }
+    // layers keep their transparency in extended data
+    ctx.layer(&entity.common.layer)?
+        .x_data
+        .iter()
+        .filter(|x_data| x_data.application_name == "AcCmTransparency")
+        .flat_map(|x_data| &x_data.items)
+        .find_map(|item| match item {
+            XDataItem::Long(value) => transparency_to_opacity(*value),
+            _ => None,
+        })
+}
+
+/// Explicit transparencies are stored as `0x02000000 | alpha`, where an alpha of 255 is opaque.
+fn transparency_to_opacity(transparency: i32) -> Option<f64> {
+    if transparency & TRANSPARENCY_EXPLICIT == 0 {
+        return None;
+    }
+    let opacity = f64::from(transparency & 0xFF) / 255.0;
+    (opacity < 1.0).then_some(opacity)
+}
+
+/// The dash pattern of the linetype an entity is drawn with, following ByLayer to the entity's layer.
+/// Linetypes missing from the drawing are looked up in the `line_type_library` option.
+/// ByBlock and continuous linetypes have no pattern, so they return None.
+fn entity_line_type_pattern<'a>(entity: &'a Entity, ctx: &RenderContext<'a>) -> Option<&'a [f64]> {
+    let mut name = entity.common.line_type_name.as_str();
+    if name.is_empty() || name.eq_ignore_ascii_case("BYLAYER") {
+        name = &ctx.layer(&entity.common.layer)?.line_type_name;
+    }
+    if name.eq_ignore_ascii_case("BYBLOCK") || name.eq_ignore_ascii_case("CONTINUOUS") {
+        return None;
+    }
+    let line_type = ctx.drawing.and_then(|drawing| {
+        drawing.line_types().find(|line_type| line_type.name.eq_ignore_ascii_case(name))
+    });
+    match line_type {
+        Some(line_type) => Some(&line_type.dash_dot_space_lengths),
+        None => ctx.options.line_type_library.as_ref()?.pattern(name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transparency_to_opacity() {
+        assert_eq!(transparency_to_opacity(0), None);
+        assert_eq!(transparency_to_opacity(0x0200_00FF), None);
+        assert_eq!(transparency_to_opacity(0x0200_0033), Some(0.2));
+    }
+}
@@ -0,0 +1,530 @@
+use std::fmt::Write;
+use std::iter::Peekable;
+use std::str::Chars;
+
+use dxf::entities::{Attribute, Text};
+use dxf::enums::{HorizontalTextJustification, VerticalTextJustification};
+use dxf::Drawing;
+use encoding_rs::{Encoding, BIG5, EUC_KR, GBK, SHIFT_JIS};
+
+use crate::{escape_xml_text, FontMap, ShxFont};
+
+/// AutoCAD's default text height, used when an entity doesn't store one
+pub(crate) const DEFAULT_TEXT_HEIGHT: f64 = 2.5;
+/// The average width of a character, in text heights, used where there are no font metrics
+pub(crate) const CHARACTER_WIDTH: f64 = 0.6;
+/// How far glyphs reach below the baseline, in text heights, used where there are no font metrics
+const DESCENT: f64 = 0.2;
+/// Enough decimals for a layout not to be visibly rounded, where it isn't written out
+pub(crate) const UNROUNDED: usize = 12;
+
+/// The font and default shape of text from its style in the STYLE table.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct TextStyle {
+    pub font_family: Option<String>,
+    pub width_factor: f64,
+    /// Slant of the glyphs in degrees, positive leaning right
+    pub oblique_angle: f64,
+    /// The loaded SHX font to draw the text's strokes with, instead of writing `<text>`
+    pub stroke_font: Option<ShxFont>,
+}
+
+impl TextStyle {
+    /// Looks up a text style by name, an empty name being AutoCAD's `Standard` style.
+    pub(crate) fn resolve(name: &str, drawing: Option<&Drawing>, fonts: &FontMap) -> Self {
+        let name = if name.is_empty() { "Standard" } else { name };
+        let Some(style) = drawing.and_then(|drawing| drawing.styles().find(|style| style.name.eq_ignore_ascii_case(name)))
+        else {
+            return Self::default();
+        };
+        TextStyle {
+            font_family: fonts.family(&style.primary_font_file_name),
+            width_factor: style.width_factor,
+            oblique_angle: style.oblique_angle,
+            stroke_font: fonts.shx(&style.primary_font_file_name).cloned(),
+        }
+    }
+
+    /// The `font-family` attribute, if the style names a font.
+    pub(crate) fn font_attribute(&self) -> String {
+        self.font_family
+            .as_ref()
+            .map(|family| format!(r#" font-family="{}""#, escape_xml_text(family)))
+            .unwrap_or_default()
+    }
+}
+
+/// The text an ATTRIB shows, which is placed and shaped like a TEXT.
+pub(crate) fn attribute_text(attribute: &Attribute) -> Text {
+    Text {
+        thickness: attribute.thickness,
+        location: attribute.location.clone(),
+        text_height: attribute.text_height,
+        value: attribute.value.clone(),
+        rotation: attribute.rotation,
+        relative_x_scale_factor: attribute.relative_x_scale_factor,
+        oblique_angle: attribute.oblique_angle,
+        text_style_name: attribute.text_style_name.clone(),
+        text_generation_flags: attribute.text_generation_flags,
+        second_alignment_point: attribute.second_alignment_point.clone(),
+        normal: attribute.normal.clone(),
+        horizontal_text_justification: attribute.horizontal_text_justification,
+        vertical_text_justification: attribute.vertical_text_justification,
+    }
+}
+
+/// Text between the `%%u` and `%%o` codes that toggle its underline and overline.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct TextRun {
+    pub text: String,
+    pub underline: bool,
+    pub overline: bool,
+}
+
+/// The character a `%%` control code stands for: a diameter, degree, plus-minus or percent sign.
+pub(crate) fn control_code_symbol(code: char) -> Option<char> {
+    match code.to_ascii_lowercase() {
+        'c' => Some('Ø'),
+        'd' => Some('°'),
+        'p' => Some('±'),
+        '%' => Some('%'),
+        _ => None,
+    }
+}
+
+/// True for characters XML allows in text: anything from the space on, tabs and line breaks.
+pub(crate) fn is_xml_char(c: char) -> bool {
+    c >= ' ' || matches!(c, '\t' | '\n' | '\r')
+}
+
+/**
+Reads a `\U+XXXX` unicode escape or a `\M+nXXXX` multibyte escape, a double byte character in one of
+the Asian code pages numbered `n`, after the backslash and `code`. The escape is only consumed from
+`chars` if it's well formed and isn't a control character XML can't hold.
+*/
+pub(crate) fn read_escape(code: char, chars: &mut Peekable<Chars>) -> Option<char> {
+    let mut lookahead = chars.clone();
+    if lookahead.next()? != '+' {
+        return None;
+    }
+    let encoding = match code {
+        'U' => None,
+        'M' => Some(multibyte_encoding(lookahead.next()?)?),
+        _ => return None,
+    };
+    let hex: String = lookahead.by_ref().take(4).collect();
+    if hex.len() != 4 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let value = u32::from_str_radix(&hex, 16).ok()?;
+    let decoded = match encoding {
+        None => char::from_u32(value)?,
+        Some(encoding) => {
+            let bytes = (value as u16).to_be_bytes();
+            encoding.decode_without_bom_handling_and_without_replacement(&bytes)?.chars().next()?
+        }
+    };
+    if !is_xml_char(decoded) {
+        return None;
+    }
+    *chars = lookahead;
+    Some(decoded)
+}
+
+/// The code pages of `\M+` escapes: Japanese, traditional Chinese, Korean (Johab isn't supported) and simplified Chinese.
+fn multibyte_encoding(number: char) -> Option<&'static Encoding> {
+    match number {
+        '1' => Some(SHIFT_JIS),
+        '2' => Some(BIG5),
+        '3' => Some(EUC_KR),
+        '5' => Some(GBK),
+        _ => None,
+    }
+}
+
+/// Splits text into runs by its underline and overline codes, replacing the other `%%` codes with the
+/// characters they stand for (`%%nnn` being a character code). Unknown codes, and character codes of
+/// control characters XML can't hold, are dropped. `\U+` and
+/// `\M+` escapes are decoded too.
+pub(crate) fn decode_control_codes(value: &str) -> Vec<TextRun> {
+    let mut runs = vec![TextRun::default()];
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        let run = runs.last_mut().unwrap();
+        if c == '\\' {
+            let mut lookahead = chars.clone();
+            if let Some(decoded) = lookahead.next().and_then(|code| read_escape(code, &mut lookahead)) {
+                chars = lookahead;
+                run.text.push(decoded);
+                continue;
+            }
+        }
+        if c != '%' || chars.peek() != Some(&'%') {
+            run.text.push(c);
+            continue;
+        }
+        chars.next();
+        let Some(code) = chars.next() else { break };
+        if let Some(symbol) = control_code_symbol(code) {
+            run.text.push(symbol);
+        } else if let Some(digit) = code.to_digit(10) {
+            let mut number = digit;
+            for _ in 0..2 {
+                let Some(digit) = chars.peek().and_then(|c| c.to_digit(10)) else { break };
+                number = number * 10 + digit;
+                chars.next();
+            }
+            run.text.extend(char::from_u32(number).filter(|c| is_xml_char(*c)));
+        } else if matches!(code, 'u' | 'U' | 'o' | 'O') {
+            let mut toggled = TextRun { text: String::new(), ..run.clone() };
+            if code.eq_ignore_ascii_case(&'u') {
+                toggled.underline = !toggled.underline;
+            } else {
+                toggled.overline = !toggled.overline;
+            }
+            if run.text.is_empty() {
+                *run = toggled;
+            } else {
+                runs.push(toggled);
+            }
+        }
+    }
+    runs.retain(|run| !run.text.is_empty());
+    runs
+}
+
+/// Text with its `%%` codes decoded, without underlines and overlines.
+pub(crate) fn plain_text(value: &str) -> String {
+    decode_control_codes(value).into_iter().map(|run| run.text).collect()
+}
+
+/// Where and how a TEXT is drawn, worked out from its justification and style.
+pub(crate) struct TextLayout {
+    pub x: f64,
+    pub y: f64,
+    /// Degrees counterclockwise
+    pub rotation: f64,
+    pub height: f64,
+    /// 1 for fitted text, which is stretched to `fit_length` instead
+    pub width_factor: f64,
+    /// Slant of the glyphs in degrees, positive leaning right
+    pub oblique_angle: f64,
+    pub anchor: Option<&'static str>,
+    pub baseline: Option<&'static str>,
+    /// The length aligned and fit text is stretched to
+    pub fit_length: Option<f64>,
+    /// If true the fitted text keeps its proportions (aligned), otherwise its height (fit)
+    pub aligned: bool,
+}
+
+impl TextLayout {
+    /// How far glyphs drawn from the origin along the baseline are moved to be justified, for a line
+    /// `advance` long with glyphs reaching `descent` below the baseline.
+    pub(crate) fn path_offset(&self, advance: f64, descent: f64) -> (f64, f64) {
+        let x = match self.anchor {
+            Some("middle") => -advance / 2.0,
+            Some("end") => -advance,
+            _ => 0.0,
+        };
+        let y = match self.baseline {
+            Some("central") => -self.height / 2.0,
+            Some("hanging") => -self.height,
+            Some("text-after-edge") => descent,
+            _ => 0.0,
+        };
+        (x, y)
+    }
+
+    /// The transform of a `<path>` of glyphs drawn Y up, `advance` long, which unlike `<text>` needs no flip.
+    pub(crate) fn path_transform(&self, advance: f64, precision: usize) -> String {
+        let mut transform = format!("translate({:.p$} {:.p$})", self.x, self.y, p = precision);
+        if self.rotation != 0.0 {
+            write!(transform, " rotate({:.p$})", self.rotation, p = precision).unwrap();
+        }
+        let stretch = self.fit_length.map_or(self.width_factor, |length| length / advance);
+        if stretch != 1.0 {
+            let vertical = if self.aligned { stretch } else { 1.0 };
+            write!(transform, " scale({:.p$} {:.p$})", stretch, vertical, p = precision).unwrap();
+        }
+        if self.oblique_angle != 0.0 {
+            write!(transform, " skewX({:.p$})", self.oblique_angle, p = precision).unwrap();
+        }
+        transform
+    }
+}
+
+/**
+Works out how a TEXT is sized, rotated and stretched by its width factor.
+Justified text is placed at its alignment point. Aligned and fit text is fitted between its two points,
+aligned text keeping its proportions and fit text keeping its height.
+*/
+pub(crate) fn layout(text: &Text, style: &TextStyle, precision: usize) -> TextLayout {
+    let mut height = if text.text_height > 0.0 { text.text_height } else { DEFAULT_TEXT_HEIGHT };
+    let horizontal = text.horizontal_text_justification;
+    let vertical = text.vertical_text_justification;
+    let justified = horizontal != HorizontalTextJustification::Left || vertical != VerticalTextJustification::Baseline;
+    let start = &text.location;
+    let end = &text.second_alignment_point;
+    let (dx, dy) = (end.x - start.x, end.y - start.y);
+    let fit_length = (dx * dx + dy * dy).sqrt();
+    let fitted = matches!(horizontal, HorizontalTextJustification::Aligned | HorizontalTextJustification::Fit)
+        && fit_length > f64::EPSILON;
+    let aligned = fitted && horizontal == HorizontalTextJustification::Aligned;
+
+    let (position, rotation) = match (fitted, justified) {
+        (true, _) => (start, dy.atan2(dx).to_degrees()),
+        (false, true) => (end, text.rotation),
+        (false, false) => (start, text.rotation),
+    };
+    // the entity's own width factor and slant override its style's
+    let width_factor = [text.relative_x_scale_factor, style.width_factor]
+        .into_iter()
+        .find(|factor| *factor > 0.0)
+        .filter(|_| !fitted)
+        .unwrap_or(1.0);
+    let oblique_angle = if text.oblique_angle != 0.0 { text.oblique_angle } else { style.oblique_angle };
+    let anchor = match horizontal {
+        HorizontalTextJustification::Center | HorizontalTextJustification::Middle => Some("middle"),
+        HorizontalTextJustification::Right => Some("end"),
+        HorizontalTextJustification::Left | HorizontalTextJustification::Aligned | HorizontalTextJustification::Fit => None,
+    };
+    // horizontal Middle centers the text both ways
+    let baseline = match (horizontal, vertical) {
+        (HorizontalTextJustification::Middle, _) | (_, VerticalTextJustification::Middle) => Some("central"),
+        (_, VerticalTextJustification::Top) => Some("hanging"),
+        (_, VerticalTextJustification::Bottom) => Some("text-after-edge"),
+        (_, VerticalTextJustification::Baseline) => None,
+    };
+    if aligned {
+        let characters = plain_text(&text.value).chars().count().max(1) as f64;
+        let rounding = 10f64.powi(precision as i32);
+        height = (fit_length / (characters * CHARACTER_WIDTH) * rounding).round() / rounding;
+    }
+    TextLayout {
+        x: position.x,
+        y: position.y,
+        rotation,
+        height,
+        width_factor,
+        oblique_angle,
+        anchor,
+        baseline,
+        fit_length: Some(fit_length).filter(|_| fitted),
+        aligned,
+    }
+}
+
+/**
+The corners of the box a TEXT covers in the drawing. `metrics` are the length of its line and how far its
+glyphs reach below the baseline, measured in its font before stretching. Without them the line is
+estimated to be `CHARACTER_WIDTH` text heights per character long.
+*/
+pub(crate) fn text_corners(text: &Text, style: &TextStyle, metrics: Option<(f64, f64)>) -> [(f64, f64); 4] {
+    let layout = layout(text, style, UNROUNDED);
+    let characters = plain_text(&text.value).chars().count() as f64;
+    let (advance, descent) = metrics.unwrap_or((characters * CHARACTER_WIDTH * layout.height, DESCENT * layout.height));
+    if advance <= 0.0 {
+        return [(layout.x, layout.y); 4];
+    }
+    let (x, y) = layout.path_offset(advance, descent);
+    let stretch = layout.fit_length.map_or(layout.width_factor, |length| length / advance);
+    let vertical = if layout.aligned { stretch } else { 1.0 };
+    let (sin, cos) = layout.rotation.to_radians().sin_cos();
+    [(x, y - descent), (x + advance, y - descent), (x + advance, y + layout.height), (x, y + layout.height)].map(|(u, v)| {
+        let (u, v) = (u * stretch, v * vertical);
+        (layout.x + u * cos - v * sin, layout.y + u * sin + v * cos)
+    })
+}
+
+/// Renders a TEXT as a `<text>` placed by its `layout`.
+pub(crate) fn render_text(text: &Text, style: &TextStyle, color: &str, precision: usize, svg: &mut String) {
+    let layout = layout(text, style, precision);
+    let mut transform = format!("translate({:.p$} {:.p$})", layout.x, layout.y, p = precision);
+    if layout.rotation != 0.0 {
+        write!(transform, " rotate({:.p$})", layout.rotation, p = precision).unwrap();
+    }
+    // the output's Y axis is flipped, so the glyphs are flipped back to stay upright
+    if layout.width_factor != 1.0 {
+        write!(transform, " scale({:.p$} -1)", layout.width_factor, p = precision).unwrap();
+    } else {
+        transform.push_str(" scale(1 -1)");
+    }
+    if layout.oblique_angle != 0.0 {
+        write!(transform, " skewX({:.p$})", -layout.oblique_angle, p = precision).unwrap();
+    }
+
+    let mut attributes = style.font_attribute();
+    if let Some(anchor) = layout.anchor {
+        write!(attributes, r#" text-anchor="{}""#, anchor).unwrap();
+    }
+    if let Some(baseline) = layout.baseline {
+        write!(attributes, r#" dominant-baseline="{}""#, baseline).unwrap();
+    }
+    if let Some(fit_length) = layout.fit_length {
+        write!(
+            attributes,
+            r#" textLength="{:.p$}" lengthAdjust="spacingAndGlyphs""#,
+            fit_length,
+            p = precision
+        ).unwrap();
+    }
+
+    let content: String = decode_control_codes(&text.value)
+        .iter()
+        .map(|run| {
+            let decorations: Vec<&str> = [(run.underline, "underline"), (run.overline, "overline")]
+                .iter()
+                .filter(|(on, _)| *on)
+                .map(|(_, decoration)| *decoration)
+                .collect();
+            if decorations.is_empty() {
+                escape_xml_text(&run.text)
+            } else {
+                format!(r#"<tspan text-decoration="{}">{}</tspan>"#, decorations.join(" "), escape_xml_text(&run.text))
+            }
+        })
+        .collect();
+    write!(
+        svg,
+        r#"<text transform="{}" font-size="{:.p$}"{} fill="{}" stroke="none">{}</text>"#,
+        transform,
+        layout.height,
+        attributes,
+        color,
+        content,
+        p = precision
+    ).unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dxf::Point;
+
+    #[test]
+    fn test_render_text() {
+        let text = Text {
+            location: Point::new(1.0, 2.0, 0.0),
+            text_height: 3.5,
+            value: "A&B".to_string(),
+            rotation: 30.0,
+            relative_x_scale_factor: 0.8,
+            ..Default::default()
+        };
+        let mut svg = String::new();
+        render_text(&text, &TextStyle::default(), "red", 3, &mut svg);
+        assert_eq!(
+            svg,
+            r#"<text transform="translate(1.000 2.000) rotate(30.000) scale(0.800 -1)" font-size="3.500" fill="red" stroke="none">A&amp;B</text>"#
+        );
+
+        let mut svg = String::new();
+        render_text(&Text { rotation: 12.3456, ..text }, &TextStyle::default(), "red", 1, &mut svg);
+        assert!(svg.starts_with(r#"<text transform="translate(1.0 2.0) rotate(12.3) scale(0.8 -1)" font-size="3.5""#));
+    }
+
+    #[test]
+    fn test_control_codes() {
+        assert_eq!(plain_text("%%c20 %%D45%%P0.1 100%%% %%177%%x"), "Ø20 °45±0.1 100% ±");
+        assert_eq!(plain_text("A%%1B%%0C%%9D"), "ABC\tD");
+        assert_eq!(plain_text(r"A\U+0000B\U+001FC\U+000AD"), "A\\U+0000B\\U+001FC\nD");
+        assert_eq!(plain_text(r"90\U+00B0 \M+182A0 \U+12 C:\Users"), r"90° あ \U+12 C:\Users");
+        assert_eq!(
+            decode_control_codes("%%uNOTE%%u: see %%o%%uA"),
+            [
+                TextRun { text: "NOTE".to_string(), underline: true, overline: false },
+                TextRun { text: ": see ".to_string(), underline: false, overline: false },
+                TextRun { text: "A".to_string(), underline: true, overline: true },
+            ]
+        );
+
+        let text = Text { value: "%%uA%%u%%d".to_string(), ..Default::default() };
+        let mut svg = String::new();
+        render_text(&text, &TextStyle::default(), "red", 3, &mut svg);
+        assert!(svg.ends_with(r#"><tspan text-decoration="underline">A</tspan>°</text>"#));
+    }
+
+    #[test]
+    fn test_text_style() {
+        let mut drawing = Drawing::new();
+        drawing.add_style(dxf::tables::Style {
+            name: "NOTES".to_string(),
+            primary_font_file_name: "romans.shx".to_string(),
+            width_factor: 0.9,
+            oblique_angle: 15.0,
+            ..Default::default()
+        });
+        let style = TextStyle::resolve("notes", Some(&drawing), &FontMap::default());
+        assert_eq!(style.font_family.as_deref(), Some("Arial, Helvetica, sans-serif"));
+        assert_eq!(TextStyle::resolve("", Some(&drawing), &FontMap::default()), TextStyle::default());
+
+        let text = Text { value: "A".to_string(), ..Default::default() };
+        let mut svg = String::new();
+        render_text(&text, &style, "red", 3, &mut svg);
+        assert!(svg.starts_with(
+            r#"<text transform="translate(0.000 0.000) scale(0.900 -1) skewX(-15.000)" font-size="2.500" font-family="Arial, Helvetica, sans-serif""#
+        ));
+    }
+
+    #[test]
+    fn test_text_corners() {
+        let mut text = Text {
+            location: Point::new(1.0, 2.0, 0.0),
+            second_alignment_point: Point::new(5.0, 2.0, 0.0),
+            text_height: 1.0,
+            value: "AB".to_string(),
+            horizontal_text_justification: HorizontalTextJustification::Right,
+            vertical_text_justification: VerticalTextJustification::Top,
+            ..Default::default()
+        };
+        let close = |corners: [(f64, f64); 4], expected: [(f64, f64); 4]| {
+            corners.iter().zip(expected).all(|((x, y), (ex, ey))| (x - ex).abs() < 1e-9 && (y - ey).abs() < 1e-9)
+        };
+        // hanging from its top right corner, two characters wide
+        let corners = text_corners(&text, &TextStyle::default(), None);
+        assert!(close(corners, [(3.8, 0.8), (5.0, 0.8), (5.0, 2.0), (3.8, 2.0)]), "{:?}", corners);
+
+        text.horizontal_text_justification = HorizontalTextJustification::Left;
+        text.vertical_text_justification = VerticalTextJustification::Baseline;
+        text.rotation = 90.0;
+        let corners = text_corners(&text, &TextStyle::default(), Some((3.0, 0.0)));
+        assert!(close(corners, [(1.0, 2.0), (1.0, 5.0), (0.0, 5.0), (0.0, 2.0)]), "{:?}", corners);
+    }
+
+    #[test]
+    fn test_text_justification() {
+        let mut text = Text {
+            location: Point::new(1.0, 2.0, 0.0),
+            second_alignment_point: Point::new(5.0, 2.0, 0.0),
+            text_height: 1.0,
+            value: "AB".to_string(),
+            horizontal_text_justification: HorizontalTextJustification::Right,
+            vertical_text_justification: VerticalTextJustification::Top,
+            ..Default::default()
+        };
+        let mut svg = String::new();
+        render_text(&text, &TextStyle::default(), "red", 3, &mut svg);
+        assert!(svg.starts_with(
+            r#"<text transform="translate(5.000 2.000) scale(1 -1)" font-size="1.000" text-anchor="end" dominant-baseline="hanging""#
+        ));
+
+        text.horizontal_text_justification = HorizontalTextJustification::Middle;
+        text.vertical_text_justification = VerticalTextJustification::Baseline;
+        let mut svg = String::new();
+        render_text(&text, &TextStyle::default(), "red", 3, &mut svg);
+        assert!(svg.contains(r#"text-anchor="middle" dominant-baseline="central""#));
+
+        text.horizontal_text_justification = HorizontalTextJustification::Fit;
+        text.second_alignment_point = Point::new(1.0, 6.0, 0.0);
+        let mut svg = String::new();
+        render_text(&text, &TextStyle::default(), "red", 3, &mut svg);
+        assert!(svg.starts_with(
+            r#"<text transform="translate(1.000 2.000) rotate(90.000) scale(1 -1)" font-size="1.000" textLength="4.000" lengthAdjust="spacingAndGlyphs""#
+        ));
+
+        text.horizontal_text_justification = HorizontalTextJustification::Aligned;
+        let mut svg = String::new();
+        render_text(&text, &TextStyle::default(), "red", 3, &mut svg);
+        assert!(svg.contains(r#"font-size="3.333""#));
+    }
+}
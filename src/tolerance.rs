@@ -0,0 +1,176 @@
+use std::fmt::Write;
+
+use dxf::entities::Tolerance;
+use dxf::Drawing;
+
+use crate::dimension::DimensionStyle;
+use crate::escape_xml_text;
+use crate::text::{self, CHARACTER_WIDTH};
+
+/// The letters of AutoCAD's GDT font and the geometric tolerancing symbols they draw.
+const GDT_SYMBOLS: &[(char, char)] = &[
+    ('a', '∠'),
+    ('b', '⟂'),
+    ('c', '⏥'),
+    ('d', '⌓'),
+    ('e', '○'),
+    ('f', '∥'),
+    ('g', '⌭'),
+    ('h', '↗'),
+    ('i', '⌯'),
+    ('j', '⌖'),
+    ('k', '⌒'),
+    ('l', 'Ⓛ'),
+    ('m', 'Ⓜ'),
+    ('n', '⌀'),
+    ('p', 'Ⓟ'),
+    ('r', '◎'),
+    ('s', 'Ⓢ'),
+    ('t', '⌰'),
+    ('u', '⏤'),
+];
+
+/**
+Renders a TOLERANCE as a feature control frame: a row of boxes for each line of its text, holding the
+geometric characteristic symbol, the tolerance and the datum references. The frame is twice as high as
+the text of its dimension style, with its first row centered on the insertion point.
+*/
+pub(crate) fn render_tolerance(
+    tolerance: &Tolerance,
+    drawing: Option<&Drawing>,
+    stroke_attr: &str,
+    color: &str,
+    precision: usize,
+    svg: &mut String,
+) {
+    let rows = frame_cells(&tolerance.display_text);
+    if rows.iter().all(|row| row.is_empty()) {
+        return;
+    }
+    let height = DimensionStyle::named(&tolerance.dimension_style_name, drawing).text_height;
+    let row_height = 2.0 * height;
+    let direction = &tolerance.direction_vector;
+    let mut transform = format!(
+        "translate({:.p$} {:.p$})",
+        tolerance.insertion_point.x,
+        tolerance.insertion_point.y,
+        p = precision
+    );
+    if direction.y != 0.0 || direction.x < 0.0 {
+        write!(transform, " rotate({:.p$})", direction.y.atan2(direction.x).to_degrees(), p = precision).unwrap();
+    }
+
+    write!(svg, r#"<g transform="{}">"#, transform).unwrap();
+    for (index, row) in rows.iter().enumerate() {
+        let bottom = -row_height / 2.0 - row_height * index as f64;
+        let mut x = 0.0;
+        for cell in row {
+            let width = (cell.chars().count() as f64 * CHARACTER_WIDTH * height + height).max(row_height);
+            write!(
+                svg,
+                r#"<rect x="{:.p$}" y="{:.p$}" width="{:.p$}" height="{:.p$}" {} fill="none" />"#,
+                x,
+                bottom,
+                width,
+                row_height,
+                stroke_attr,
+                p = precision
+            ).unwrap();
+            // the output's Y axis is flipped, so the text is flipped back to stay upright
+            write!(
+                svg,
+                r#"<text transform="translate({:.p$} {:.p$}) scale(1 -1)" font-size="{:.p$}" text-anchor="middle" dominant-baseline="central" fill="{}" stroke="none">{}</text>"#,
+                x + width / 2.0,
+                bottom + row_height / 2.0,
+                height,
+                color,
+                escape_xml_text(cell),
+                p = precision
+            ).unwrap();
+            x += width;
+        }
+    }
+    svg.push_str("</g>");
+}
+
+/// The cells of each row of a frame. Rows are separated by `^J` and cells by `%%v`; empty cells aren't drawn.
+fn frame_cells(display_text: &str) -> Vec<Vec<String>> {
+    display_text
+        .split("^J")
+        .map(|row| {
+            row.split("%%v")
+                .flat_map(|cells| cells.split("%%V"))
+                .map(decode_cell)
+                .filter(|cell| !cell.trim().is_empty())
+                .collect()
+        })
+        .collect()
+}
+
+/// The text of a cell, with the letters written in the GDT font (`{\Fgdt;j}`) replaced by their symbols
+/// and other formatting codes removed.
+fn decode_cell(cell: &str) -> String {
+    let mut decoded = String::new();
+    let mut gdt = false;
+    let mut stack = vec![];
+    let mut chars = cell.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => stack.push(gdt),
+            '}' => gdt = stack.pop().unwrap_or(false),
+            '\\' => match chars.next() {
+                Some('f' | 'F') => {
+                    let font: String = chars.by_ref().take_while(|&c| c != ';').collect();
+                    gdt = font.split('|').next().is_some_and(|name| name.trim().eq_ignore_ascii_case("gdt"));
+                }
+                Some('H' | 'C' | 'c' | 'A' | 'Q' | 'T' | 'W' | 'p') => {
+                    chars.by_ref().take_while(|&c| c != ';').for_each(drop);
+                }
+                Some(code) => decoded.push(code),
+                None => {}
+            },
+            _ if gdt => decoded.push(
+                GDT_SYMBOLS
+                    .iter()
+                    .find(|(letter, _)| *letter == c.to_ascii_lowercase())
+                    .map_or(c, |(_, symbol)| *symbol),
+            ),
+            _ => decoded.push(c),
+        }
+    }
+    text::plain_text(&decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dxf::{Point, Vector};
+
+    #[test]
+    fn test_frame_cells() {
+        let cells = frame_cells(r"{\Fgdt;j}%%v{\Fgdt;n}0.05{\Fgdt;m}%%vA%%vB%%v^J{\Fgdt;f}%%v0.1%%vA");
+        assert_eq!(cells, [vec!["⌖", "⌀0.05Ⓜ", "A", "B"], vec!["∥", "0.1", "A"]]);
+    }
+
+    #[test]
+    fn test_render_tolerance() {
+        let tolerance = Tolerance {
+            insertion_point: Point::new(10.0, 20.0, 0.0),
+            display_text: r"{\Fgdt;u}%%v0.2".to_string(),
+            ..Default::default()
+        };
+        let mut svg = String::new();
+        render_tolerance(&tolerance, None, r#"stroke="red""#, "red", 3, &mut svg);
+        assert!(svg.starts_with(concat!(
+            r#"<g transform="translate(10.000 20.000)">"#,
+            r#"<rect x="0.000" y="-2.500" width="5.000" height="5.000" stroke="red" fill="none" />"#
+        )));
+        assert!(svg.contains(r#"<rect x="5.000" y="-2.500" width="7.000" height="5.000""#));
+        assert!(svg.contains(">⏤</text>") && svg.ends_with(">0.2</text></g>"));
+
+        let reversed = Tolerance { direction_vector: Vector::new(-1.0, 0.0, 0.0), ..tolerance };
+        let mut svg = String::new();
+        render_tolerance(&reversed, None, r#"stroke="red""#, "red", 3, &mut svg);
+        assert!(svg.starts_with(r#"<g transform="translate(10.000 20.000) rotate(180.000)">"#));
+    }
+}
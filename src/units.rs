@@ -0,0 +1,33 @@
+use dxf::enums::Units;
+
+/// The length of one drawing unit in millimetres, or None for unitless drawings.
+pub(crate) fn millimeters_per_unit(units: Units) -> Option<f64> {
+    let millimeters = match units {
+        Units::Unitless => return None,
+        Units::Inches => 25.4,
+        Units::Feet => 304.8,
+        Units::Miles => 1_609_344.0,
+        Units::Millimeters => 1.0,
+        Units::Centimeters => 10.0,
+        Units::Meters => 1_000.0,
+        Units::Kilometers => 1_000_000.0,
+        Units::Microinches => 25.4e-6,
+        Units::Mils => 25.4e-3,
+        Units::Yards => 914.4,
+        Units::Angstroms => 1e-7,
+        Units::Nanometers => 1e-6,
+        Units::Microns => 1e-3,
+        Units::Decimeters => 100.0,
+        Units::Decameters => 10_000.0,
+        Units::Hectometers => 100_000.0,
+        Units::Gigameters => 1e12,
+        Units::AstronomicalUnits => 1.495_978_707e14,
+        Units::LightYears => 9.460_730_472_580_8e18,
+        Units::Parsecs => 3.085_677_581_491_367e19,
+        Units::USSurveyFeet => 1_200_000.0 / 3_937.0,
+        Units::USSurveyInch => 100_000.0 / 3_937.0,
+        Units::USSurveyYard => 3_600_000.0 / 3_937.0,
+        Units::USSurveyMile => 6_336_000_000.0 / 3_937.0,
+    };
+    Some(millimeters)
+}
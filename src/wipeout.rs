@@ -0,0 +1,125 @@
+use std::fmt::Write;
+
+use dxf::entities::Wipeout;
+
+use crate::emit::Points;
+use crate::Bounds;
+
+/**
+The outline of a WIPEOUT in the drawing. Its clipping vertices are in pixels from the top left corner of
+its image, which is one pixel along the U vector wide and one pixel up the V vector high, with the insertion
+point at its bottom left corner. Two vertices are the opposite corners of a rectangle, and a wipeout
+without any covers its whole image.
+*/
+pub(crate) fn outline(wipeout: &Wipeout) -> Vec<(f64, f64)> {
+    let (width, height) = (wipeout.image_size.x, wipeout.image_size.y);
+    let pixels = match wipeout.clipping_vertices.as_slice() {
+        [] => vec![(-0.5, -0.5), (width - 0.5, -0.5), (width - 0.5, height - 0.5), (-0.5, height - 0.5)],
+        [first, second] => vec![(first.x, first.y), (second.x, first.y), (second.x, second.y), (first.x, second.y)],
+        vertices => vertices.iter().map(|vertex| (vertex.x, vertex.y)).collect(),
+    };
+    let (u, v) = (&wipeout.u_vector, &wipeout.v_vector);
+    // pixel coordinates are pixel centers, so the image starts half a pixel before the first one
+    let origin_x = wipeout.location.x + 0.5 * (u.x - v.x);
+    let origin_y = wipeout.location.y + 0.5 * (u.y - v.y);
+    pixels
+        .into_iter()
+        .map(|(x, y)| {
+            let up = height - y;
+            (origin_x + u.x * x + v.x * up, origin_y + u.y * x + v.y * up)
+        })
+        .collect()
+}
+
+/// The outline of a WIPEOUT as a `<polygon>` filled with `fill`, covering whatever was drawn before it.
+pub(crate) fn render_wipeout(wipeout: &Wipeout, fill: &str, precision: usize, svg: &mut String) {
+    let outline = outline(wipeout);
+    if outline.len() < 3 {
+        return;
+    }
+    write!(
+        svg,
+        r#"<polygon points="{}" fill="{}" stroke="none" />"#,
+        Points(&outline, precision),
+        fill
+    ).unwrap();
+}
+
+/**
+Hides what was written to `svg` since `start` inside the outline of a WIPEOUT, by wrapping it in a group
+masked by the `<mask>` returned, which is white over the whole `view` and black inside the outline.
+Unlike a polygon in the background color, the masked area stays transparent.
+*/
+pub(crate) fn mask_behind(
+    wipeout: &Wipeout,
+    start: usize,
+    id: &str,
+    view: Bounds,
+    precision: usize,
+    svg: &mut String,
+) -> Option<String> {
+    let outline = outline(wipeout);
+    if outline.len() < 3 {
+        return None;
+    }
+    svg.insert_str(start, &format!(r#"<g mask="url(#{})">"#, id));
+    svg.push_str("</g>");
+    Some(format!(
+        concat!(
+            r#"<mask id="{id}" maskUnits="userSpaceOnUse" x="{x:.p$}" y="{y:.p$}" width="{width:.p$}" height="{height:.p$}">"#,
+            r#"<rect x="{x:.p$}" y="{y:.p$}" width="{width:.p$}" height="{height:.p$}" fill="white" />"#,
+            r#"<polygon points="{points}" fill="black" /></mask>"#
+        ),
+        id = id,
+        x = view.min_x,
+        y = view.min_y,
+        width = view.max_x - view.min_x,
+        height = view.max_y - view.min_y,
+        points = Points(&outline, precision),
+        p = precision
+    ))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dxf::{Point, Vector};
+
+    fn wipeout() -> Wipeout {
+        Wipeout {
+            location: Point::new(10.0, 20.0, 0.0),
+            u_vector: Vector::new(4.0, 0.0, 0.0),
+            v_vector: Vector::new(0.0, 2.0, 0.0),
+            image_size: Vector::new(1.0, 1.0, 0.0),
+            clipping_vertices: vec![
+                Point::new(-0.5, 0.5, 0.0),
+                Point::new(0.5, 0.5, 0.0),
+                Point::new(0.5, -0.5, 0.0),
+                Point::new(-0.5, -0.5, 0.0),
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_outline() {
+        assert_eq!(outline(&wipeout()), [(10.0, 20.0), (14.0, 20.0), (14.0, 22.0), (10.0, 22.0)]);
+    }
+
+    #[test]
+    fn test_mask_behind() {
+        let mut svg = String::from("<svg><line />");
+        let view = Bounds { min_x: 0.0, min_y: 0.0, max_x: 50.0, max_y: 40.0 };
+        let mask = mask_behind(&wipeout(), 5, "wipeout-1", view, 0, &mut svg);
+        assert_eq!(svg, r#"<svg><g mask="url(#wipeout-1)"><line /></g>"#);
+        assert_eq!(
+            mask.as_deref(),
+            Some(concat!(
+                r#"<mask id="wipeout-1" maskUnits="userSpaceOnUse" x="0" y="0" width="50" height="40">"#,
+                r#"<rect x="0" y="0" width="50" height="40" fill="white" />"#,
+                r#"<polygon points="10,20 14,20 14,22 10,22" fill="black" /></mask>"#
+            ))
+        );
+    }
+}